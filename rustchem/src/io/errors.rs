@@ -5,6 +5,27 @@ pub enum FileReadError {
     #[error("Error in line {line}")]
     LineParse { source: ParseError, line: usize },
 
+    #[error("Error in record {record}")]
+    RecordParse {
+        source: Box<FileReadError>,
+        record: usize,
+    },
+
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum FileWriteError {
+    #[error("{message}")]
+    Unsupported { message: String },
+
+    #[error(transparent)]
+    PropertyError(#[from] crate::mol::PropertyError),
+
+    #[error(transparent)]
+    MoleculeError(#[from] crate::mol::MoleculeError),
+
     #[error(transparent)]
     IOError(#[from] std::io::Error),
 }
@@ -34,6 +55,9 @@ pub enum ParseError {
     #[error("{message}")]
     UnexpectedTag { message: String },
 
+    #[error("{message} at position {position}")]
+    InvalidSyntax { message: String, position: usize },
+
     #[error(transparent)]
     MoleculeError(#[from] crate::mol::MoleculeError),
 }
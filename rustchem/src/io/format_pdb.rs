@@ -79,7 +79,7 @@ mod tests {
         let line =
             "ATOM      4  CA  ALA L   1B     13.000  21.098  20.348  1.00 20.50      A    C  ";
         let atom = parse_pdb_atom(&line)?;
-        assert_eq!(atom.element.atomic_number, 12);
+        assert_eq!(atom.element.atomic_number, 6);
         Ok(())
     }
 
@@ -0,0 +1,2004 @@
+use super::{FileWriteError, ParseError};
+use crate::mol::{
+    allowed_valences, canonical_ranks, kekulize, Atom, AtomIndex, AtomProperty, Bond, BondIndex,
+    BondProperty, BondType, DoubleBondConfiguration, DoubleBondStereo, Element, HasProperties,
+    Isotope, Molecule, StereoGroup, StereoGroupKind, TetrahedralParity, TetrahedralStereo,
+};
+use std::collections::HashMap;
+
+// Reference: http://opensmiles.org/opensmiles.html
+
+/// Reads a molecule from a SMILES string.
+///
+/// Bracket atoms have their hydrogen count set explicitly, while atoms from the organic subset
+/// leave it unset so that their implicit hydrogens follow from their normal valences.
+/// Tetrahedral centres ('@' and '@@') and bond directions around double bonds are kept both as
+/// properties and in the molecule's stereochemistry.
+/// The SMILES ends at the first whitespace. Stereo groups are read from a CXSMILES extension
+/// ('|...|') that follows it, and anything else there (such as a name) is ignored.
+/// Error positions are character offsets into the string, starting from 0.
+pub fn read_smiles(smiles: &str) -> Result<Molecule, ParseError> {
+    SmilesParser::new(smiles).parse()
+}
+
+struct RingBond {
+    atom: AtomIndex,
+    bond_symbol: Option<char>,
+    position: usize,
+    // Place of the ring bond among the atom's neighbours
+    slot: usize,
+}
+
+// Stands in for the ring closure atom in the neighbour order until the ring bond is closed
+const RING_BOND_SLOT: AtomIndex = AtomIndex::MAX;
+
+struct SmilesParser {
+    chars: Vec<char>,
+    position: usize,
+    atoms: Vec<Atom>,
+    bonds: Vec<Bond>,
+    previous_atom: Option<AtomIndex>,
+    // Bond symbol (or '.') waiting for the next atom, with its position
+    pending_bond: Option<(char, usize)>,
+    // Atoms that open branches, with the position of their '('
+    branches: Vec<(AtomIndex, usize)>,
+    ring_bonds: HashMap<u32, RingBond>,
+    // Neighbours of each atom in the order they are written, where an atom stands for its own
+    // implicit hydrogen or lone pair
+    neighbor_order: Vec<Vec<AtomIndex>>,
+}
+
+impl SmilesParser {
+    fn new(smiles: &str) -> SmilesParser {
+        SmilesParser {
+            chars: smiles.chars().collect(),
+            position: 0,
+            atoms: Vec::new(),
+            bonds: Vec::new(),
+            previous_atom: None,
+            pending_bond: None,
+            branches: Vec::new(),
+            ring_bonds: HashMap::new(),
+            neighbor_order: Vec::new(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Molecule, ParseError> {
+        while let Some(c) = self.peek() {
+            match c {
+                c if c.is_whitespace() => break,
+                '(' => self.open_branch()?,
+                ')' => self.close_branch()?,
+                '-' | '=' | '#' | '$' | ':' | '/' | '\\' | '.' => self.bond_symbol(c)?,
+                '0'..='9' | '%' => self.ring_bond()?,
+                '[' => {
+                    let atom = self.bracket_atom()?;
+                    self.add_atom(atom);
+                }
+                _ => {
+                    let atom = self.organic_atom()?;
+                    self.add_atom(atom);
+                }
+            }
+        }
+
+        if let Some((_, position)) = self.pending_bond {
+            return Err(syntax_error("Bond without a following atom", position));
+        }
+        if let Some((_, position)) = self.branches.last() {
+            return Err(syntax_error("Unclosed branch", *position));
+        }
+        if let Some(position) = self.ring_bonds.values().map(|ring| ring.position).min() {
+            return Err(syntax_error("Unclosed ring bond", position));
+        }
+
+        let groups = self.stereo_groups()?;
+        let tetrahedral = self.tetrahedral_stereo();
+        let double_bonds = self.double_bond_stereo();
+        let mut molecule = Molecule::from_graph(self.atoms, self.bonds);
+        molecule.stereo_mut().tetrahedral = tetrahedral;
+        molecule.stereo_mut().double_bonds = double_bonds;
+        molecule.stereo_mut().groups = groups;
+        Ok(molecule)
+    }
+
+    fn stereo_groups(&mut self) -> Result<Vec<StereoGroup>, ParseError> {
+        /*
+        CXSMILES extension: ' |field,field,...|', of which only stereo groups are read
+        a:i,j,... = atoms with absolute configurations
+        on:i,j,... = atoms in OR group n
+        &n:i,j,... = atoms in AND group n
+        Atom indices start from 0, in the order the atoms are written.
+        */
+
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+        if self.peek() != Some('|') {
+            return Ok(Vec::new());
+        }
+        let start = self.position;
+        let end = match self.chars[start + 1..].iter().position(|c| *c == '|') {
+            Some(offset) => start + 1 + offset,
+            None => return Err(syntax_error("Unclosed CXSMILES extension", start)),
+        };
+
+        // Fields with the same label are merged into one group
+        let mut groups: Vec<StereoGroup> = Vec::new();
+        let mut current_group = None;
+        let mut token_start = start + 1;
+        while token_start < end {
+            let token_end = self.chars[token_start..end]
+                .iter()
+                .position(|c| *c == ',')
+                .map_or(end, |offset| token_start + offset);
+            let token = self.chars[token_start..token_end]
+                .iter()
+                .collect::<String>();
+
+            let value = match token.find(':') {
+                Some(index) => {
+                    let label = &token[..index];
+                    let number = match label.get(1..) {
+                        Some(digits) if digits.chars().all(|c| c.is_ascii_digit()) => {
+                            digits.parse::<u32>().ok()
+                        }
+                        _ => None,
+                    };
+                    let kind = match (label.chars().next(), number) {
+                        (Some('a'), None) if label.len() == 1 => Some(StereoGroupKind::Absolute),
+                        (Some('o'), Some(_)) => Some(StereoGroupKind::Or),
+                        (Some('&'), Some(_)) => Some(StereoGroupKind::And),
+                        _ => None,
+                    };
+                    current_group = kind.map(|kind| {
+                        let number = number.unwrap_or(0);
+                        match groups
+                            .iter()
+                            .position(|group| group.kind == kind && group.number == number)
+                        {
+                            Some(group) => group,
+                            None => {
+                                groups.push(StereoGroup {
+                                    kind,
+                                    number,
+                                    atoms: Vec::new(),
+                                });
+                                groups.len() - 1
+                            }
+                        }
+                    });
+                    &token[index + 1..]
+                }
+                // Other fields continue until the next label
+                None if !token.chars().all(|c| c.is_ascii_digit()) => {
+                    current_group = None;
+                    &token[..]
+                }
+                None => &token[..],
+            };
+
+            if let Some(group) = current_group {
+                match value.parse::<usize>() {
+                    Ok(atom) if atom < self.atoms.len() => groups[group].atoms.push(atom),
+                    _ => {
+                        return Err(syntax_error(
+                            &format!("Invalid stereo group atom '{}'", value),
+                            token_start,
+                        ))
+                    }
+                }
+            }
+            token_start = token_end + 1;
+        }
+
+        self.position = end + 1;
+        Ok(groups)
+    }
+
+    fn tetrahedral_stereo(&self) -> Vec<TetrahedralStereo> {
+        let mut tetrahedral = Vec::new();
+        for (center, atom) in self.atoms.iter().enumerate() {
+            let parity = match atom.get_property_string(&AtomProperty::Chirality) {
+                Ok(Some("@")) | Ok(Some("@TH1")) => TetrahedralParity::Anticlockwise,
+                Ok(Some("@@")) | Ok(Some("@TH2")) => TetrahedralParity::Clockwise,
+                _ => continue,
+            };
+
+            // The centre was added for an implicit hydrogen or lone pair, and is only needed
+            // when there are three other neighbours
+            let mut neighbors = self.neighbor_order[center].clone();
+            if neighbors.len() == 5 {
+                neighbors.retain(|neighbor| *neighbor != center);
+            }
+            if neighbors.len() == 4 {
+                tetrahedral.push(TetrahedralStereo {
+                    center,
+                    neighbors: [neighbors[0], neighbors[1], neighbors[2], neighbors[3]],
+                    parity,
+                });
+            }
+        }
+        tetrahedral
+    }
+
+    fn double_bond_stereo(&self) -> Vec<DoubleBondStereo> {
+        // The direction of a bond written from `atom`, as '/' or '\'
+        let direction_from = |atom: AtomIndex, bond: &Bond| {
+            let direction = bond.get_property::<char>(&BondProperty::Direction).ok()??;
+            match (bond.from_atom_id == atom, direction) {
+                (true, direction) => Some(direction),
+                (false, '/') => Some('\\'),
+                (false, _) => Some('/'),
+            }
+        };
+        let directional_neighbor = |atom: AtomIndex, double_bond: BondIndex| {
+            self.bonds
+                .iter()
+                .enumerate()
+                .filter(|(index, bond)| {
+                    *index != double_bond && (bond.from_atom_id == atom || bond.to_atom_id == atom)
+                })
+                .find_map(|(_, bond)| {
+                    let neighbor = if bond.from_atom_id == atom {
+                        bond.to_atom_id
+                    } else {
+                        bond.from_atom_id
+                    };
+                    Some((neighbor, direction_from(neighbor, bond)?))
+                })
+        };
+
+        let mut double_bonds = Vec::new();
+        for (index, bond) in self.bonds.iter().enumerate() {
+            if bond.bond_type != BondType::double() {
+                continue;
+            }
+
+            // Directions are taken as written into the double bond at its first atom, and out of
+            // it at its second, so that 'F/C=C/F' has matching directions and is trans
+            let first = directional_neighbor(bond.from_atom_id, index);
+            let second = directional_neighbor(bond.to_atom_id, index);
+            if let (Some((first, into)), Some((second, out_of))) = (first, second) {
+                let out_of = if out_of == '/' { '\\' } else { '/' };
+                let configuration = if into == out_of {
+                    DoubleBondConfiguration::Trans
+                } else {
+                    DoubleBondConfiguration::Cis
+                };
+                double_bonds.push(DoubleBondStereo {
+                    bond: index,
+                    neighbors: [first, second],
+                    configuration,
+                });
+            }
+        }
+        double_bonds
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn unexpected_character(&self) -> ParseError {
+        match self.peek() {
+            Some(c) => syntax_error(&format!("Unexpected character '{}'", c), self.position),
+            None => syntax_error("Unexpected end of SMILES", self.position),
+        }
+    }
+
+    fn read_digits(&mut self, max_digits: usize) -> Option<u32> {
+        let start = self.position;
+        while self.position - start < max_digits && self.peek().is_some_and(|c| c.is_ascii_digit())
+        {
+            self.position += 1;
+        }
+
+        let digits = self.chars[start..self.position].iter().collect::<String>();
+        digits.parse::<u32>().ok()
+    }
+
+    fn open_branch(&mut self) -> Result<(), ParseError> {
+        let previous_atom = match (self.previous_atom, self.pending_bond) {
+            (Some(previous_atom), None) => previous_atom,
+            _ => return Err(self.unexpected_character()),
+        };
+        self.branches.push((previous_atom, self.position));
+        self.position += 1;
+
+        if self.peek() == Some(')') {
+            return Err(syntax_error("Empty branch", self.position));
+        }
+        Ok(())
+    }
+
+    fn close_branch(&mut self) -> Result<(), ParseError> {
+        if let Some((_, position)) = self.pending_bond {
+            return Err(syntax_error("Bond without a following atom", position));
+        }
+        match self.branches.pop() {
+            Some((atom, _)) => self.previous_atom = Some(atom),
+            None => return Err(syntax_error("Unmatched ')'", self.position)),
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn bond_symbol(&mut self, c: char) -> Result<(), ParseError> {
+        if self.previous_atom.is_none() {
+            return Err(syntax_error("Bond without a preceding atom", self.position));
+        }
+        if self.pending_bond.is_some() {
+            return Err(self.unexpected_character());
+        }
+        self.pending_bond = Some((c, self.position));
+        self.position += 1;
+        Ok(())
+    }
+
+    fn ring_bond(&mut self) -> Result<(), ParseError> {
+        let position = self.position;
+        let atom = match self.previous_atom {
+            Some(atom) => atom,
+            None => return Err(syntax_error("Ring bond without a preceding atom", position)),
+        };
+
+        let ring_number = if self.peek() == Some('%') {
+            self.position += 1;
+            match (self.peek(), self.peek_at(1)) {
+                (Some(a), Some(b)) if a.is_ascii_digit() && b.is_ascii_digit() => {
+                    self.read_digits(2)
+                }
+                _ => None,
+            }
+        } else {
+            self.read_digits(1)
+        };
+        let ring_number = match ring_number {
+            Some(ring_number) => ring_number,
+            None => return Err(syntax_error("Expected two digits after '%'", position)),
+        };
+
+        let bond_symbol = match self.pending_bond.take() {
+            Some(('.', position)) => {
+                return Err(syntax_error("Ring bond cannot follow '.'", position))
+            }
+            Some((symbol, _)) => Some(symbol),
+            None => None,
+        };
+
+        match self.ring_bonds.remove(&ring_number) {
+            Some(ring_bond) => {
+                self.neighbor_order[ring_bond.atom][ring_bond.slot] = atom;
+                self.neighbor_order[atom].push(ring_bond.atom);
+                if ring_bond.atom == atom {
+                    return Err(syntax_error("Ring bond to the same atom", position));
+                }
+                if self.bonds.iter().any(|bond| {
+                    (bond.from_atom_id == ring_bond.atom && bond.to_atom_id == atom)
+                        || (bond.from_atom_id == atom && bond.to_atom_id == ring_bond.atom)
+                }) {
+                    return Err(syntax_error("Duplicate bond", position));
+                }
+
+                // The closing symbol is written from this atom, so directions are reversed
+                let closing_symbol = bond_symbol.map(|symbol| match symbol {
+                    '/' => '\\',
+                    '\\' => '/',
+                    _ => symbol,
+                });
+                let symbol = match (ring_bond.bond_symbol, closing_symbol) {
+                    (Some(opening), Some(closing)) if opening != closing => {
+                        return Err(syntax_error("Conflicting ring bond symbols", position))
+                    }
+                    (opening, closing) => opening.or(closing),
+                };
+
+                let bond = self.make_bond(ring_bond.atom, atom, symbol);
+                self.bonds.push(bond);
+            }
+            None => {
+                let slot = self.neighbor_order[atom].len();
+                self.neighbor_order[atom].push(RING_BOND_SLOT);
+                self.ring_bonds.insert(
+                    ring_number,
+                    RingBond {
+                        atom,
+                        bond_symbol,
+                        position,
+                        slot,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn organic_atom(&mut self) -> Result<Atom, ParseError> {
+        /*
+        Organic subset: 'B', 'C', 'N', 'O', 'P', 'S', 'F', 'Cl', 'Br', 'I', '*'
+        Aromatic organic subset: 'b', 'c', 'n', 'o', 'p', 's'
+        */
+
+        let (symbol, aromatic, length) = match (self.peek(), self.peek_at(1)) {
+            (Some('*'), _) => ("*".to_string(), false, 1),
+            (Some('C'), Some('l')) => ("Cl".to_string(), false, 2),
+            (Some('B'), Some('r')) => ("Br".to_string(), false, 2),
+            (Some(c @ 'B'), _)
+            | (Some(c @ 'C'), _)
+            | (Some(c @ 'N'), _)
+            | (Some(c @ 'O'), _)
+            | (Some(c @ 'P'), _)
+            | (Some(c @ 'S'), _)
+            | (Some(c @ 'F'), _)
+            | (Some(c @ 'I'), _) => (c.to_string(), false, 1),
+            (Some(c @ 'b'), _)
+            | (Some(c @ 'c'), _)
+            | (Some(c @ 'n'), _)
+            | (Some(c @ 'o'), _)
+            | (Some(c @ 'p'), _)
+            | (Some(c @ 's'), _) => (c.to_ascii_uppercase().to_string(), true, 1),
+            _ => return Err(self.unexpected_character()),
+        };
+        self.position += length;
+
+        let mut atom = Atom::from_symbol(&symbol)?;
+        atom.aromatic = aromatic;
+        Ok(atom)
+    }
+
+    fn bracket_atom(&mut self) -> Result<Atom, ParseError> {
+        /*
+        Bracket Atom: '[' isotope? symbol chiral? hcount? charge? class? ']'
+
+        isotope = mass number
+        symbol = element symbol, aromatic symbol ('b', 'c', 'n', 'o', 'p', 's', 'se', 'as') or '*'
+        chiral = '@', '@@', '@TH1'..'@TH2', '@AL1'..'@AL2', '@SP1'..'@SP3', '@TB1'..'@TB20', '@OH1'..'@OH30'
+        hcount = 'H' followed by an optional count
+        charge = '+' or '-' followed by an optional count (up to 15), or '++'/'--'
+        class = ':' followed by an atom class number
+        */
+
+        self.position += 1;
+
+        let isotope_position = self.position;
+        let isotope = self.read_digits(usize::MAX);
+
+        let symbol_position = self.position;
+        let (symbol, aromatic) = self.bracket_symbol()?;
+        let mut atom = Atom::from_symbol(&symbol).map_err(|_| {
+            syntax_error(
+                &format!("Unknown element symbol '{}'", symbol),
+                symbol_position,
+            )
+        })?;
+        atom.aromatic = aromatic;
+
+        if let Some(mass_number) = isotope {
+            let isotope = Isotope::lookup(atom.element, mass_number)
+                .map_err(|err| syntax_error(&err.to_string(), isotope_position))?;
+            atom.isotope = Some(isotope.mass_number);
+        }
+
+        if self.peek() == Some('@') {
+            let chirality = self.chirality()?;
+            atom.set_property(AtomProperty::Chirality, chirality);
+        }
+
+        atom.hydrogen_count = Some(0);
+        if self.peek() == Some('H') {
+            self.position += 1;
+            atom.hydrogen_count = Some(self.read_digits(1).unwrap_or(1));
+        }
+
+        if let Some(sign @ '+') | Some(sign @ '-') = self.peek() {
+            let charge_position = self.position;
+            self.position += 1;
+
+            let magnitude = if self.peek() == Some(sign) {
+                self.position += 1;
+                2
+            } else {
+                self.read_digits(2).unwrap_or(1) as i32
+            };
+            if magnitude > 15 {
+                return Err(syntax_error("Charge out of range", charge_position));
+            }
+            atom.formal_charge = if sign == '+' { magnitude } else { -magnitude };
+        }
+
+        if self.peek() == Some(':') {
+            self.position += 1;
+            match self.read_digits(usize::MAX) {
+                Some(atom_class) => atom.set_property(AtomProperty::AtomClass, atom_class),
+                None => return Err(syntax_error("Expected an atom class", self.position)),
+            }
+        }
+
+        if self.peek() != Some(']') {
+            return Err(self.unexpected_character());
+        }
+        self.position += 1;
+
+        Ok(atom)
+    }
+
+    fn bracket_symbol(&mut self) -> Result<(String, bool), ParseError> {
+        let (symbol, aromatic) = match (self.peek(), self.peek_at(1)) {
+            (Some('*'), _) => ("*".to_string(), false),
+            (Some('s'), Some('e')) => ("Se".to_string(), true),
+            (Some('a'), Some('s')) => ("As".to_string(), true),
+            (Some(c @ 'b'), _)
+            | (Some(c @ 'c'), _)
+            | (Some(c @ 'n'), _)
+            | (Some(c @ 'o'), _)
+            | (Some(c @ 'p'), _)
+            | (Some(c @ 's'), _) => (c.to_ascii_uppercase().to_string(), true),
+            (Some(first), Some(second))
+                if first.is_ascii_uppercase()
+                    && second.is_ascii_lowercase()
+                    && Element::from_symbol(&format!("{}{}", first, second)).is_ok() =>
+            {
+                (format!("{}{}", first, second), false)
+            }
+            (Some(first), _) if first.is_ascii_uppercase() => (first.to_string(), false),
+            _ => return Err(self.unexpected_character()),
+        };
+
+        // Aromatic symbols are written in lower case, so their length matches the element symbol
+        self.position += symbol.len();
+        Ok((symbol, aromatic))
+    }
+
+    fn chirality(&mut self) -> Result<String, ParseError> {
+        let position = self.position;
+        self.position += 1;
+
+        if self.peek() == Some('@') {
+            self.position += 1;
+            return Ok("@@".to_string());
+        }
+
+        let chiral_class = match (self.peek(), self.peek_at(1)) {
+            (Some(first), Some(second)) => format!("{}{}", first, second),
+            _ => return Ok("@".to_string()),
+        };
+        let max_number = match chiral_class.as_str() {
+            "TH" | "AL" => 2,
+            "SP" => 3,
+            "TB" => 20,
+            "OH" => 30,
+            _ => return Ok("@".to_string()),
+        };
+        self.position += 2;
+
+        match self.read_digits(2) {
+            Some(number) if (1..=max_number).contains(&number) => {
+                Ok(format!("@{}{}", chiral_class, number))
+            }
+            _ => Err(syntax_error("Invalid chirality", position)),
+        }
+    }
+
+    fn add_atom(&mut self, atom: Atom) {
+        let index = self.atoms.len();
+        let is_chiral = atom.properties.contains_key(&AtomProperty::Chirality);
+        self.atoms.push(atom);
+        self.neighbor_order.push(Vec::new());
+
+        if let Some(previous_atom) = self.previous_atom {
+            let symbol = match self.pending_bond.take() {
+                Some(('.', _)) => None,
+                Some((symbol, _)) => Some(Some(symbol)),
+                None => Some(None),
+            };
+            if let Some(symbol) = symbol {
+                let bond = self.make_bond(previous_atom, index, symbol);
+                self.bonds.push(bond);
+                self.neighbor_order[previous_atom].push(index);
+                self.neighbor_order[index].push(previous_atom);
+            }
+        }
+
+        // An implicit hydrogen (or lone pair) comes straight after the preceding atom
+        if is_chiral {
+            self.neighbor_order[index].push(index);
+        }
+        self.previous_atom = Some(index);
+    }
+
+    fn make_bond(&self, from: AtomIndex, to: AtomIndex, symbol: Option<char>) -> Bond {
+        let bond_type = match symbol {
+            Some('=') => BondType::double(),
+            Some('#') => BondType::triple(),
+            Some('$') => BondType::Covalent(4),
+            Some(':') => BondType::Aromatic,
+            Some(_) => BondType::single(),
+            // Implicit bonds between aromatic atoms are aromatic, all others are single
+            None if self.atoms[from].aromatic && self.atoms[to].aromatic => BondType::Aromatic,
+            None => BondType::single(),
+        };
+
+        let mut bond = Bond::new(from, to, bond_type);
+        bond.aromatic = bond.bond_type == BondType::Aromatic;
+        if let Some(direction @ '/') | Some(direction @ '\\') = symbol {
+            bond.set_property(BondProperty::Direction, direction);
+        }
+        bond
+    }
+}
+
+fn syntax_error(message: &str, position: usize) -> ParseError {
+    ParseError::InvalidSyntax {
+        message: message.to_string(),
+        position,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SmilesOptions {
+    /// Write atoms in a canonical order, so that the same structure always gives the same string
+    pub canonical: bool,
+    /// Write tetrahedral centres ('@' and '@@') and the configurations of double bonds, as bond
+    /// directions ('/' and '\')
+    pub stereo: bool,
+    pub isotopes: bool,
+    /// Write aromatic atoms in lower case with implicit aromatic bonds, rather than in Kekulé form
+    pub aromatic: bool,
+    /// Write the stereo groups of atoms written with '@' or '@@' in a CXSMILES extension after the
+    /// SMILES (e.g. ' |a:1,o1:3|')
+    pub stereo_groups: bool,
+}
+
+impl Default for SmilesOptions {
+    fn default() -> Self {
+        SmilesOptions {
+            canonical: false,
+            stereo: true,
+            isotopes: true,
+            aromatic: true,
+            stereo_groups: false,
+        }
+    }
+}
+
+/// Writes a molecule as a SMILES string.
+///
+/// Atoms without an explicit hydrogen count are given the hydrogens implied by their normal
+/// valences, and are written without brackets where the organic subset allows.
+pub fn write_smiles(molecule: &Molecule, options: SmilesOptions) -> Result<String, FileWriteError> {
+    let has_aromatic_bonds = molecule
+        .bonds()
+        .iter()
+        .any(|bond| bond.bond_type == BondType::Aromatic);
+    if has_aromatic_bonds && !options.aromatic {
+        let mut molecule = molecule.clone();
+        kekulize(&mut molecule)?;
+        return SmilesWriter::new(&molecule, &options)?.write();
+    }
+
+    SmilesWriter::new(molecule, &options)?.write()
+}
+
+enum WriteStep {
+    Atom {
+        atom: AtomIndex,
+        from: Option<(AtomIndex, BondIndex)>,
+    },
+    OpenBranch,
+    CloseBranch,
+}
+
+struct SmilesWriter<'a> {
+    molecule: &'a Molecule,
+    options: &'a SmilesOptions,
+    // Neighbouring atoms of each atom, with the index of the bond to them
+    neighbors: Vec<Vec<(AtomIndex, BondIndex)>>,
+    // Whether each atom is written with a lower case (aromatic) symbol
+    aromatic: Vec<bool>,
+    hydrogen_counts: Vec<u32>,
+}
+
+impl<'a> SmilesWriter<'a> {
+    fn new(
+        molecule: &'a Molecule,
+        options: &'a SmilesOptions,
+    ) -> Result<SmilesWriter<'a>, FileWriteError> {
+        let num_atoms = molecule.atoms().len();
+        for bond in molecule.bonds() {
+            if bond.from_atom_id >= num_atoms || bond.to_atom_id >= num_atoms {
+                return Err(unsupported("Bond refers to an atom that does not exist"));
+            }
+        }
+        for group in &molecule.stereo().groups {
+            if group.atoms.iter().any(|atom| *atom >= num_atoms) {
+                return Err(unsupported(
+                    "Stereo group refers to an atom that does not exist",
+                ));
+            }
+        }
+        let neighbors = (0..num_atoms)
+            .map(|index| molecule.neighbor_bonds(index).collect())
+            .collect::<Vec<Vec<(AtomIndex, BondIndex)>>>();
+
+        let aromatic = (0..num_atoms)
+            .map(|index| {
+                let atom = &molecule.atoms()[index];
+                let has_aromatic_bond = neighbors[index]
+                    .iter()
+                    .any(|(_, bond)| molecule.bonds()[*bond].bond_type == BondType::Aromatic);
+                options.aromatic
+                    && (atom.aromatic || has_aromatic_bond)
+                    && AROMATIC_SYMBOLS.contains(&atom.element.symbol.as_str())
+            })
+            .collect::<Vec<bool>>();
+
+        let hydrogen_counts = (0..num_atoms)
+            .map(|index| molecule.implicit_hydrogen_count(index))
+            .collect();
+
+        Ok(SmilesWriter {
+            molecule,
+            options,
+            neighbors,
+            aromatic,
+            hydrogen_counts,
+        })
+    }
+
+    fn write(&self) -> Result<String, FileWriteError> {
+        let num_atoms = self.molecule.atoms().len();
+        let ranks = match (self.options.canonical, self.options.isotopes) {
+            (false, _) => (0..num_atoms).collect(),
+            (true, true) => canonical_ranks(self.molecule),
+            // Isotopes that are not written must not change the order either
+            (true, false) => {
+                let mut molecule = self.molecule.clone();
+                for atom in molecule.atoms_mut() {
+                    atom.isotope = None;
+                }
+                canonical_ranks(&molecule)
+            }
+        };
+
+        let mut neighbors = self.neighbors.clone();
+        for atom_neighbors in &mut neighbors {
+            atom_neighbors.sort_by_key(|(neighbor, _)| ranks[*neighbor]);
+        }
+
+        // Build a depth-first spanning tree, recording the remaining bonds as ring bonds
+        let mut visited = vec![false; num_atoms];
+        let mut bond_used = vec![false; self.molecule.bonds().len()];
+        let mut children: Vec<Vec<(AtomIndex, BondIndex)>> = vec![Vec::new(); num_atoms];
+        let mut ring_bonds: Vec<Vec<usize>> = vec![Vec::new(); num_atoms];
+        let mut roots = Vec::new();
+
+        let mut atom_order = (0..num_atoms).collect::<Vec<AtomIndex>>();
+        atom_order.sort_by_key(|atom| ranks[*atom]);
+
+        for root in atom_order {
+            if visited[root] {
+                continue;
+            }
+            roots.push(root);
+            visited[root] = true;
+
+            let mut stack: Vec<(AtomIndex, Option<usize>, usize)> = vec![(root, None, 0)];
+            while let Some(top) = stack.last_mut() {
+                let (atom, parent_bond, cursor) = *top;
+                if cursor >= neighbors[atom].len() {
+                    stack.pop();
+                    continue;
+                }
+                top.2 += 1;
+
+                let (neighbor, bond) = neighbors[atom][cursor];
+                if Some(bond) == parent_bond || bond_used[bond] {
+                    continue;
+                }
+                bond_used[bond] = true;
+
+                if visited[neighbor] {
+                    ring_bonds[neighbor].push(bond);
+                    ring_bonds[atom].push(bond);
+                } else {
+                    visited[neighbor] = true;
+                    children[atom].push((neighbor, bond));
+                    stack.push((neighbor, Some(bond), 0));
+                }
+            }
+        }
+
+        let directions = self.bond_directions(&roots, &children);
+
+        let mut output = String::new();
+        let mut written_atoms = Vec::with_capacity(num_atoms);
+        let mut written_chiral = vec![false; num_atoms];
+        let mut ring_digits: HashMap<usize, u32> = HashMap::new();
+        let mut digits_in_use = [false; 100];
+
+        for (index, root) in roots.into_iter().enumerate() {
+            if index > 0 {
+                output.push('.');
+            }
+
+            let mut steps = vec![WriteStep::Atom {
+                atom: root,
+                from: None,
+            }];
+            while let Some(step) = steps.pop() {
+                let (atom, from) = match step {
+                    WriteStep::OpenBranch => {
+                        output.push('(');
+                        continue;
+                    }
+                    WriteStep::CloseBranch => {
+                        output.push(')');
+                        continue;
+                    }
+                    WriteStep::Atom { atom, from } => (atom, from),
+                };
+
+                if let Some((previous_atom, bond)) = from {
+                    output.push_str(&self.bond_symbol(bond, previous_atom, atom, &directions)?);
+                }
+                let parity = self.written_parity(atom, from, &ring_bonds[atom], &children[atom]);
+                output.push_str(&self.atom_symbol(atom, parity)?);
+                written_atoms.push(atom);
+                written_chiral[atom] = parity.is_some();
+
+                // Digits are freed once the atom is written, so they are not reopened straight away
+                let mut closed_digits = Vec::new();
+                for bond in &ring_bonds[atom] {
+                    match ring_digits.remove(bond) {
+                        Some(digit) => {
+                            output.push_str(&format_ring_digit(digit));
+                            closed_digits.push(digit);
+                        }
+                        None => {
+                            let digit = match (1..100).find(|digit| !digits_in_use[*digit]) {
+                                Some(digit) => digit,
+                                None => return Err(unsupported("Too many open ring bonds")),
+                            };
+                            digits_in_use[digit] = true;
+                            ring_digits.insert(*bond, digit as u32);
+
+                            let other_atom = self.other_atom(*bond, atom);
+                            output.push_str(&self.bond_symbol(
+                                *bond,
+                                atom,
+                                other_atom,
+                                &directions,
+                            )?);
+                            output.push_str(&format_ring_digit(digit as u32));
+                        }
+                    }
+                }
+                for digit in closed_digits {
+                    digits_in_use[digit as usize] = false;
+                }
+
+                // All but the last child are written as branches
+                if let Some((last_child, bonds)) = children[atom].split_last() {
+                    steps.push(WriteStep::Atom {
+                        atom: last_child.0,
+                        from: Some((atom, last_child.1)),
+                    });
+                    for (child, bond) in bonds.iter().rev() {
+                        steps.push(WriteStep::CloseBranch);
+                        steps.push(WriteStep::Atom {
+                            atom: *child,
+                            from: Some((atom, *bond)),
+                        });
+                        steps.push(WriteStep::OpenBranch);
+                    }
+                }
+            }
+        }
+
+        if self.options.stereo_groups && !self.molecule.stereo().groups.is_empty() {
+            output.push_str(&self.stereo_group_extension(&written_atoms, &written_chiral));
+        }
+
+        Ok(output)
+    }
+
+    fn stereo_group_extension(
+        &self,
+        written_atoms: &[AtomIndex],
+        written_chiral: &[bool],
+    ) -> String {
+        // Atoms are numbered in the order they are written, and only those written with '@' or
+        // '@@' are kept in their groups. Canonical SMILES number the groups of each kind in order
+        // of their atoms, so that they give canonical extensions; otherwise groups keep their
+        // numbers.
+        let mut positions = vec![0; written_atoms.len()];
+        for (position, atom) in written_atoms.iter().enumerate() {
+            positions[*atom] = position;
+        }
+
+        let mut absolute = Vec::new();
+        let mut or_groups = Vec::new();
+        let mut and_groups = Vec::new();
+        for group in &self.molecule.stereo().groups {
+            let mut atoms = group
+                .atoms
+                .iter()
+                .filter(|atom| written_chiral[**atom])
+                .map(|atom| positions[*atom])
+                .collect::<Vec<usize>>();
+            if atoms.is_empty() {
+                continue;
+            }
+            atoms.sort_unstable();
+            let number = if self.options.canonical {
+                0
+            } else {
+                group.number
+            };
+            match group.kind {
+                StereoGroupKind::Absolute => absolute.extend(atoms),
+                StereoGroupKind::Or => or_groups.push((number, atoms)),
+                StereoGroupKind::And => and_groups.push((number, atoms)),
+            }
+        }
+        absolute.sort_unstable();
+        or_groups.sort();
+        and_groups.sort();
+
+        let atom_list = |atoms: &[usize]| {
+            atoms
+                .iter()
+                .map(|atom| atom.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let mut fields = Vec::new();
+        if !absolute.is_empty() {
+            fields.push(format!("a:{}", atom_list(&absolute)));
+        }
+        for (prefix, groups) in &[("o", or_groups), ("&", and_groups)] {
+            for (index, (number, atoms)) in groups.iter().enumerate() {
+                let number = if self.options.canonical {
+                    index as u32 + 1
+                } else {
+                    *number
+                };
+                fields.push(format!("{}{}:{}", prefix, number, atom_list(atoms)));
+            }
+        }
+
+        if fields.is_empty() {
+            String::new()
+        } else {
+            format!(" |{}|", fields.join(","))
+        }
+    }
+
+    fn other_atom(&self, bond: usize, atom: AtomIndex) -> AtomIndex {
+        let bond = &self.molecule.bonds()[bond];
+        if bond.from_atom_id == atom {
+            bond.to_atom_id
+        } else {
+            bond.from_atom_id
+        }
+    }
+
+    // The hydrogens a reader gives the atom when it is written without brackets
+    fn default_hydrogen_count(&self, atom: AtomIndex) -> u32 {
+        let bond_order_sum = self.bond_order_sum(atom);
+        allowed_valences(self.molecule.atoms()[atom].element, 0)
+            .iter()
+            .find(|valence| **valence >= bond_order_sum)
+            .map_or(0, |valence| valence - bond_order_sum)
+    }
+
+    fn bond_order_sum(&self, atom: AtomIndex) -> u32 {
+        // Aromatic bonds count as single bonds, with one more added for the aromatic atom itself
+        let bond_order_sum: u32 = self.neighbors[atom]
+            .iter()
+            .map(|(_, bond)| match self.molecule.bonds()[*bond].bond_type {
+                BondType::Covalent(order) if order > 0 => order as u32,
+                _ => 1,
+            })
+            .sum();
+
+        if self.aromatic[atom] {
+            bond_order_sum + 1
+        } else {
+            bond_order_sum
+        }
+    }
+
+    // The parity of a tetrahedral centre relative to the order its neighbours are written: the
+    // previous atom, then the implicit hydrogen or lone pair, ring bonds and branches
+    fn written_parity(
+        &self,
+        atom: AtomIndex,
+        from: Option<(AtomIndex, BondIndex)>,
+        ring_bonds: &[BondIndex],
+        children: &[(AtomIndex, BondIndex)],
+    ) -> Option<TetrahedralParity> {
+        if !self.options.stereo {
+            return None;
+        }
+        let stereo = self.molecule.stereo().tetrahedral_at(atom)?;
+
+        let mut neighbors = Vec::with_capacity(4);
+        if let Some((previous_atom, _)) = from {
+            neighbors.push(previous_atom);
+        }
+        if stereo.neighbors.contains(&atom) {
+            neighbors.push(atom);
+        }
+        neighbors.extend(ring_bonds.iter().map(|bond| self.other_atom(*bond, atom)));
+        neighbors.extend(children.iter().map(|(child, _)| *child));
+
+        match neighbors.as_slice() {
+            [first, second, third, fourth] => {
+                stereo.parity_for(&[*first, *second, *third, *fourth])
+            }
+            _ => None,
+        }
+    }
+
+    fn atom_symbol(
+        &self,
+        index: AtomIndex,
+        parity: Option<TetrahedralParity>,
+    ) -> Result<String, FileWriteError> {
+        let atom = &self.molecule.atoms()[index];
+
+        if atom.properties.contains_key(&AtomProperty::AtomList) {
+            return Err(unsupported("Atom lists cannot be written to SMILES"));
+        }
+        let wildcard = atom.element.is_dummy();
+        let symbol = if wildcard {
+            "*".to_string()
+        } else if self.aromatic[index] {
+            atom.element.symbol.to_lowercase()
+        } else {
+            atom.element.symbol.clone()
+        };
+
+        let isotope = atom.isotope.filter(|_| self.options.isotopes);
+        let atom_class = if self.options.canonical {
+            None
+        } else {
+            atom.get_property::<u32>(&AtomProperty::AtomClass)?
+        };
+        let hydrogen_count = self.hydrogen_counts[index];
+
+        let organic_subset = if wildcard {
+            hydrogen_count == 0
+        } else {
+            let symbols = if self.aromatic[index] {
+                &AROMATIC_ORGANIC_SYMBOLS[..]
+            } else {
+                &ORGANIC_SYMBOLS[..]
+            };
+            symbols.contains(&atom.element.symbol.as_str())
+                && hydrogen_count == self.default_hydrogen_count(index)
+        };
+
+        if organic_subset
+            && isotope.is_none()
+            && atom_class.is_none()
+            && atom.formal_charge == 0
+            && parity.is_none()
+        {
+            return Ok(symbol);
+        }
+
+        let mut output = "[".to_string();
+        if let Some(isotope) = isotope {
+            output.push_str(&isotope.to_string());
+        }
+        output.push_str(&symbol);
+        match parity {
+            Some(TetrahedralParity::Anticlockwise) => output.push('@'),
+            Some(TetrahedralParity::Clockwise) => output.push_str("@@"),
+            None => {}
+        }
+        match hydrogen_count {
+            0 => {}
+            1 => output.push('H'),
+            _ => output.push_str(&format!("H{}", hydrogen_count)),
+        }
+        match atom.formal_charge {
+            0 => {}
+            1 => output.push('+'),
+            -1 => output.push('-'),
+            charge => output.push_str(&format!("{:+}", charge)),
+        }
+        if let Some(atom_class) = atom_class {
+            output.push_str(&format!(":{}", atom_class));
+        }
+        output.push(']');
+
+        Ok(output)
+    }
+
+    // Directions of the single bonds next to double bonds with a configuration, going from each
+    // bond's `from_atom_id` as in `BondProperty::Direction`.
+    //
+    // Double bonds are taken in the order they are written, and each end marks one single bond:
+    // one already marked by a conjugated double bond if there is one, or else the first written.
+    // Where neither end is marked yet, the first end's bond is written as '/', so that the same
+    // structure always gives the same directions.
+    fn bond_directions(
+        &self,
+        roots: &[AtomIndex],
+        children: &[Vec<(AtomIndex, BondIndex)>],
+    ) -> Vec<Option<char>> {
+        let bonds = self.molecule.bonds();
+        let mut directions = vec![None; bonds.len()];
+        if !self.options.stereo {
+            return directions;
+        }
+
+        // Atoms are written depth first, with the children of each atom in order
+        let mut positions = vec![0; self.molecule.atoms().len()];
+        let mut position = 0;
+        for root in roots {
+            let mut stack = vec![*root];
+            while let Some(atom) = stack.pop() {
+                positions[atom] = position;
+                position += 1;
+                stack.extend(children[atom].iter().rev().map(|(child, _)| *child));
+            }
+        }
+
+        let flipped = |direction: char| if direction == '/' { '\\' } else { '/' };
+        // The direction of a bond going from `atom` to the other end
+        let direction_from = |directions: &[Option<char>], bond: BondIndex, atom: AtomIndex| {
+            directions[bond].map(|direction| {
+                if bonds[bond].from_atom_id == atom {
+                    direction
+                } else {
+                    flipped(direction)
+                }
+            })
+        };
+        // The single bond to mark at one end of a double bond, with the neighbour it leads to
+        let marked_bond = |directions: &[Option<char>], end: AtomIndex, other_end: AtomIndex| {
+            let mut single_bonds = self.neighbors[end]
+                .iter()
+                .filter(|(neighbor, bond)| {
+                    *neighbor != other_end && bonds[*bond].bond_type == BondType::single()
+                })
+                .copied()
+                .collect::<Vec<(AtomIndex, BondIndex)>>();
+            single_bonds.sort_by_key(|(neighbor, _)| positions[*neighbor]);
+            single_bonds
+                .iter()
+                .find(|(_, bond)| directions[*bond].is_some())
+                .or_else(|| single_bonds.first())
+                .copied()
+        };
+
+        let mut double_bonds = self
+            .molecule
+            .stereo()
+            .double_bonds
+            .iter()
+            .filter(|stereo| {
+                stereo.bond < bonds.len() && bonds[stereo.bond].bond_type == BondType::double()
+            })
+            .collect::<Vec<&DoubleBondStereo>>();
+        double_bonds.sort_by_key(|stereo| {
+            let bond = &bonds[stereo.bond];
+            let (first, second) = (positions[bond.from_atom_id], positions[bond.to_atom_id]);
+            (first.min(second), first.max(second))
+        });
+
+        for stereo in double_bonds {
+            let bond = &bonds[stereo.bond];
+            let (start, end) = (bond.from_atom_id, bond.to_atom_id);
+            let (start_neighbor, start_bond) = match marked_bond(&directions, start, end) {
+                Some(marked) => marked,
+                None => continue,
+            };
+            let (end_neighbor, end_bond) = match marked_bond(&directions, end, start) {
+                Some(marked) => marked,
+                None => continue,
+            };
+
+            // As in the reader, the double bond is trans where the bond into its start has the
+            // same direction as the bond out of its end
+            let same = stereo.configuration_for([start_neighbor, end_neighbor])
+                == DoubleBondConfiguration::Trans;
+            let into_start = direction_from(&directions, start_bond, start_neighbor);
+            let out_of_end = direction_from(&directions, end_bond, end);
+            let (into_start, out_of_end) = match (into_start, out_of_end) {
+                // Directions set by conjugated double bonds that disagree cannot be written
+                (Some(_), Some(_)) => continue,
+                (Some(into_start), None) => {
+                    let out_of_end = if same {
+                        into_start
+                    } else {
+                        flipped(into_start)
+                    };
+                    (into_start, out_of_end)
+                }
+                (None, Some(out_of_end)) => {
+                    let into_start = if same {
+                        out_of_end
+                    } else {
+                        flipped(out_of_end)
+                    };
+                    (into_start, out_of_end)
+                }
+                (None, None) => {
+                    // Write the first bond as '/', whichever of its atoms comes first
+                    let into_start = if positions[start_neighbor] < positions[start] {
+                        '/'
+                    } else {
+                        '\\'
+                    };
+                    let out_of_end = if same {
+                        into_start
+                    } else {
+                        flipped(into_start)
+                    };
+                    (into_start, out_of_end)
+                }
+            };
+
+            directions[start_bond] = Some(if bonds[start_bond].from_atom_id == start_neighbor {
+                into_start
+            } else {
+                flipped(into_start)
+            });
+            directions[end_bond] = Some(if bonds[end_bond].from_atom_id == end {
+                out_of_end
+            } else {
+                flipped(out_of_end)
+            });
+        }
+
+        directions
+    }
+
+    fn bond_symbol(
+        &self,
+        bond_index: usize,
+        from: AtomIndex,
+        to: AtomIndex,
+        directions: &[Option<char>],
+    ) -> Result<String, FileWriteError> {
+        let bond = &self.molecule.bonds()[bond_index];
+        let between_aromatic = self.aromatic[from] && self.aromatic[to];
+
+        let symbol = match bond.bond_type {
+            BondType::Covalent(1) => match directions[bond_index] {
+                // Directions go from `from_atom_id`, so they flip when written backwards
+                Some(direction) if bond.from_atom_id == from => direction.to_string(),
+                Some('/') => "\\".to_string(),
+                Some(_) => "/".to_string(),
+                None if between_aromatic => "-".to_string(),
+                None => String::new(),
+            },
+            BondType::Covalent(2) => "=".to_string(),
+            BondType::Covalent(3) => "#".to_string(),
+            BondType::Covalent(4) => "$".to_string(),
+            BondType::Aromatic if between_aromatic => String::new(),
+            BondType::Aromatic => ":".to_string(),
+            _ => {
+                return Err(unsupported(&format!(
+                    "Bond type {:?} cannot be written to SMILES",
+                    bond.bond_type
+                )))
+            }
+        };
+
+        Ok(symbol)
+    }
+}
+
+const ORGANIC_SYMBOLS: [&str; 10] = ["B", "C", "N", "O", "P", "S", "F", "Cl", "Br", "I"];
+const AROMATIC_ORGANIC_SYMBOLS: [&str; 6] = ["B", "C", "N", "O", "P", "S"];
+const AROMATIC_SYMBOLS: [&str; 8] = ["B", "C", "N", "O", "P", "S", "Se", "As"];
+
+fn format_ring_digit(digit: u32) -> String {
+    if digit < 10 {
+        digit.to_string()
+    } else {
+        format!("%{}", digit)
+    }
+}
+
+fn unsupported(message: &str) -> FileWriteError {
+    FileWriteError::Unsupported {
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol::{AtomList, MolecularFormula, MoleculeError};
+
+    fn assert_syntax_error(smiles: &str, expected_position: usize) {
+        match read_smiles(smiles) {
+            Err(ParseError::InvalidSyntax { position, .. }) => {
+                assert_eq!(position, expected_position, "{}", smiles)
+            }
+            _ => panic!("Expected ParseError::InvalidSyntax for '{}'", smiles),
+        }
+    }
+
+    fn bond_types(mol: &Molecule) -> Vec<&BondType> {
+        mol.bonds().iter().map(|bond| &bond.bond_type).collect()
+    }
+
+    #[test]
+    fn read_chain() -> Result<(), ParseError> {
+        let mol = read_smiles("CCO")?;
+
+        assert_eq!(mol.atoms().len(), 3);
+        assert_eq!(mol.atoms()[2].element.symbol, "O");
+        assert_eq!(mol.atoms()[2].hydrogen_count, None);
+        assert_eq!(mol.bonds().len(), 2);
+        assert_eq!(mol.bonds()[1].from_atom_id, 1);
+        assert_eq!(mol.bonds()[1].to_atom_id, 2);
+        assert_eq!(mol.bonds()[1].bond_type, BondType::single());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_two_letter_organic_atoms() -> Result<(), ParseError> {
+        let mol = read_smiles("ClCBr")?;
+
+        let symbols = mol
+            .atoms()
+            .iter()
+            .map(|atom| atom.element.symbol.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(symbols, vec!["Cl", "C", "Br"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_bond_symbols() -> Result<(), ParseError> {
+        let mol = read_smiles("C-C=C#C$C:C")?;
+
+        assert_eq!(
+            bond_types(&mol),
+            vec![
+                &BondType::single(),
+                &BondType::double(),
+                &BondType::triple(),
+                &BondType::Covalent(4),
+                &BondType::Aromatic
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_branches() -> Result<(), ParseError> {
+        let mol = read_smiles("CC(=O)(O)C")?;
+
+        let bonds = mol
+            .bonds()
+            .iter()
+            .map(|bond| (bond.from_atom_id, bond.to_atom_id))
+            .collect::<Vec<(AtomIndex, AtomIndex)>>();
+        assert_eq!(bonds, vec![(0, 1), (1, 2), (1, 3), (1, 4)]);
+        assert_eq!(mol.bonds()[1].bond_type, BondType::double());
+        assert_eq!(mol.bonds()[2].bond_type, BondType::single());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ring_bonds() -> Result<(), ParseError> {
+        let mol = read_smiles("C1CCCCC1")?;
+
+        assert_eq!(mol.bonds().len(), 6);
+        assert_eq!(mol.bonds()[5].from_atom_id, 0);
+        assert_eq!(mol.bonds()[5].to_atom_id, 5);
+
+        let mol = read_smiles("C=1CC%12CC%12C1")?;
+
+        assert_eq!(mol.bonds().len(), 7);
+        assert_eq!(mol.bonds()[4].from_atom_id, 2);
+        assert_eq!(mol.bonds()[4].to_atom_id, 4);
+        assert_eq!(mol.bonds()[6].from_atom_id, 0);
+        assert_eq!(mol.bonds()[6].to_atom_id, 5);
+        assert_eq!(mol.bonds()[6].bond_type, BondType::double());
+
+        let mol = read_smiles("C1CC1C1CC1")?;
+
+        assert_eq!(mol.bonds().len(), 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_aromatic() -> Result<(), ParseError> {
+        let mol = read_smiles("c1ccc2[nH]ccc2c1-c1ccccc1")?;
+
+        assert!(mol.atoms().iter().all(|atom| atom.aromatic));
+        assert_eq!(mol.atoms()[4].element.symbol, "N");
+        assert_eq!(mol.atoms()[4].hydrogen_count, Some(1));
+        assert_eq!(
+            mol.bonds()
+                .iter()
+                .filter(|bond| bond.bond_type == BondType::Aromatic)
+                .count(),
+            16
+        );
+        assert_eq!(mol.bonds()[10].bond_type, BondType::single());
+        assert!(mol.bonds()[9].aromatic);
+        assert!(!mol.bonds()[10].aromatic);
+
+        let mol = read_smiles("[se]1cccc1.[as]")?;
+
+        assert_eq!(mol.atoms()[0].element.symbol, "Se");
+        assert_eq!(mol.atoms()[5].element.symbol, "As");
+        assert!(mol.atoms()[5].aromatic);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_bracket_atom() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("[13CH3+:12]")?;
+        let atom = &mol.atoms()[0];
+
+        assert_eq!(atom.element.symbol, "C");
+        assert_eq!(atom.isotope, Some(13));
+        assert_eq!(atom.hydrogen_count, Some(3));
+        assert_eq!(atom.formal_charge, 1);
+        assert_eq!(
+            atom.get_property::<u32>(&AtomProperty::AtomClass)?,
+            Some(12)
+        );
+
+        let mol = read_smiles("[Cu+2].[O--].[Fe-3].[H][H].[Sc]")?;
+
+        assert_eq!(mol.atoms()[0].formal_charge, 2);
+        assert_eq!(mol.atoms()[1].formal_charge, -2);
+        assert_eq!(mol.atoms()[2].formal_charge, -3);
+        assert_eq!(mol.atoms()[3].element.symbol, "H");
+        assert_eq!(mol.atoms()[3].hydrogen_count, Some(0));
+        assert_eq!(mol.atoms()[5].element.symbol, "Sc");
+        assert_eq!(mol.bonds().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_chirality() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("N[C@@H](C)C(=O)O.F[C@](Cl)(Br)I.[Co@OH25].[As@TB3]")?;
+
+        assert_eq!(
+            mol.atoms()[1].get_property_string(&AtomProperty::Chirality)?,
+            Some("@@")
+        );
+        assert_eq!(
+            mol.atoms()[7].get_property_string(&AtomProperty::Chirality)?,
+            Some("@")
+        );
+        assert_eq!(
+            mol.atoms()[11].get_property_string(&AtomProperty::Chirality)?,
+            Some("@OH25")
+        );
+        assert_eq!(
+            mol.atoms()[12].get_property_string(&AtomProperty::Chirality)?,
+            Some("@TB3")
+        );
+        assert_eq!(mol.atoms()[1].hydrogen_count, Some(1));
+
+        assert_syntax_error("[C@TH3]", 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_tetrahedral_stereo() -> Result<(), Box<dyn std::error::Error>> {
+        let stereo = |smiles: &str| -> Result<Vec<TetrahedralStereo>, ParseError> {
+            Ok(read_smiles(smiles)?.stereo().tetrahedral.clone())
+        };
+
+        assert_eq!(
+            stereo("N[C@@H](C)C(=O)O")?,
+            vec![TetrahedralStereo {
+                center: 1,
+                neighbors: [0, 1, 2, 3],
+                parity: TetrahedralParity::Clockwise,
+            }]
+        );
+        assert_eq!(
+            stereo("F[C@](Cl)(Br)I")?,
+            vec![TetrahedralStereo {
+                center: 1,
+                neighbors: [0, 2, 3, 4],
+                parity: TetrahedralParity::Anticlockwise,
+            }]
+        );
+        assert_eq!(stereo("[C@@H](F)(Cl)Br")?[0].neighbors, [0, 1, 2, 3]);
+        assert_eq!(stereo("C[C@TH2]1(F)CCC1")?[0].neighbors, [0, 5, 2, 3]);
+        assert_eq!(stereo("CC1CCC[C@]1(F)C")?[0].neighbors, [4, 1, 6, 7]);
+        // The lone pair of a sulfoxide takes the place of an implicit hydrogen
+        assert_eq!(stereo("C[S@](=O)CC")?[0].neighbors, [0, 1, 2, 3]);
+        assert_eq!(stereo("[Co@OH25]")?, Vec::new());
+
+        // L-alanine has the same configuration as in the Molfile
+        let from_smiles = read_smiles("[C@H]([NH3+])([13CH3])C(=O)[O-]")?;
+        let from_molfile =
+            crate::io::read_mol(std::fs::File::open("./test_files/alanine_v2000.mol")?)?;
+        let smiles_neighbors = [0, 1, 2, 3];
+        let molfile_neighbors = [0, 3, 2, 1];
+        assert_eq!(
+            from_molfile.stereo().tetrahedral[0].parity_for(&molfile_neighbors),
+            from_smiles.stereo().tetrahedral[0].parity_for(&smiles_neighbors)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_double_bond_stereo() -> Result<(), ParseError> {
+        let configuration = |smiles: &str| -> Result<Option<DoubleBondStereo>, ParseError> {
+            Ok(read_smiles(smiles)?.stereo().double_bonds.first().copied())
+        };
+
+        assert_eq!(
+            configuration("F/C=C/F")?,
+            Some(DoubleBondStereo {
+                bond: 1,
+                neighbors: [0, 3],
+                configuration: DoubleBondConfiguration::Trans,
+            })
+        );
+        assert_eq!(
+            configuration("F/C=C\\F")?.map(|stereo| stereo.configuration),
+            Some(DoubleBondConfiguration::Cis)
+        );
+        assert_eq!(
+            configuration("C(\\F)=C/F")?.map(|stereo| stereo.configuration),
+            Some(DoubleBondConfiguration::Trans)
+        );
+        assert_eq!(
+            configuration("F/C=C/1.Br1")?.map(|stereo| stereo.configuration),
+            Some(DoubleBondConfiguration::Trans)
+        );
+        assert_eq!(configuration("FC=C/F")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_bond_directions() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("F/C=C/F")?;
+
+        assert_eq!(
+            mol.bonds()[0].get_property::<char>(&BondProperty::Direction)?,
+            Some('/')
+        );
+        assert_eq!(mol.bonds()[0].bond_type, BondType::single());
+        assert_eq!(
+            mol.bonds()[1].get_property::<char>(&BondProperty::Direction)?,
+            None
+        );
+        assert_eq!(
+            mol.bonds()[2].get_property::<char>(&BondProperty::Direction)?,
+            Some('/')
+        );
+
+        // The closing ring bond symbol is written from the other end of the bond
+        let mol = read_smiles("C1CC/C=C/CCC/1")?;
+
+        assert_eq!(mol.bonds()[7].from_atom_id, 0);
+        assert_eq!(
+            mol.bonds()[7].get_property::<char>(&BondProperty::Direction)?,
+            Some('\\')
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_disconnected() -> Result<(), ParseError> {
+        let mol = read_smiles("[Na+].[Cl-]")?;
+
+        assert_eq!(mol.atoms().len(), 2);
+        assert_eq!(mol.bonds().len(), 0);
+
+        let mol = read_smiles("C(.C)C")?;
+
+        assert_eq!(mol.atoms().len(), 3);
+        assert_eq!(mol.bonds().len(), 1);
+        assert_eq!(mol.bonds()[0].to_atom_id, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_wildcard() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("*C[*:1]")?;
+
+        assert!(mol.atoms()[0].element.is_dummy());
+        assert!(mol.atoms()[2].element.is_dummy());
+        assert!(!mol.atoms()[1].element.is_dummy());
+        assert_eq!(mol.implicit_hydrogen_count(0), 0);
+        assert_eq!(MolecularFormula::from_molecule(&mol).to_string(), "CH2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_empty() -> Result<(), ParseError> {
+        let mol = read_smiles("")?;
+
+        assert_eq!(mol.atoms().len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_error_positions() {
+        assert_syntax_error("CCX", 2);
+        assert_syntax_error("C1CC", 1);
+        assert_syntax_error("CC)C", 2);
+        assert_syntax_error("C(C", 1);
+        assert_syntax_error("C()C", 2);
+        assert_syntax_error("CC=", 2);
+        assert_syntax_error("=CC", 0);
+        assert_syntax_error("C==C", 2);
+        assert_syntax_error("C.", 1);
+        assert_syntax_error("C11", 2);
+        assert_syntax_error("C1C1", 3);
+        assert_syntax_error("C%1C", 1);
+        assert_syntax_error("C=1CC#1", 6);
+        assert_syntax_error("C[Xx]", 2);
+        assert_syntax_error("C[CH4", 5);
+        assert_syntax_error("[C+16]", 2);
+        assert_syntax_error("[CH3:]", 5);
+        assert_syntax_error("cH", 1);
+    }
+
+    #[test]
+    fn read_stereo_groups() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("C[C@H](O)[C@@H](N)[C@H](C)F |a:1,o1:3,&2:5,&2:6,$;R$|")?;
+
+        assert_eq!(
+            mol.stereo().groups,
+            vec![
+                StereoGroup {
+                    kind: StereoGroupKind::Absolute,
+                    number: 0,
+                    atoms: vec![1],
+                },
+                StereoGroup {
+                    kind: StereoGroupKind::Or,
+                    number: 1,
+                    atoms: vec![3],
+                },
+                StereoGroup {
+                    kind: StereoGroupKind::And,
+                    number: 2,
+                    atoms: vec![5, 6],
+                },
+            ]
+        );
+        assert!(read_smiles("CCO ethanol")?.stereo().groups.is_empty());
+
+        assert_syntax_error("C[C@H](O)N |o1:1", 11);
+        assert_syntax_error("C[C@H](O)N |o1:4|", 12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_error_unknown_isotope() {
+        match read_smiles("C[40C]") {
+            Err(ParseError::InvalidSyntax { message, position }) => {
+                assert_eq!(message, "Unknown isotope '40C'");
+                assert_eq!(position, 2);
+            }
+            _ => panic!("Expected ParseError::InvalidSyntax"),
+        }
+    }
+
+    fn to_smiles(smiles: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(write_smiles(
+            &read_smiles(smiles)?,
+            SmilesOptions::default(),
+        )?)
+    }
+
+    fn to_canonical_smiles(smiles: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let options = SmilesOptions {
+            canonical: true,
+            ..SmilesOptions::default()
+        };
+        Ok(write_smiles(&read_smiles(smiles)?, options)?)
+    }
+
+    #[test]
+    fn write_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        for smiles in &[
+            "CCO",
+            "CC(=O)O",
+            "C1CCCCC1",
+            "C#N",
+            "c1ccccc1-c1ccccc1",
+            "c1ccc2[nH]ccc2c1",
+            "[13CH3][NH3+]",
+            "[Na+].[Cl-]",
+            "[Fe-3].[Cu+2]",
+            "[CH3:1]C",
+            "*C",
+            "[H][H]",
+            "F/C=C/F",
+        ] {
+            assert_eq!(&to_smiles(smiles)?, smiles);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_normalized() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(to_smiles("[CH4]")?, "C");
+        assert_eq!(to_smiles("[CH3]")?, "[CH3]");
+        assert_eq!(to_smiles("C=1CCCCC1")?, "C=1CCCCC1");
+        assert_eq!(to_smiles("C1CCCCC=1")?, "C=1CCCCC1");
+        assert_eq!(to_smiles("C=1CC2CC2C=1")?, "C=1CC2CC2C1");
+        assert_eq!(to_smiles("C1CC/C=C/CCC/1")?, "C1CC/C=C/CCC1");
+        assert_eq!(to_smiles("C1CCCCCC/C=C/1")?, "C\\1CCCCCC/C=C1");
+        assert_eq!(to_smiles("C(=O)(O)C")?, "C(=O)(O)C");
+        assert_eq!(to_smiles("c1cc[se]c1")?, "c1cc[se]c1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_options() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("[13CH3]/C=C/[2H]")?;
+        let options = SmilesOptions {
+            stereo: false,
+            isotopes: false,
+            ..SmilesOptions::default()
+        };
+
+        assert_eq!(write_smiles(&mol, options)?, "CC=C[H]");
+
+        let mol = read_smiles("C1=CC=CC=C1")?;
+        let options = SmilesOptions {
+            aromatic: false,
+            ..SmilesOptions::default()
+        };
+
+        assert_eq!(write_smiles(&mol, options.clone())?, "C1=CC=CC=C1");
+
+        let mol = read_smiles("c1ccc[nH]1")?;
+        assert_eq!(write_smiles(&mol, options.clone())?, "C1=CC=CN1");
+
+        let mol = read_smiles("c1cccc1")?;
+        match write_smiles(&mol, options) {
+            Err(FileWriteError::MoleculeError(MoleculeError::NoKekuleStructure { .. })) => {}
+            _ => panic!("Expected MoleculeError::NoKekuleStructure"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_tetrahedral_stereo() -> Result<(), Box<dyn std::error::Error>> {
+        for smiles in &[
+            "N[C@@H](C)C(=O)O",
+            "N[C@H](C)C(=O)O",
+            "[C@@H](F)(Cl)Br",
+            "F[C@]1(Cl)CCCC1",
+            "C[C@@]12CCC[C@H]1CCC2",
+            "C[S@](=O)CC",
+        ] {
+            assert_eq!(&to_smiles(smiles)?, smiles);
+        }
+
+        // Written in another order, the parity follows the order of the neighbours
+        let mol = read_smiles("C[C@H](N)O")?;
+        let reordered = read_smiles("O[C@@H](N)C")?;
+        let options = SmilesOptions::default();
+        assert_eq!(
+            read_smiles(&write_smiles(&mol, options.clone())?)?.stereo(),
+            mol.stereo()
+        );
+        assert_eq!(write_smiles(&reordered, options)?, "O[C@@H](N)C");
+
+        let options = SmilesOptions {
+            stereo: false,
+            ..SmilesOptions::default()
+        };
+        assert_eq!(write_smiles(&mol, options)?, "CC(N)O");
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_double_bond_stereo() -> Result<(), Box<dyn std::error::Error>> {
+        // 2-Butene from a Molfile, with its configuration taken from the coordinates
+        let molfile = |y_end: f64| {
+            format!(
+                "2-Butene\n\n\n  4  3  0  0  0  0  0  0  0  0999 V2000\n\
+                 \x20  -0.7500    1.2990    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0\n\
+                 \x20   0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0\n\
+                 \x20   1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0\n\
+                 \x20   2.2500{:10.4}    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0\n\
+                 \x20 1  2  1  0  0  0  0\n\
+                 \x20 2  3  2  0  0  0  0\n\
+                 \x20 3  4  1  0  0  0  0\n\
+                 M  END\n",
+                y_end
+            )
+        };
+        let trans = crate::io::read_mol(molfile(-1.299).as_bytes())?;
+        let cis = crate::io::read_mol(molfile(1.299).as_bytes())?;
+        assert_eq!(write_smiles(&trans, SmilesOptions::default())?, "C/C=C/C");
+        assert_eq!(write_smiles(&cis, SmilesOptions::default())?, "C/C=C\\C");
+
+        // Conjugated double bonds share the bond between them, and the first bond written is '/'
+        let canonical = to_canonical_smiles("C/C=C/C=C/C")?;
+        for smiles in &[
+            "C/C=C/C=C/C",
+            "C\\C=C\\C=C\\C",
+            "C(\\C=C\\C)=C/C",
+            "C(=C/C=C/C)\\C",
+            "C(=C\\C)/C=C/C",
+        ] {
+            assert_eq!(to_canonical_smiles(smiles)?, canonical, "{}", smiles);
+        }
+        assert_eq!(canonical, "C/C=C/C=C/C");
+        assert_ne!(to_canonical_smiles("C/C=C/C=C\\C")?, canonical);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_implicit_hydrogens() -> Result<(), Box<dyn std::error::Error>> {
+        let mut atoms = Vec::new();
+        for (symbol, charge) in &[("C", 0), ("N", 1), ("O", -1), ("Na", 1), ("S", 0)] {
+            let mut atom = Atom::from_symbol(symbol)?;
+            atom.formal_charge = *charge;
+            atoms.push(atom);
+        }
+        let bonds = vec![
+            Bond::new(0, 1, BondType::single()),
+            Bond::new(0, 2, BondType::single()),
+            Bond::new(0, 4, BondType::double()),
+        ];
+        let mol = Molecule::from_graph(atoms, bonds);
+
+        assert_eq!(
+            write_smiles(&mol, SmilesOptions::default())?,
+            "C([NH3+])([O-])=S.[Na+]"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_canonical() -> Result<(), Box<dyn std::error::Error>> {
+        let equivalent_smiles = [
+            vec!["CCO", "OCC", "C(O)C"],
+            vec!["NCc1ccccc1", "c1ccccc1CN", "c1cc(CN)ccc1", "C(N)c1ccccc1"],
+            vec!["CC(N)C(=O)O", "OC(=O)C(C)N", "NC(C(O)=O)C", "C(C(=O)O)(N)C"],
+            vec!["C1CC2CCC1CC2", "C12CCC(CC1)CC2", "C1CC(CC2)CCC12"],
+            vec!["[Na+].[Cl-]", "[Cl-].[Na+]"],
+            vec!["c1ccc2ccccc2c1", "c1cccc2c1cccc2", "c12ccccc1cccc2"],
+        ];
+
+        for smiles_list in &equivalent_smiles {
+            let canonical = to_canonical_smiles(smiles_list[0])?;
+            for smiles in smiles_list {
+                assert_eq!(to_canonical_smiles(smiles)?, canonical, "{}", smiles);
+            }
+            assert_eq!(to_canonical_smiles(&canonical)?, canonical);
+        }
+
+        assert_ne!(to_canonical_smiles("CCO")?, to_canonical_smiles("COC")?);
+
+        // Enantiomers have different canonical SMILES
+        let canonical = to_canonical_smiles("C[C@H](N)O")?;
+        assert_eq!(to_canonical_smiles("O[C@@H](N)C")?, canonical);
+        assert_ne!(to_canonical_smiles("O[C@H](N)C")?, canonical);
+        assert_eq!(to_canonical_smiles(&canonical)?, canonical);
+        assert_ne!(
+            to_canonical_smiles("N[C@@H](C)C(=O)O")?,
+            to_canonical_smiles("N[C@H](C)C(=O)O")?
+        );
+        assert_eq!(
+            to_canonical_smiles("C[C@H]1CC[C@@H](C)CC1")?,
+            to_canonical_smiles("C[C@@H]1CC[C@H](C)CC1")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_canonical_stereo_groups() -> Result<(), Box<dyn std::error::Error>> {
+        let options = SmilesOptions {
+            canonical: true,
+            stereo_groups: true,
+            ..SmilesOptions::default()
+        };
+        let canonical = |smiles| -> Result<String, Box<dyn std::error::Error>> {
+            Ok(write_smiles(&read_smiles(smiles)?, options.clone())?)
+        };
+
+        let expected = canonical("C[C@H](O)[C@@H](C)N |o1:1,&1:3|")?;
+        assert_eq!(expected, "C[C@H]([C@H](C)O)N |o1:2,&1:1|");
+        assert_eq!(canonical("N[C@H](C)[C@@H](O)C |o1:3,&1:1|")?, expected);
+        assert_eq!(canonical(&expected)?, expected);
+
+        assert_eq!(to_canonical_smiles("C[C@H](O)N |a:1|")?, "C[C@@H](N)O");
+
+        // Atoms written without a configuration are left out of their groups
+        assert_eq!(
+            canonical("CC(O)[C@@H](C)N |o1:1,&1:3|")?,
+            "C[C@H](C(C)O)N |&1:1|"
+        );
+        assert_eq!(canonical("CC(O)C(C)N |o1:1,&1:3|")?, "CC(C(C)O)N");
+
+        // Without canonical ordering, groups keep their numbers
+        let smiles = "C[C@H](O)[C@@H](N)[C@H](C)F |a:1,o3:3,&2:5|";
+        let options = SmilesOptions {
+            stereo_groups: true,
+            ..SmilesOptions::default()
+        };
+        assert_eq!(write_smiles(&read_smiles(smiles)?, options)?, smiles);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_canonical_across_formats() -> Result<(), Box<dyn std::error::Error>> {
+        let options = SmilesOptions {
+            canonical: true,
+            isotopes: false,
+            ..SmilesOptions::default()
+        };
+        let mol_file = crate::io::read_mol(std::fs::File::open("./test_files/alanine_v2000.mol")?)?;
+        let mol_smiles = read_smiles("[NH3+][C@@H](C)C([O-])=O")?;
+
+        assert_eq!(
+            write_smiles(&mol_file, options.clone())?,
+            write_smiles(&mol_smiles, options)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_error_atom_list() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = Molecule::new();
+        let mut atom = Atom::from_symbol("C")?;
+        atom.set_property(
+            AtomProperty::AtomList,
+            AtomList {
+                elements: vec![Element::from_symbol("N")?],
+                exclude: false,
+            },
+        );
+        mol.add_atom(atom);
+
+        match write_smiles(&mol, SmilesOptions::default()) {
+            Err(FileWriteError::Unsupported { .. }) => {}
+            _ => panic!("Expected FileWriteError::Unsupported"),
+        }
+
+        Ok(())
+    }
+}
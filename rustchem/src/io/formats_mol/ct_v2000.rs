@@ -1,78 +1,98 @@
-use super::utils::{parse_f64_default, parse_i32_default, parse_u32_default, parse_usize_default};
-use crate::io::{FileReadError, LineReader, ParseError};
-use crate::mol::{Atom, Bond, BondType, Molecule, Point3d};
+use super::utils::{
+    bond_type_code, mass_difference, parse_bond_type, parse_f64_default, parse_i32_default,
+    parse_radical, parse_u32_default, parse_usize_default, radical_code, stereo_parity_code,
+    written_valence,
+};
+use crate::io::{FileReadError, FileWriteError, LineReader, ParseError};
+use crate::mol::{
+    Atom, AtomList, AtomProperty, Bond, BondStereo, Element, HasProperties, Isotope, Molecule,
+    MoleculeError, Point3d, Radical,
+};
+use std::convert::TryFrom;
 
 // Reference: https://web.archive.org/web/20070630061308/http:/www.mdl.com/downloads/public/ctfile/ctfile.pdf
 // Reference: https://depth-first.com/articles/2020/07/13/the-sdfile-format/
 
 pub fn read_ct(
+    counts_line: &CountsLine,
     line_reader: &mut LineReader<impl std::io::Read>,
 ) -> Result<Molecule, FileReadError> {
-    let counts_line = parse_counts(&line_reader.read_line()?)
-        .map_err(|source| FileReadError::LineParse { source, line: 3 })?;
-
-    let mut atoms = line_reader
+    let first_atom_line = line_reader.line_number() + 1;
+    let atoms = line_reader
         .read_lines(counts_line.num_atoms)
         .enumerate()
-        .map(|(line_number, atom_line)| {
+        .map(|(index, atom_line)| {
             parse_atom_line(&atom_line?).map_err(|source| FileReadError::LineParse {
                 source,
-                line: line_number + 3,
+                line: first_atom_line + index,
             })
         })
         .collect::<Result<Vec<Atom>, FileReadError>>()?;
 
-    let bonds = line_reader
-        .read_lines(counts_line.num_bonds)
-        .enumerate()
-        .map(|(line_number, bond_line)| {
-            parse_bond_line(&bond_line?).map_err(|source| FileReadError::LineParse {
+    let mut molecule = Molecule::from_graph(atoms, Vec::new());
+    molecule.stereo_mut().chiral = counts_line.chiral_flag;
+
+    let first_line = line_reader.line_number() + 1;
+    for (index, bond_line) in line_reader.read_lines(counts_line.num_bonds).enumerate() {
+        parse_bond_line(&bond_line?)
+            .and_then(|bond| Ok(molecule.add_bond(bond)?))
+            .map_err(|source| FileReadError::LineParse {
                 source,
-                line: counts_line.num_atoms as usize + line_number + 3,
-            })
-        })
-        .collect::<Result<Vec<Bond>, FileReadError>>()?;
+                line: first_line + index,
+            })?;
+    }
 
     for _ in line_reader.read_lines(counts_line.num_atom_lists) {}
     for _ in line_reader.read_lines(counts_line.num_stext * 2) {}
 
-    let mut has_charge_props = false;
+    let mut state = PropertyState::default();
 
-    loop {
+    // Old files give the number of property lines (including 'M  END'), while newer ones set it to
+    // 999 and only end the block with 'M  END'
+    let num_properties = match counts_line.num_properties {
+        count @ 1..=998 => count,
+        _ => u32::MAX,
+    };
+    for _ in 0..num_properties {
         let line = line_reader.read_line()?;
-        match &line[..6] {
-            "M  END" => break,
-            "M  CHG" => {
-                if !has_charge_props {
-                    reset_atom_charges(&mut atoms);
-                    has_charge_props = true
-                };
-            }
-            "M  RAD" => {
-                if !has_charge_props {
-                    reset_atom_charges(&mut atoms);
-                    has_charge_props = true
-                };
-            }
-            _ => {}
+        if line.starts_with("M  END") {
+            break;
         }
+
+        parse_property_line(&line, molecule.atoms_mut(), &mut state).map_err(|source| {
+            FileReadError::LineParse {
+                source,
+                line: line_reader.line_number(),
+            }
+        })?;
+    }
+
+    // Atom list ('L') atoms must be given their elements by an 'M  ALS' line
+    if let Some(index) = molecule.atoms().iter().position(is_pending_atom_list) {
+        return Err(FileReadError::LineParse {
+            source: ParseError::InvalidValue {
+                name: "atom symbol".to_string(),
+                value: "L".to_string(),
+            },
+            line: first_atom_line + index,
+        });
     }
 
-    Ok(Molecule::from_graph(atoms, bonds))
+    Ok(molecule)
 }
 
 #[derive(Debug)]
-struct CountsLine {
+pub struct CountsLine {
     pub num_atoms: u32,
     num_bonds: u32,
     num_atom_lists: u32,
     chiral_flag: bool,
     num_stext: u32,
     num_properties: u32,
-    version: String,
+    pub version: String,
 }
 
-fn parse_counts(line: &str) -> Result<CountsLine, ParseError> {
+pub fn parse_counts(line: &str) -> Result<CountsLine, ParseError> {
     /*
     Counts Line: 'aaabbblllfffcccsssxxxrrrpppiiimmmvvvvvv'
 
@@ -90,6 +110,12 @@ fn parse_counts(line: &str) -> Result<CountsLine, ParseError> {
     vvvvvv = version string (' V2000' or ' V3000')
     */
 
+    let line = if line.len() >= 39 {
+        line.to_string()
+    } else {
+        format!("{:39}", line)
+    };
+
     let counts_line = CountsLine {
         num_atoms: parse_u32_default(&line[0..3], "atom count")?,
         num_bonds: parse_u32_default(&line[3..6], "bond count")?,
@@ -113,7 +139,7 @@ fn parse_atom_line(line: &str) -> Result<Atom, ParseError> {
     aaa = atom symbol (or 'L'=atom list, 'A'/'Q'/'*'=unspecified, 'LP'=lone pair, 'R#'=R-group)
     dd = mass difference from default isotope (range -3..4, outside of limits are zero, 'M ISO' takes precedence)
     ccc = charge(0=uncharged, 1=+3, 2=+2, 3=+1, 5=-1, 6=-2, 7=-3, 4=doublet radical, 'M CHG/RAD' take precedence)
-    sss = atom stero-parity (0=not stereo, 1=odd, 2=even, 3=either/unmarked, Ignored when read)
+    sss = atom stereo parity (0=not stereo, 1=odd, 2=even, 3=either/unmarked)
     hhh (*) = hydrogen count + 1 (1=H0 (no H-atoms allowed unless drawn), 2..5=Hn ('n' or more H-atoms + those drawn)
     bbb (*) = stereo care box (for double bond stereochemistry, 0=ignore, 1=must match drawn, NB: must be 1 at both ends of bond)
     vvv = valence (0=unspecified, 1..14=number of bonds to atom including implied H-atoms, 15=zero valence)
@@ -138,32 +164,57 @@ fn parse_atom_line(line: &str) -> Result<Atom, ParseError> {
     let symbol = &line[31..34].trim();
     let mass_difference = parse_i32_default(&line[34..36], "mass difference")?;
     let charge_id = parse_u32_default(&line[36..39], "charge")?;
-    let _stereo_parity = parse_u32_default(&line[39..42], "atom stereo parity")?;
+    let stereo_parity = parse_u32_default(&line[39..42], "atom stereo parity")?;
     let _hydrogen_count = parse_u32_default(&line[42..45], "hydrogen count")?;
     let _stereo_care_box = parse_u32_default(&line[45..48], "stereo care box")?;
-    let _valence = parse_u32_default(&line[48..51], "valence")?;
+    let valence = parse_u32_default(&line[48..51], "valence")?;
     let _h0_designator = parse_u32_default(&line[51..54], "H0 designator")?;
     let _atom_mapping = parse_u32_default(&line[60..63], "atom-atom mapping number")?;
     let _inversion_flag = parse_u32_default(&line[63..66], "inversion/retention flag")?;
     let _exact_change_flag = parse_u32_default(&line[66..69], "inversion/retention flag")?;
 
-    let formal_charge = match charge_id {
-        1 => 3,
-        2 => 2,
-        3 => 1,
-        // TODO: 4 = doublet radical
-        5 => -1,
-        6 => -2,
-        7 => -3,
-        _ => 0,
+    let (formal_charge, radical) = match charge_id {
+        1 => (3, Radical::None),
+        2 => (2, Radical::None),
+        3 => (1, Radical::None),
+        4 => (0, Radical::Doublet),
+        5 => (-1, Radical::None),
+        6 => (-2, Radical::None),
+        7 => (-3, Radical::None),
+        _ => (0, Radical::None),
     };
 
-    let mut atom = Atom::from_symbol(symbol)?;
+    // Atom list atoms are given the dummy element and an empty list until their 'M  ALS' line is read
+    let mut atom = match *symbol {
+        "L" => {
+            let mut atom = Atom::new(Element::dummy());
+            atom.set_property(
+                AtomProperty::AtomList,
+                AtomList {
+                    elements: Vec::new(),
+                    exclude: false,
+                },
+            );
+            atom
+        }
+        _ => Atom::from_symbol(symbol)?,
+    };
     atom.formal_charge = formal_charge;
+    atom.radical = radical;
     atom.position = Point3d::new(x, y, z);
+    atom.valence = match valence {
+        0 => None,
+        15 => Some(0),
+        valence => Some(valence),
+    };
+    if stereo_parity != 0 {
+        atom.set_property(AtomProperty::StereoParity, stereo_parity);
+    }
 
-    if mass_difference >= -3 && mass_difference <= 3 && mass_difference != 0 {
-        atom.isotope = Some(((atom.element.most_common_isotope) as i32 + mass_difference) as u32);
+    if (-3..=3).contains(&mass_difference) && mass_difference != 0 {
+        // The mass difference is relative to the periodic table mass of the element
+        let mass_number = (atom.element.average_mass.round() as i32 + mass_difference) as u32;
+        atom.isotope = Some(Isotope::lookup(atom.element, mass_number)?.mass_number);
     }
 
     Ok(atom)
@@ -190,53 +241,387 @@ fn parse_bond_line(line: &str) -> Result<Bond, ParseError> {
         format!("{:21}", line)
     };
 
-    let from_atom_id = parse_usize_default(&line[0..3], "atom 1")?;
-    let to_atom_id = parse_usize_default(&line[3..6], "atom 2")?;
-    let bond_type = parse_u32_default(&line[6..9], "bond type")?;
-    let _bond_stereo = parse_u32_default(&line[9..12], "bond stereochemistry")?;
+    let from_atom_id = parse_bond_atom_index(&line[0..3], "atom 1")?;
+    let to_atom_id = parse_bond_atom_index(&line[3..6], "atom 2")?;
+    let bond_type = parse_bond_type(&line[6..9])?;
+    let bond_stereo = match parse_u32_default(&line[9..12], "bond stereochemistry")? {
+        0 => BondStereo::None,
+        1 => BondStereo::Up,
+        3 => BondStereo::CisTransEither,
+        4 => BondStereo::Either,
+        6 => BondStereo::Down,
+        _ => {
+            return Err(ParseError::InvalidValue {
+                name: "bond stereochemistry".to_string(),
+                value: line[9..12].to_string(),
+            })
+        }
+    };
     let _bond_topology = parse_u32_default(&line[15..18], "bond topology")?;
     let _reacting_center = parse_u32_default(&line[18..21], "reacting center status")?;
 
-    let bond_type = match bond_type {
-        1 => BondType::single(),
-        2 => BondType::double(),
-        3 => BondType::triple(),
-        4 => BondType::Aromatic,
-        5 => BondType::single_or_double(),
-        6 => BondType::single_or_aromatic(),
-        7 => BondType::double_or_aromatic(),
-        8 => BondType::Any,
-        _ => {
+    let mut bond = Bond::new(from_atom_id - 1, to_atom_id - 1, bond_type);
+    bond.stereo = bond_stereo;
+    Ok(bond)
+}
+
+fn parse_bond_atom_index(val: &str, dest_nature: &str) -> Result<usize, ParseError> {
+    // Atom indices start from 1, and are checked against the atom count when the bond is added
+    match parse_usize_default(val, dest_nature)? {
+        0 => Err(ParseError::InvalidValue {
+            name: dest_nature.to_string(),
+            value: val.to_string(),
+        }),
+        index => Ok(index),
+    }
+}
+
+pub fn write_ct(
+    molecule: &Molecule,
+    writer: &mut impl std::io::Write,
+) -> Result<(), FileWriteError> {
+    if molecule.atoms().len() > 999 || molecule.bonds().len() > 999 {
+        return Err(FileWriteError::Unsupported {
+            message: "V2000 connection tables are limited to 999 atoms and bonds".to_string(),
+        });
+    }
+
+    writeln!(
+        writer,
+        "{:3}{:3}  0  0{:3}  0  0  0  0  0999 V2000",
+        molecule.atoms().len(),
+        molecule.bonds().len(),
+        molecule.stereo().chiral as u32
+    )?;
+
+    for (index, atom) in molecule.atoms().iter().enumerate() {
+        let valence = written_valence(molecule, index);
+        let stereo_parity = stereo_parity_code(molecule, index);
+        writeln!(
+            writer,
+            "{}",
+            format_atom_line(atom, stereo_parity, valence)?
+        )?;
+    }
+
+    for bond in molecule.bonds() {
+        // 1=up, 4=either, 6=down, 3=either cis/trans
+        let stereo_code = match bond.stereo {
+            BondStereo::None => 0,
+            BondStereo::Up => 1,
+            BondStereo::Down => 6,
+            BondStereo::Either => 4,
+            BondStereo::CisTransEither => 3,
+        };
+        writeln!(
+            writer,
+            "{:3}{:3}{:3}{:3}  0  0  0",
+            bond.from_atom_id + 1,
+            bond.to_atom_id + 1,
+            bond_type_code(&bond.bond_type)?,
+            stereo_code
+        )?;
+    }
+
+    let charges = molecule
+        .atoms()
+        .iter()
+        .enumerate()
+        .filter(|(_, atom)| atom.formal_charge != 0)
+        .map(|(index, atom)| (index, atom.formal_charge))
+        .collect::<Vec<(usize, i32)>>();
+    write_property_lines(writer, "CHG", &charges)?;
+
+    let radicals = molecule
+        .atoms()
+        .iter()
+        .enumerate()
+        .filter(|(_, atom)| atom.radical != Radical::None)
+        .map(|(index, atom)| (index, radical_code(&atom.radical)))
+        .collect::<Vec<(usize, i32)>>();
+    write_property_lines(writer, "RAD", &radicals)?;
+
+    let isotopes = molecule
+        .atoms()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, atom)| atom.isotope.map(|isotope| (index, isotope as i32)))
+        .collect::<Vec<(usize, i32)>>();
+    write_property_lines(writer, "ISO", &isotopes)?;
+
+    writeln!(writer, "M  END")?;
+    Ok(())
+}
+
+fn format_atom_line(
+    atom: &Atom,
+    stereo_parity: u32,
+    valence: Option<u32>,
+) -> Result<String, FileWriteError> {
+    let charge_id = match (atom.formal_charge, atom.radical) {
+        (3, _) => 1,
+        (2, _) => 2,
+        (1, _) => 3,
+        (0, Radical::Doublet) => 4,
+        (-1, _) => 5,
+        (-2, _) => 6,
+        (-3, _) => 7,
+        _ => 0,
+    };
+    // 0=unspecified, 15=zero valence
+    let valence_code = match valence {
+        None => 0,
+        Some(0) => 15,
+        Some(valence @ 1..=14) => valence,
+        Some(valence) => {
+            return Err(FileWriteError::Unsupported {
+                message: format!("Valence {} cannot be written to a V2000 Molfile", valence),
+            })
+        }
+    };
+
+    Ok(format!(
+        "{:10.4}{:10.4}{:10.4} {:<3}{:2}{:3}{:3}  0  0{:3}  0  0  0  0  0  0",
+        atom.position.x,
+        atom.position.y,
+        atom.position.z,
+        atom.element.symbol,
+        mass_difference(atom).unwrap_or(0),
+        charge_id,
+        stereo_parity,
+        valence_code
+    ))
+}
+
+fn write_property_lines(
+    writer: &mut impl std::io::Write,
+    tag: &str,
+    values: &[(usize, i32)],
+) -> std::io::Result<()> {
+    // Property lines hold at most eight entries: 'M  XXXnn8 aaa vvv ...'
+    for chunk in values.chunks(8) {
+        write!(writer, "M  {}{:3}", tag, chunk.len())?;
+        for (index, value) in chunk {
+            write!(writer, " {:3} {:3}", index + 1, value)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct PropertyState {
+    has_charge_props: bool,
+    has_isotope_props: bool,
+}
+
+fn parse_property_line(
+    line: &str,
+    atoms: &mut [Atom],
+    state: &mut PropertyState,
+) -> Result<(), ParseError> {
+    /*
+    Property Lines: 'M  XXXnn8 aaa vvv ...'
+
+    nn8 = number of entries on the line (maximum of 8)
+    aaa = atom index (starting from 1)
+    vvv = value
+
+    CHG = formal charge (-15..15, supersedes all atom block charges and radicals)
+    RAD = radical (0=none, 1=singlet, 2=doublet, 3=triplet, supersedes all atom block charges and radicals)
+    ISO = absolute atomic mass (supersedes all atom block mass differences)
+    SUB (*) = substitution count (0=off, -1=no substitution, -2=as drawn, 1..6=count, 6=6 or more)
+    UNS (*) = unsaturated atom (0=off, 1=on)
+    RBC (*) = ring bond count (0=off, -1=no ring bonds, -2=as drawn, 2..4=count, 4=4 or more)
+    APO = Rgroup attachment point (1=first point, 2=second point, 3=both)
+
+    Atom List: 'M  ALS aaannn e 11112222 33334444 ...'
+
+    aaa = atom index (starting from 1)
+    nnn = number of entries in the list
+    e = 'T' to exclude the listed elements, 'F' to include them
+    1111 = element symbol (left justified)
+
+    Atom Label: 'M  ZZC aaa label' (non-standard)
+
+    NB: (*) specifies properties that apply only to queries
+    */
+
+    match line.get(..6).unwrap_or(line) {
+        "M  CHG" => {
+            reset_charge_props(atoms, state);
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                atoms[index].formal_charge = value;
+            }
+        }
+        "M  RAD" => {
+            reset_charge_props(atoms, state);
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                atoms[index].radical = parse_radical(&value.to_string())?;
+            }
+        }
+        "M  ISO" => {
+            if !state.has_isotope_props {
+                atoms.iter_mut().for_each(|atom| atom.isotope = None);
+                state.has_isotope_props = true;
+            }
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                let mass_number = u32::try_from(value).map_err(|_| invalid_value("mass", value))?;
+                let atom = &mut atoms[index];
+                atom.isotope = Some(Isotope::lookup(atom.element, mass_number)?.mass_number);
+            }
+        }
+        "M  SUB" => {
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                atoms[index].set_property(AtomProperty::SubstitutionCount, value);
+            }
+        }
+        "M  UNS" => {
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                atoms[index].set_property(AtomProperty::Unsaturated, value != 0);
+            }
+        }
+        "M  RBC" => {
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                atoms[index].set_property(AtomProperty::RingBondCount, value);
+            }
+        }
+        "M  APO" => {
+            for (index, value) in parse_property_values(line, atoms.len())? {
+                if !(1..=3).contains(&value) {
+                    return Err(invalid_value("attachment point", value));
+                }
+                atoms[index].set_property(AtomProperty::AttachmentPoint, value as u32);
+            }
+        }
+        "M  ALS" => {
+            let (index, atom_list) = parse_atom_list(line, atoms.len())?;
+            let atom = &mut atoms[index];
+            // An included element stands in for the list, while exclusion lists keep the dummy
+            if is_pending_atom_list(atom) && !atom_list.exclude {
+                atom.element = atom_list.elements[0];
+            }
+            atom.set_property(AtomProperty::AtomList, atom_list);
+        }
+        "M  ZZC" => {
+            let values = line[6..].split_whitespace().collect::<Vec<&str>>();
+            if values.len() < 2 {
+                return Err(ParseError::LineTooShort);
+            }
+            let index = parse_atom_index(values[0], atoms.len())?;
+            atoms[index].set_property(AtomProperty::Label, values[1..].join(" "));
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn parse_property_values(line: &str, num_atoms: usize) -> Result<Vec<(usize, i32)>, ParseError> {
+    let values = line[6..].split_whitespace().collect::<Vec<&str>>();
+    if values.is_empty() {
+        return Err(ParseError::LineTooShort);
+    }
+
+    let count = parse_usize_default(values[0], "entry count")?;
+    if values.len() < 1 + count * 2 {
+        return Err(ParseError::LineTooShort);
+    }
+
+    values[1..=count * 2]
+        .chunks(2)
+        .map(|entry| {
+            Ok((
+                parse_atom_index(entry[0], num_atoms)?,
+                parse_i32_default(entry[1], "property value")?,
+            ))
+        })
+        .collect()
+}
+
+fn parse_atom_list(line: &str, num_atoms: usize) -> Result<(usize, AtomList), ParseError> {
+    let line = if line.len() >= 16 {
+        line.to_string()
+    } else {
+        format!("{:16}", line)
+    };
+
+    let index = parse_atom_index(&line[7..10], num_atoms)?;
+    let count = parse_usize_default(&line[10..13], "atom list count")?;
+    let exclude = match &line[14..15] {
+        "T" => true,
+        "F" => false,
+        value => {
             return Err(ParseError::InvalidValue {
-                name: "bond type".to_string(),
-                value: line[6..9].to_string(),
+                name: "atom list exclusion flag".to_string(),
+                value: value.to_string(),
             })
         }
     };
 
-    Ok(Bond::new(from_atom_id - 1, to_atom_id - 1, bond_type))
+    let elements = line[16..]
+        .split_whitespace()
+        .map(Element::from_symbol)
+        .collect::<Result<Vec<&'static Element>, MoleculeError>>()?;
+    if elements.is_empty() || elements.len() != count {
+        return Err(ParseError::InvalidValue {
+            name: "atom list count".to_string(),
+            value: line[10..13].to_string(),
+        });
+    }
+
+    Ok((index, AtomList { elements, exclude }))
+}
+
+fn is_pending_atom_list(atom: &Atom) -> bool {
+    match atom.get_property_ref::<AtomList>(&AtomProperty::AtomList) {
+        Ok(Some(atom_list)) => atom_list.elements.is_empty(),
+        _ => false,
+    }
 }
 
-fn reset_atom_charges(atoms: &mut Vec<Atom>) {
-    for atom in atoms {
-        atom.formal_charge = 0;
-        // TODO: atom.radical = 0;
+fn parse_atom_index(val: &str, num_atoms: usize) -> Result<usize, ParseError> {
+    let index = parse_usize_default(val, "atom index")?;
+    if index == 0 || index > num_atoms {
+        return Err(ParseError::InvalidValue {
+            name: "atom index".to_string(),
+            value: val.to_string(),
+        });
+    }
+    Ok(index - 1)
+}
+
+fn invalid_value(name: &str, value: i32) -> ParseError {
+    ParseError::InvalidValue {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn reset_charge_props(atoms: &mut [Atom], state: &mut PropertyState) {
+    if !state.has_charge_props {
+        for atom in atoms {
+            atom.formal_charge = 0;
+            atom.radical = Radical::None;
+        }
+        state.has_charge_props = true;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mol::{BondType, MolecularFormula};
 
     #[test]
     fn parse_counts_line() -> Result<(), ParseError> {
         let line = "  6  5  0  0  1  0              3 V2000";
-        let counts_lines = parse_counts(&line)?;
+        let counts_lines = parse_counts(line)?;
 
         assert_eq!(counts_lines.num_atoms, 6);
         assert_eq!(counts_lines.num_bonds, 5);
         assert_eq!(counts_lines.num_atom_lists, 0);
-        assert_eq!(counts_lines.chiral_flag, true);
+        assert!(counts_lines.chiral_flag);
         assert_eq!(counts_lines.num_stext, 0);
         assert_eq!(counts_lines.num_properties, 3);
         assert_eq!(counts_lines.version, " V2000");
@@ -246,13 +631,30 @@ mod tests {
     #[test]
     fn parse_atom_standard() -> Result<(), ParseError> {
         let line = "   -0.6622    0.5342    0.0000 C   0  0  2  0  0  0";
-        let atom = parse_atom_line(&line)?;
+        let atom = parse_atom_line(line)?;
 
-        assert_eq!(atom.element.atomic_number, 12);
+        assert_eq!(atom.element.atomic_number, 6);
         assert_eq!(atom.position, Point3d::new(-0.6622, 0.5342, 0.0000));
         assert_eq!(atom.formal_charge, 0);
         assert_eq!(atom.isotope, None);
-        assert_eq!(atom.properties.len(), 0);
+        assert_eq!(atom.properties.len(), 1);
+        assert!(matches!(
+            atom.get_property::<u32>(&AtomProperty::StereoParity),
+            Ok(Some(2))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_atom_valence() -> Result<(), ParseError> {
+        let line_default = "    0.0000    0.0000    0.0000 N   0  0  0  0  0  0";
+        let line_valence = "    0.0000    0.0000    0.0000 N   0  0  0  0  0  4";
+        let line_zero = "    0.0000    0.0000    0.0000 N   0  0  0  0  0 15";
+
+        assert_eq!(parse_atom_line(line_default)?.valence, None);
+        assert_eq!(parse_atom_line(line_valence)?.valence, Some(4));
+        assert_eq!(parse_atom_line(line_zero)?.valence, Some(0));
 
         Ok(())
     }
@@ -266,12 +668,12 @@ mod tests {
         let line_neg2 = "   -0.6622    0.5342    0.0000 C   0  6  2  0  0  0";
         let line_neg3 = "   -0.6622    0.5342    0.0000 C   0  7  2  0  0  0";
 
-        let atom_pos3 = parse_atom_line(&line_pos3)?;
-        let atom_pos2 = parse_atom_line(&line_pos2)?;
-        let atom_pos1 = parse_atom_line(&line_pos1)?;
-        let atom_neg1 = parse_atom_line(&line_neg1)?;
-        let atom_neg2 = parse_atom_line(&line_neg2)?;
-        let atom_neg3 = parse_atom_line(&line_neg3)?;
+        let atom_pos3 = parse_atom_line(line_pos3)?;
+        let atom_pos2 = parse_atom_line(line_pos2)?;
+        let atom_pos1 = parse_atom_line(line_pos1)?;
+        let atom_neg1 = parse_atom_line(line_neg1)?;
+        let atom_neg2 = parse_atom_line(line_neg2)?;
+        let atom_neg3 = parse_atom_line(line_neg3)?;
 
         assert_eq!(atom_pos3.formal_charge, 3);
         assert_eq!(atom_pos2.formal_charge, 2);
@@ -283,6 +685,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_atom_radical() -> Result<(), ParseError> {
+        let line = "   -0.6622    0.5342    0.0000 C   0  4  2  0  0  0";
+
+        let atom = parse_atom_line(line)?;
+
+        assert_eq!(atom.formal_charge, 0);
+        assert_eq!(atom.radical, Radical::Doublet);
+
+        let mut atoms = vec![atom];
+        let mut state = PropertyState::default();
+        parse_property_line("M  CHG  1   1   1", &mut atoms, &mut state)?;
+
+        assert_eq!(atoms[0].formal_charge, 1);
+        assert_eq!(atoms[0].radical, Radical::None);
+
+        Ok(())
+    }
+
     #[test]
     fn parse_atom_isotope() -> Result<(), ParseError> {
         let line_c13 = "   -0.6622    0.5342    0.0000 C   1  0  2  0  0  0";
@@ -290,10 +711,10 @@ mod tests {
         let line_c11 = "   -0.6622    0.5342    0.0000 C  -1  0  2  0  0  0";
         let line_n15 = "   -0.6622    0.5342    0.0000 N   1  0  2  0  0  0";
 
-        let atom_c13 = parse_atom_line(&line_c13)?;
-        let atom_c14 = parse_atom_line(&line_c14)?;
-        let atom_c11 = parse_atom_line(&line_c11)?;
-        let atom_n15 = parse_atom_line(&line_n15)?;
+        let atom_c13 = parse_atom_line(line_c13)?;
+        let atom_c14 = parse_atom_line(line_c14)?;
+        let atom_c11 = parse_atom_line(line_c11)?;
+        let atom_n15 = parse_atom_line(line_n15)?;
 
         assert_eq!(atom_c13.isotope, Some(13));
         assert_eq!(atom_c14.isotope, Some(14));
@@ -303,15 +724,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_atom_isotope_relative_to_average_mass() -> Result<(), ParseError> {
+        let line_br81 = "   -0.6622    0.5342    0.0000 Br  1  0  0  0  0  0";
+        let atom_br81 = parse_atom_line(line_br81)?;
+
+        assert_eq!(atom_br81.isotope, Some(81));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_atom_isotope_error_unknown() -> Result<(), ParseError> {
+        let line_h4 = "   -0.6622    0.5342    0.0000 H   3  0  0  0  0  0";
+
+        match parse_atom_line(line_h4) {
+            Err(ParseError::MoleculeError(MoleculeError::UnknownIsotope {
+                symbol,
+                mass_number,
+            })) => {
+                assert_eq!(symbol, "H");
+                assert_eq!(mass_number, 4);
+            }
+            _ => panic!("Expected MoleculeError::UnknownIsotope"),
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn parse_atom_isotope_outofrange() -> Result<(), ParseError> {
         let line_1 = "   -0.6622    0.5342    0.0000 C  -4  0  2  0  0  0";
         let line_2 = "   -0.6622    0.5342    0.0000 C   4  0  2  0  0  0";
         let line_3 = "   -0.6622    0.5342    0.0000 C   0  0  2  0  0  0";
 
-        let atom_1 = parse_atom_line(&line_1)?;
-        let atom_2 = parse_atom_line(&line_2)?;
-        let atom_3 = parse_atom_line(&line_3)?;
+        let atom_1 = parse_atom_line(line_1)?;
+        let atom_2 = parse_atom_line(line_2)?;
+        let atom_3 = parse_atom_line(line_3)?;
 
         assert_eq!(atom_1.isotope, None);
         assert_eq!(atom_2.isotope, None);
@@ -323,7 +772,7 @@ mod tests {
     #[test]
     fn parse_bond_standard() -> Result<(), ParseError> {
         let line = "  2  5  2  0  0  0";
-        let bond = parse_bond_line(&line)?;
+        let bond = parse_bond_line(line)?;
 
         assert_eq!(bond.from_atom_id, 1);
         assert_eq!(bond.to_atom_id, 4);
@@ -333,6 +782,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_bond_stereo() -> Result<(), ParseError> {
+        assert_eq!(parse_bond_line("  1  2  1  0")?.stereo, BondStereo::None);
+        assert_eq!(parse_bond_line("  1  2  1  1")?.stereo, BondStereo::Up);
+        assert_eq!(parse_bond_line("  1  2  1  6")?.stereo, BondStereo::Down);
+        assert_eq!(parse_bond_line("  1  2  1  4")?.stereo, BondStereo::Either);
+        assert_eq!(
+            parse_bond_line("  1  2  2  3")?.stereo,
+            BondStereo::CisTransEither
+        );
+
+        match parse_bond_line("  1  2  1  2") {
+            Err(ParseError::InvalidValue { name, value }) => {
+                assert_eq!(name, "bond stereochemistry");
+                assert_eq!(value, "  2");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn parse_bond_type_simple() -> Result<(), ParseError> {
         let line_single = "  2  5  1  0  0  0";
@@ -340,10 +811,10 @@ mod tests {
         let line_triple = "  2  5  3  0  0  0";
         let line_aromatic = "  2  5  4  0  0  0";
 
-        let bond_single = parse_bond_line(&line_single)?;
-        let bond_double = parse_bond_line(&line_double)?;
-        let bond_triple = parse_bond_line(&line_triple)?;
-        let bond_aromatic = parse_bond_line(&line_aromatic)?;
+        let bond_single = parse_bond_line(line_single)?;
+        let bond_double = parse_bond_line(line_double)?;
+        let bond_triple = parse_bond_line(line_triple)?;
+        let bond_aromatic = parse_bond_line(line_aromatic)?;
 
         assert_eq!(bond_single.bond_type, BondType::single());
         assert_eq!(bond_double.bond_type, BondType::double());
@@ -360,10 +831,10 @@ mod tests {
         let line_doubleoraromatic = "  2  5  7  0  0  0";
         let line_any = "  2  5  8  0  0  0";
 
-        let bond_singleordouble = parse_bond_line(&line_singleordouble)?;
-        let bond_singleoraromatic = parse_bond_line(&line_singleoraromatic)?;
-        let bond_doubleoraromatic = parse_bond_line(&line_doubleoraromatic)?;
-        let bond_any = parse_bond_line(&line_any)?;
+        let bond_singleordouble = parse_bond_line(line_singleordouble)?;
+        let bond_singleoraromatic = parse_bond_line(line_singleoraromatic)?;
+        let bond_doubleoraromatic = parse_bond_line(line_doubleoraromatic)?;
+        let bond_any = parse_bond_line(line_any)?;
 
         assert_eq!(bond_singleordouble.bond_type, BondType::single_or_double());
         assert_eq!(
@@ -384,7 +855,7 @@ mod tests {
         let line_bond_zero = "  2  5  0  0  0  0";
         let line_bond_nine = "  2  5  9  0  0  0";
 
-        match parse_bond_line(&line_bond_zero) {
+        match parse_bond_line(line_bond_zero) {
             Err(ParseError::InvalidValue { name, value }) => {
                 assert_eq!(name, "bond type");
                 assert_eq!(value, "  0");
@@ -392,7 +863,7 @@ mod tests {
             _ => panic!("Expected ParseError::Parse"),
         }
 
-        match parse_bond_line(&line_bond_nine) {
+        match parse_bond_line(line_bond_nine) {
             Err(ParseError::InvalidValue { name, value }) => {
                 assert_eq!(name, "bond type");
                 assert_eq!(value, "  9");
@@ -402,4 +873,284 @@ mod tests {
 
         Ok(())
     }
+
+    fn carbon_atoms(count: usize) -> Vec<Atom> {
+        (0..count)
+            .map(|_| Atom::from_symbol("C").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parse_property_charge() -> Result<(), ParseError> {
+        let mut atoms = carbon_atoms(4);
+        atoms[0].formal_charge = 1;
+        atoms[3].formal_charge = 2;
+        let mut state = PropertyState::default();
+
+        parse_property_line("M  CHG  2   2   1   3 -15", &mut atoms, &mut state)?;
+
+        assert_eq!(atoms[0].formal_charge, 0);
+        assert_eq!(atoms[1].formal_charge, 1);
+        assert_eq!(atoms[2].formal_charge, -15);
+        assert_eq!(atoms[3].formal_charge, 0);
+
+        parse_property_line("M  CHG  1   4   3", &mut atoms, &mut state)?;
+
+        assert_eq!(atoms[1].formal_charge, 1);
+        assert_eq!(atoms[3].formal_charge, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_property_radical() -> Result<(), ParseError> {
+        let mut atoms = carbon_atoms(2);
+        atoms[0].formal_charge = 1;
+        let mut state = PropertyState::default();
+
+        parse_property_line("M  RAD  1   2   2", &mut atoms, &mut state)?;
+
+        assert_eq!(atoms[0].formal_charge, 0);
+        assert_eq!(atoms[0].radical, Radical::None);
+        assert_eq!(atoms[1].radical, Radical::Doublet);
+
+        match parse_property_line("M  RAD  1   2   4", &mut atoms, &mut state) {
+            Err(ParseError::InvalidValue { name, value }) => {
+                assert_eq!(name, "radical");
+                assert_eq!(value, "4");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_property_isotope() -> Result<(), ParseError> {
+        let mut atoms = carbon_atoms(3);
+        atoms[0].isotope = Some(13);
+        let mut state = PropertyState::default();
+
+        parse_property_line("M  ISO  2   2  14   3  12", &mut atoms, &mut state)?;
+
+        assert_eq!(atoms[0].isotope, None);
+        assert_eq!(atoms[1].isotope, Some(14));
+        assert_eq!(atoms[2].isotope, Some(12));
+
+        match parse_property_line("M  ISO  1   1  40", &mut atoms, &mut state) {
+            Err(ParseError::MoleculeError(MoleculeError::UnknownIsotope {
+                mass_number, ..
+            })) => {
+                assert_eq!(mass_number, 40);
+            }
+            _ => panic!("Expected MoleculeError::UnknownIsotope"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_property_query_values() -> Result<(), Box<dyn std::error::Error>> {
+        let mut atoms = carbon_atoms(3);
+        let mut state = PropertyState::default();
+
+        parse_property_line("M  SUB  2   1  -1   2   3", &mut atoms, &mut state)?;
+        parse_property_line("M  UNS  1   3   1", &mut atoms, &mut state)?;
+        parse_property_line("M  RBC  1   2  -2", &mut atoms, &mut state)?;
+        parse_property_line("M  APO  1   1   3", &mut atoms, &mut state)?;
+
+        assert_eq!(
+            atoms[0].get_property::<i32>(&AtomProperty::SubstitutionCount)?,
+            Some(-1)
+        );
+        assert_eq!(
+            atoms[1].get_property::<i32>(&AtomProperty::SubstitutionCount)?,
+            Some(3)
+        );
+        assert_eq!(
+            atoms[2].get_property::<bool>(&AtomProperty::Unsaturated)?,
+            Some(true)
+        );
+        assert_eq!(
+            atoms[1].get_property::<i32>(&AtomProperty::RingBondCount)?,
+            Some(-2)
+        );
+        assert_eq!(
+            atoms[0].get_property::<u32>(&AtomProperty::AttachmentPoint)?,
+            Some(3)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_property_atom_list() -> Result<(), Box<dyn std::error::Error>> {
+        let mut atoms = carbon_atoms(2);
+        let mut state = PropertyState::default();
+
+        parse_property_line("M  ALS   2  3 T N   O   Cl  ", &mut atoms, &mut state)?;
+
+        let atom_list = atoms[1]
+            .get_property_ref::<AtomList>(&AtomProperty::AtomList)?
+            .unwrap();
+        assert!(atom_list.exclude);
+        assert_eq!(
+            atom_list.elements,
+            vec![
+                Element::from_symbol("N")?,
+                Element::from_symbol("O")?,
+                Element::from_symbol("Cl")?
+            ]
+        );
+        assert_eq!(atom_list.to_string(), "NOT [N,O,Cl]");
+
+        match parse_property_line("M  ALS   1  2 F N   ", &mut atoms, &mut state) {
+            Err(ParseError::InvalidValue { name, .. }) => {
+                assert_eq!(name, "atom list count");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_property_label() -> Result<(), Box<dyn std::error::Error>> {
+        let mut atoms = carbon_atoms(2);
+        let mut state = PropertyState::default();
+
+        parse_property_line("M  ZZC   2 Label text", &mut atoms, &mut state)?;
+
+        assert_eq!(
+            atoms[1].get_property_string(&AtomProperty::Label)?,
+            Some("Label text")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_property_error_atom_index() -> Result<(), ParseError> {
+        let mut atoms = carbon_atoms(2);
+        let mut state = PropertyState::default();
+
+        match parse_property_line("M  CHG  1   3   1", &mut atoms, &mut state) {
+            Err(ParseError::InvalidValue { name, value }) => {
+                assert_eq!(name, "atom index");
+                assert_eq!(value, "3");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        match parse_property_line("M  CHG  2   1   1", &mut atoms, &mut state) {
+            Err(ParseError::LineTooShort) => {}
+            _ => panic!("Expected ParseError::LineTooShort"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_atom_list() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "  2  1  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.0000    0.0000    0.0000 L   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+M  ALS   2  2 F O   S   
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let counts_line = parse_counts(&reader.read_line()?)?;
+        let mol = read_ct(&counts_line, &mut reader)?;
+
+        assert_eq!(mol.atoms()[0].element.symbol, "C");
+        assert_eq!(mol.atoms()[1].element.symbol, "O");
+        assert_eq!(
+            mol.atoms()[1]
+                .get_property_ref::<AtomList>(&AtomProperty::AtomList)?
+                .map(|atom_list| atom_list.to_string()),
+            Some("[O,S]".to_string())
+        );
+
+        let lines = "  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 L   0  0  0  0  0  0  0  0  0  0  0  0
+M  ALS   1  3 T N   O   Cl  
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let counts_line = parse_counts(&reader.read_line()?)?;
+        let mol = read_ct(&counts_line, &mut reader)?;
+
+        assert!(mol.atoms()[0].element.is_dummy());
+        assert_eq!(mol.implicit_hydrogen_count(0), 0);
+        assert_eq!(MolecularFormula::from_molecule(&mol).to_string(), "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_property_line_count() -> Result<(), Box<dyn std::error::Error>> {
+        // The number of property lines in the counts line includes 'M  END'
+        let lines = "  1  0  0  0  0  0  0  0  0  0  2 V2000
+    0.0000    0.0000    0.0000 N   0  0  0  0  0  0  0  0  0  0  0  0
+M  CHG  1   1   1
+M  END
+Next Line
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let counts_line = parse_counts(&reader.read_line()?)?;
+        let mol = read_ct(&counts_line, &mut reader)?;
+
+        assert_eq!(mol.atoms()[0].formal_charge, 1);
+        assert_eq!(reader.read_line()?, "Next Line");
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_error_atom_list_missing() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "  1  0  0  0  0  0  0  0  0  0999 V2000
+    1.0000    0.0000    0.0000 L   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let counts_line = parse_counts(&reader.read_line()?)?;
+
+        match read_ct(&counts_line, &mut reader) {
+            Err(FileReadError::LineParse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("Expected FileReadError::LineParse"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_error_bond_atom_index() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "  2  1  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.0000    0.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+  1  3  1  0  0  0  0
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let counts_line = parse_counts(&reader.read_line()?)?;
+
+        match read_ct(&counts_line, &mut reader) {
+            Err(FileReadError::LineParse {
+                source: ParseError::MoleculeError(MoleculeError::InvalidAtomIndex(index)),
+                line,
+            }) => {
+                assert_eq!(index, 2);
+                assert_eq!(line, 4);
+            }
+            _ => panic!("Expected MoleculeError::InvalidAtomIndex"),
+        }
+
+        match parse_bond_line("  0  1  1  0  0  0  0") {
+            Err(ParseError::InvalidValue { name, .. }) => assert_eq!(name, "atom 1"),
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
 }
@@ -1,6 +1,504 @@
-use crate::io::LineReader;
+use super::utils::{
+    bond_type_code, parse_bond_type, parse_radical, radical_code, stereo_parity_code,
+    written_valence,
+};
+use crate::io::utils::{parse_f64, parse_i32, parse_u32, parse_usize};
+use crate::io::{FileReadError, FileWriteError, LineReader, ParseError};
+use crate::mol::{
+    Atom, AtomIndex, AtomProperty, Bond, BondStereo, BondType, HasProperties, Isotope, Molecule,
+    Point3d, Radical, StereoGroup, StereoGroupKind,
+};
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::collections::HashMap;
+
+// Reference: https://web.archive.org/web/20070630061308/http:/www.mdl.com/downloads/public/ctfile/ctfile.pdf
+
+pub fn read_ct(
+    line_reader: &mut LineReader<impl std::io::Read>,
+) -> Result<Molecule, FileReadError> {
+    let (line, line_number) = next_v3000_line(line_reader)?;
+    if line.trim() != "BEGIN CTAB" {
+        return Err(unexpected_tag("BEGIN CTAB", &line, line_number));
+    }
+
+    let (line, line_number) = next_v3000_line(line_reader)?;
+    let counts_line = parse_counts_line(&line).map_err(|source| FileReadError::LineParse {
+        source,
+        line: line_number,
+    })?;
+
+    let mut molecule = Molecule::from_graph(
+        Vec::with_capacity(counts_line.num_atoms),
+        Vec::with_capacity(counts_line.num_bonds),
+    );
+    molecule.stereo_mut().chiral = counts_line.chiral_flag;
+    let mut atom_indices: HashMap<usize, AtomIndex> = HashMap::new();
+
+    loop {
+        let (line, line_number) = next_v3000_line(line_reader)?;
+        match line.trim() {
+            "END CTAB" => break,
+            "BEGIN ATOM" => {
+                for (index, atom) in read_block(line_reader, "ATOM", parse_atom_line)? {
+                    atom_indices.insert(index, molecule.add_atom(atom));
+                }
+            }
+            "BEGIN BOND" => {
+                read_block(line_reader, "BOND", |line| {
+                    molecule.add_bond(parse_bond_line(line, &atom_indices)?)?;
+                    Ok(())
+                })?;
+            }
+            "BEGIN COLLECTION" => {
+                for group in read_block(line_reader, "COLLECTION", |line| {
+                    parse_collection_line(line, &atom_indices)
+                })? {
+                    molecule.stereo_mut().groups.extend(group);
+                }
+            }
+            block if block.starts_with("BEGIN ") => {
+                read_block(line_reader, &block[6..], |_| Ok(()))?;
+            }
+            _ => return Err(unexpected_tag("END CTAB", &line, line_number)),
+        }
+    }
+
+    loop {
+        let line = line_reader.read_line()?;
+        if line.starts_with("M  END") {
+            break;
+        }
+    }
+
+    Ok(molecule)
+}
+
+#[derive(Debug)]
+struct CountsLine {
+    num_atoms: usize,
+    num_bonds: usize,
+    chiral_flag: bool,
+}
+
+fn parse_counts_line(line: &str) -> Result<CountsLine, ParseError> {
+    /*
+    Counts Line: 'COUNTS na nb nsg n3d chiral [REGNO=regno]'
+
+    na = number of atoms
+    nb = number of bonds
+    nsg = number of Sgroups
+    n3d = number of 3D constraints
+    chiral = chiral flag (1=chiral, 0=achiral)
+    regno = molecule registry number
+    */
+
+    let values = split_v3000_values(line)?;
+    if values.len() < 6 {
+        return Err(ParseError::LineTooShort);
+    }
+    if values[0] != "COUNTS" {
+        return Err(ParseError::UnexpectedTag {
+            message: format!("Expected 'COUNTS' but found '{}'", values[0]),
+        });
+    }
+
+    let counts_line = CountsLine {
+        num_atoms: parse_usize(&values[1], "atom count")?,
+        num_bonds: parse_usize(&values[2], "bond count")?,
+        chiral_flag: parse_u32(&values[5], "chiral flag")? != 0,
+    };
+    let _num_sgroups = parse_u32(&values[3], "Sgroup count")?;
+    let _num_3d = parse_u32(&values[4], "3D constraint count")?;
+
+    Ok(counts_line)
+}
+
+fn parse_atom_line(line: &str) -> Result<(usize, Atom), ParseError> {
+    /*
+    Atom Line: 'index type x y z aamap [CHG=val] [RAD=val] [CFG=val] [MASS=val] [VAL=val] [HCOUNT=val] ...'
+
+    index = atom index (starting from 1, referenced by the bond block)
+    type = atom symbol (or atom list, 'A'/'Q'/'*'=unspecified, 'R#'=R-group)
+    x, y, z = coordinates
+    aamap = atom-atom mapping number (for reactions, 0=none)
+    CHG = formal charge
+    RAD = radical (0=none, 1=singlet, 2=doublet, 3=triplet)
+    CFG = atom stereo parity (0=not stereo, 1=odd, 2=even, 3=either/unmarked)
+    MASS = absolute atomic mass
+    VAL = valence (-1=zero valence)
+    HCOUNT (*) = hydrogen count (-1=H0, 1..n=Hn or more)
+
+    NB: (*) specifies properties that apply only to queries
+    */
+
+    let values = split_v3000_values(line)?;
+    if values.len() < 6 {
+        return Err(ParseError::LineTooShort);
+    }
+
+    let index = parse_usize(&values[0], "atom index")?;
+    let x = parse_f64(&values[2], "x-coordinate")?;
+    let y = parse_f64(&values[3], "y-coordinate")?;
+    let z = parse_f64(&values[4], "z-coordinate")?;
+    let _atom_mapping = parse_u32(&values[5], "atom-atom mapping number")?;
+
+    let mut atom = Atom::from_symbol(&values[1])?;
+    atom.position = Point3d::new(x, y, z);
+
+    for (keyword, value) in parse_keywords(&values[6..])? {
+        match keyword {
+            "CHG" => atom.formal_charge = parse_i32(value, "charge")?,
+            "MASS" => {
+                let mass_number = parse_u32(value, "mass")?;
+                atom.isotope = Some(Isotope::lookup(atom.element, mass_number)?.mass_number);
+            }
+            "RAD" => atom.radical = parse_radical(value)?,
+            "CFG" => match parse_u32(value, "atom stereo parity")? {
+                0 => {}
+                stereo_parity => atom.set_property(AtomProperty::StereoParity, stereo_parity),
+            },
+            // -1 stands for zero, and 0 for the default
+            "VAL" => atom.valence = parse_explicit_count(value, "valence")?,
+            // A query for a minimum hydrogen count rather than the number of hydrogens
+            "HCOUNT" => match parse_i32(value, "hydrogen count")? {
+                0 => {}
+                count if count == -1 || count > 0 => {
+                    atom.set_property(AtomProperty::HydrogenCount, count)
+                }
+                _ => {
+                    return Err(ParseError::InvalidValue {
+                        name: "hydrogen count".to_string(),
+                        value: value.to_string(),
+                    })
+                }
+            },
+            _ => {}
+        }
+    }
+
+    Ok((index, atom))
+}
+
+fn parse_explicit_count(value: &str, dest_nature: &str) -> Result<Option<u32>, ParseError> {
+    match parse_i32(value, dest_nature)? {
+        -1 => Ok(Some(0)),
+        0 => Ok(None),
+        count if count > 0 => Ok(Some(count as u32)),
+        _ => Err(ParseError::InvalidValue {
+            name: dest_nature.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
+fn parse_bond_line(
+    line: &str,
+    atom_indices: &HashMap<usize, AtomIndex>,
+) -> Result<Bond, ParseError> {
+    /*
+    Bond Line: 'index type atom1 atom2 [CFG=val] [TOPO=val] [RXCTR=val] [STBOX=val]'
+
+    index = bond index (starting from 1)
+    type = bond type (1=single, 2=double, 3=triple; (*) 4=aromatic, 5=single/double, 6=single/aromatic, 7=double/aromatic, 8=any)
+    atom1, atom2 = atom indices as given in the atom block
+    CFG = bond configuration (0=none, 1=up, 2=either (either cis/trans for double bonds), 3=down)
+    TOPO (*) = bond topology (0=either, 1=ring, 2=chain)
+    RXCTR (*) = reacting centre status
+    STBOX (*) = stereo care box
+
+    NB: (*) specifies properties that apply only to queries
+    */
+
+    let values = split_v3000_values(line)?;
+    if values.len() < 4 {
+        return Err(ParseError::LineTooShort);
+    }
+
+    let _index = parse_usize(&values[0], "bond index")?;
+    let bond_type = parse_bond_type(&values[1])?;
+    let from_atom_id = lookup_atom_index(&values[2], atom_indices)?;
+    let to_atom_id = lookup_atom_index(&values[3], atom_indices)?;
+
+    let mut bond = Bond::new(from_atom_id, to_atom_id, bond_type);
+    for (keyword, value) in parse_keywords(&values[4..])? {
+        match keyword {
+            "CFG" => {
+                bond.stereo = match parse_u32(value, "bond configuration")? {
+                    0 => BondStereo::None,
+                    1 => BondStereo::Up,
+                    2 if bond.bond_type == BondType::double() => BondStereo::CisTransEither,
+                    2 => BondStereo::Either,
+                    3 => BondStereo::Down,
+                    _ => {
+                        return Err(ParseError::InvalidValue {
+                            name: "bond configuration".to_string(),
+                            value: value.to_string(),
+                        })
+                    }
+                }
+            }
+            "TOPO" => {
+                let _bond_topology = parse_u32(value, "bond topology")?;
+            }
+            "RXCTR" => {
+                let _reacting_center = parse_i32(value, "reacting center status")?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(bond)
+}
+
+fn parse_collection_line(
+    line: &str,
+    atom_indices: &HashMap<usize, AtomIndex>,
+) -> Result<Option<StereoGroup>, ParseError> {
+    /*
+    Collection Line: 'name [ATOMS=(n a1 a2 ...)] [BONDS=(n b1 b2 ...)]'
+
+    name = collection name, of which only stereo groups are read:
+        'MDLV30/STEABS' = absolute configuration
+        'MDLV30/STERELn' = relative configuration (OR group n)
+        'MDLV30/STERACn' = racemic mixture (AND group n)
+    ATOMS = number of atoms, then the atom indices as given in the atom block
+    */
+
+    let values = split_v3000_values(line)?;
+    if values.is_empty() {
+        return Err(ParseError::LineTooShort);
+    }
+
+    let (kind, number) = match values[0].as_str() {
+        "MDLV30/STEABS" => (StereoGroupKind::Absolute, 0),
+        name if name.starts_with("MDLV30/STEREL") => (
+            StereoGroupKind::Or,
+            parse_u32(&name[13..], "stereo group number")?,
+        ),
+        name if name.starts_with("MDLV30/STERAC") => (
+            StereoGroupKind::And,
+            parse_u32(&name[13..], "stereo group number")?,
+        ),
+        _ => return Ok(None),
+    };
+
+    let mut atoms = Vec::new();
+    for (keyword, value) in parse_keywords(&values[1..])? {
+        if keyword == "ATOMS" {
+            for atom in parse_index_list(value, "atom list")? {
+                atoms.push(lookup_atom_index(atom, atom_indices)?);
+            }
+        }
+    }
+
+    Ok(Some(StereoGroup {
+        kind,
+        number,
+        atoms,
+    }))
+}
+
+fn parse_index_list<'a>(value: &'a str, dest_nature: &str) -> Result<Vec<&'a str>, ParseError> {
+    // '(n i1 i2 ...)', where n is the number of indices that follow
+    let invalid_value = || ParseError::InvalidValue {
+        name: dest_nature.to_string(),
+        value: value.to_string(),
+    };
+
+    let values = value
+        .strip_prefix('(')
+        .and_then(|value| value.strip_suffix(')'))
+        .ok_or_else(invalid_value)?
+        .split_whitespace()
+        .collect::<Vec<&str>>();
+    match values.split_first() {
+        Some((count, indices)) if parse_usize(count, dest_nature)? == indices.len() => {
+            Ok(indices.to_vec())
+        }
+        _ => Err(invalid_value()),
+    }
+}
+
+fn lookup_atom_index(
+    value: &str,
+    atom_indices: &HashMap<usize, AtomIndex>,
+) -> Result<AtomIndex, ParseError> {
+    match atom_indices.get(&parse_usize(value, "atom index")?) {
+        Some(atom_index) => Ok(*atom_index),
+        None => Err(ParseError::InvalidValue {
+            name: "atom index".to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
+fn read_block<R: std::io::Read, T>(
+    line_reader: &mut LineReader<R>,
+    name: &str,
+    mut parse_line: impl FnMut(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, FileReadError> {
+    let end_tag = format!("END {}", name);
+    let mut items = Vec::new();
+
+    loop {
+        let (line, line_number) = next_v3000_line(line_reader)?;
+        if line.trim() == end_tag {
+            return Ok(items);
+        }
+
+        items.push(
+            parse_line(&line).map_err(|source| FileReadError::LineParse {
+                source,
+                line: line_number,
+            })?,
+        );
+    }
+}
+
+fn next_v3000_line(
+    line_reader: &mut LineReader<impl std::io::Read>,
+) -> Result<(String, usize), FileReadError> {
+    let line = line_reader.read_line()?;
+    let line_number = line_reader.line_number();
+
+    if !line.starts_with("M  V30 ") {
+        return Err(unexpected_tag("M  V30", &line, line_number));
+    }
+
+    let line = read_v3000_line(&line, line_reader)?;
+    Ok((line[7..].to_string(), line_number))
+}
+
+fn unexpected_tag(expected: &str, line: &str, line_number: usize) -> FileReadError {
+    FileReadError::LineParse {
+        source: ParseError::UnexpectedTag {
+            message: format!("Expected '{}' but found '{}'", expected, line.trim()),
+        },
+        line: line_number,
+    }
+}
+
+pub fn write_ct(
+    molecule: &Molecule,
+    writer: &mut impl std::io::Write,
+) -> Result<(), FileWriteError> {
+    writeln!(writer, "  0  0  0     0  0            999 V3000")?;
+    write_v3000_line(writer, "BEGIN CTAB")?;
+    write_v3000_line(
+        writer,
+        &format!(
+            "COUNTS {} {} 0 0 {}",
+            molecule.atoms().len(),
+            molecule.bonds().len(),
+            molecule.stereo().chiral as u32
+        ),
+    )?;
+
+    write_v3000_line(writer, "BEGIN ATOM")?;
+    for (index, atom) in molecule.atoms().iter().enumerate() {
+        let mut line = format!(
+            "{} {} {:.4} {:.4} {:.4} 0",
+            index + 1,
+            atom.element.symbol,
+            atom.position.x,
+            atom.position.y,
+            atom.position.z
+        );
+        if atom.formal_charge != 0 {
+            line.push_str(&format!(" CHG={}", atom.formal_charge));
+        }
+        if atom.radical != Radical::None {
+            line.push_str(&format!(" RAD={}", radical_code(&atom.radical)));
+        }
+        match stereo_parity_code(molecule, index) {
+            0 => {}
+            stereo_parity => line.push_str(&format!(" CFG={}", stereo_parity)),
+        }
+        if let Some(isotope) = atom.isotope {
+            line.push_str(&format!(" MASS={}", isotope));
+        }
+        match written_valence(molecule, index) {
+            Some(0) => line.push_str(" VAL=-1"),
+            Some(valence) => line.push_str(&format!(" VAL={}", valence)),
+            None => {}
+        }
+        if let Some(count) = atom.get_property::<i32>(&AtomProperty::HydrogenCount)? {
+            line.push_str(&format!(" HCOUNT={}", count));
+        }
+        write_v3000_line(writer, &line)?;
+    }
+    write_v3000_line(writer, "END ATOM")?;
+
+    write_v3000_line(writer, "BEGIN BOND")?;
+    for (index, bond) in molecule.bonds().iter().enumerate() {
+        let mut line = format!(
+            "{} {} {} {}",
+            index + 1,
+            bond_type_code(&bond.bond_type)?,
+            bond.from_atom_id + 1,
+            bond.to_atom_id + 1
+        );
+        match bond.stereo {
+            BondStereo::None => {}
+            BondStereo::Up => line.push_str(" CFG=1"),
+            BondStereo::Either | BondStereo::CisTransEither => line.push_str(" CFG=2"),
+            BondStereo::Down => line.push_str(" CFG=3"),
+        }
+        write_v3000_line(writer, &line)?;
+    }
+    write_v3000_line(writer, "END BOND")?;
+
+    if !molecule.stereo().groups.is_empty() {
+        write_v3000_line(writer, "BEGIN COLLECTION")?;
+        for group in &molecule.stereo().groups {
+            let name = match group.kind {
+                StereoGroupKind::Absolute => "MDLV30/STEABS".to_string(),
+                StereoGroupKind::Or => format!("MDLV30/STEREL{}", group.number),
+                StereoGroupKind::And => format!("MDLV30/STERAC{}", group.number),
+            };
+            let atoms = group
+                .atoms
+                .iter()
+                .map(|atom| (atom + 1).to_string())
+                .collect::<Vec<String>>();
+            write_v3000_line(
+                writer,
+                &format!("{} ATOMS=({} {})", name, atoms.len(), atoms.join(" ")),
+            )?;
+        }
+        write_v3000_line(writer, "END COLLECTION")?;
+    }
+
+    write_v3000_line(writer, "END CTAB")?;
+    writeln!(writer, "M  END")?;
+    Ok(())
+}
+
+fn write_v3000_line(writer: &mut impl std::io::Write, line: &str) -> std::io::Result<()> {
+    // Lines are limited to 80 characters, so longer lines are split at a space and continued
+    // after a trailing '-' (the space is kept at the start of the continuation line)
+    let mut prefix = "M  V30 ";
+    let mut line = line;
+
+    while prefix.len() + line.len() > 80 {
+        let mut end = 80 - prefix.len() - 1;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        match line[..end].rfind(' ') {
+            Some(index) if index > 0 => {
+                writeln!(writer, "{}{}-", prefix, &line[..index])?;
+                line = &line[index..];
+                prefix = "M  V30";
+            }
+            _ => break,
+        }
+    }
+
+    writeln!(writer, "{}{}", prefix, line)
+}
 
 pub fn read_v3000_line(
     line: &str,
@@ -14,19 +512,48 @@ pub fn read_v3000_line(
     Ok(line)
 }
 
-pub fn pop_v3000_value(line: &str) -> (String, &str) {
+fn split_v3000_values(line: &str) -> Result<Vec<String>, ParseError> {
+    let mut values = Vec::new();
+    let mut rest = line.trim();
+
+    while !rest.is_empty() {
+        let (value, remaining) = pop_v3000_value(rest)?;
+        values.push(value);
+        rest = remaining.trim_start();
+    }
+
+    Ok(values)
+}
+
+fn parse_keywords(values: &[String]) -> Result<Vec<(&str, &str)>, ParseError> {
+    values
+        .iter()
+        .map(|value| match value.find('=') {
+            Some(index) => Ok((&value[..index], &value[index + 1..])),
+            None => Err(ParseError::InvalidValue {
+                name: "keyword property".to_string(),
+                value: value.to_string(),
+            }),
+        })
+        .collect()
+}
+
+pub fn pop_v3000_value(line: &str) -> Result<(String, &str), ParseError> {
     let (value, rest) = if line.starts_with('"') {
         match split_quoted_string(line) {
             Some(s) => s,
-            None => panic!(),
+            None => {
+                return Err(ParseError::InvalidValue {
+                    name: "quoted value".to_string(),
+                    value: line.to_string(),
+                })
+            }
         }
-    } else if let Some(index) = line.find(char::is_whitespace) {
-        (&line[..index], &line[index + 1..])
     } else {
-        panic!()
+        split_unquoted_string(line)
     };
 
-    (value.replace("\"\"", "\""), rest)
+    Ok((value.replace("\"\"", "\""), rest))
 }
 
 fn split_quoted_string(line: &str) -> Option<(&str, &str)> {
@@ -43,9 +570,30 @@ fn split_quoted_string(line: &str) -> Option<(&str, &str)> {
     }
 }
 
+fn split_unquoted_string(line: &str) -> (&str, &str) {
+    // Whitespace inside quotes or parenthesised lists (e.g. 'RGROUPS=(1 1)') does not end the value
+    let mut in_quotes = false;
+    let mut depth = 0;
+
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes && depth > 0 => depth -= 1,
+            c if c.is_whitespace() && !in_quotes && depth == 0 => {
+                return (&line[..index], &line[index + c.len_utf8()..])
+            }
+            _ => {}
+        }
+    }
+
+    (line, "")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mol::{BondType, MoleculeError};
 
     #[test]
     fn read_v3000_line_simple() -> Result<(), Box<dyn std::error::Error>> {
@@ -76,7 +624,7 @@ mod tests {
     #[test]
     fn pop_v3000_value_simple() -> Result<(), Box<dyn std::error::Error>> {
         let line = "Value Rest of string";
-        let (value, line) = pop_v3000_value(&line);
+        let (value, line) = pop_v3000_value(line)?;
 
         assert_eq!(value, "Value");
         assert_eq!(line, "Rest of string");
@@ -87,7 +635,7 @@ mod tests {
     #[test]
     fn pop_v3000_value_tabseparated() -> Result<(), Box<dyn std::error::Error>> {
         let line = "Value\tRest of string";
-        let (value, line) = pop_v3000_value(&line);
+        let (value, line) = pop_v3000_value(line)?;
 
         assert_eq!(value, "Value");
         assert_eq!(line, "Rest of string");
@@ -98,7 +646,7 @@ mod tests {
     #[test]
     fn pop_v3000_value_quoted() -> Result<(), Box<dyn std::error::Error>> {
         let line = "\"Value with space\" Rest of string";
-        let (value, line) = pop_v3000_value(&line);
+        let (value, line) = pop_v3000_value(line)?;
 
         assert_eq!(value, "Value with space");
         assert_eq!(line, " Rest of string");
@@ -109,7 +657,7 @@ mod tests {
     #[test]
     fn pop_v3000_value_quotedwithquote() -> Result<(), Box<dyn std::error::Error>> {
         let line = "\"Value \"\" quote\" Rest of string";
-        let (value, line) = pop_v3000_value(&line);
+        let (value, line) = pop_v3000_value(line)?;
 
         assert_eq!(value, "Value \" quote");
         assert_eq!(line, " Rest of string");
@@ -120,11 +668,316 @@ mod tests {
     #[test]
     fn pop_v3000_value_unquotedwithquote() -> Result<(), Box<dyn std::error::Error>> {
         let line = "Value\"\"quote Rest of string";
-        let (value, line) = pop_v3000_value(&line);
+        let (value, line) = pop_v3000_value(line)?;
 
         assert_eq!(value, "Value\"quote");
         assert_eq!(line, "Rest of string");
 
         Ok(())
     }
+
+    #[test]
+    fn pop_v3000_value_endofline() -> Result<(), Box<dyn std::error::Error>> {
+        let line = "Value";
+        let (value, line) = pop_v3000_value(line)?;
+
+        assert_eq!(value, "Value");
+        assert_eq!(line, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn pop_v3000_value_error_unterminatedquote() -> Result<(), Box<dyn std::error::Error>> {
+        match pop_v3000_value("\"Value Rest of string") {
+            Err(ParseError::InvalidValue { name, .. }) => {
+                assert_eq!(name, "quoted value");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn split_v3000_values_parenthesised() -> Result<(), ParseError> {
+        let values = split_v3000_values("1 R# 0 0 0 0 RGROUPS=(2 1 3) CHG=1")?;
+
+        assert_eq!(
+            values,
+            vec!["1", "R#", "0", "0", "0", "0", "RGROUPS=(2 1 3)", "CHG=1"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_counts_standard() -> Result<(), ParseError> {
+        let counts_line = parse_counts_line("COUNTS 6 5 0 0 1")?;
+
+        assert_eq!(counts_line.num_atoms, 6);
+        assert_eq!(counts_line.num_bonds, 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_atom_standard() -> Result<(), ParseError> {
+        let (index, atom) = parse_atom_line("4 N -1.8519 -0.3674 0 0")?;
+
+        assert_eq!(index, 4);
+        assert_eq!(atom.element.atomic_number, 7);
+        assert_eq!(atom.position, Point3d::new(-1.8519, -0.3674, 0.0));
+        assert_eq!(atom.formal_charge, 0);
+        assert_eq!(atom.isotope, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_atom_properties() -> Result<(), Box<dyn std::error::Error>> {
+        let (_, atom) =
+            parse_atom_line("3 C -0.7167 2.0701 0 0 CHG=-2 MASS=13 RAD=2 CFG=1 VAL=3 HCOUNT=1")?;
+
+        assert_eq!(atom.formal_charge, -2);
+        assert_eq!(atom.isotope, Some(13));
+        assert_eq!(atom.radical, Radical::Doublet);
+        assert_eq!(atom.valence, Some(3));
+        assert_eq!(atom.hydrogen_count, None);
+        assert_eq!(
+            atom.get_property::<i32>(&AtomProperty::HydrogenCount)?,
+            Some(1)
+        );
+
+        let (_, atom) = parse_atom_line("1 N 0 0 0 0 VAL=-1 HCOUNT=-1")?;
+
+        assert_eq!(atom.valence, Some(0));
+        assert_eq!(atom.hydrogen_count, None);
+        assert_eq!(
+            atom.get_property::<i32>(&AtomProperty::HydrogenCount)?,
+            Some(-1)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_atom_error_unknown_isotope() -> Result<(), ParseError> {
+        match parse_atom_line("1 C 0 0 0 0 MASS=30") {
+            Err(ParseError::MoleculeError(MoleculeError::UnknownIsotope {
+                mass_number, ..
+            })) => {
+                assert_eq!(mass_number, 30);
+            }
+            _ => panic!("Expected MoleculeError::UnknownIsotope"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_atom_error_too_short() -> Result<(), ParseError> {
+        match parse_atom_line("1 C 0 0") {
+            Err(ParseError::LineTooShort) => {}
+            _ => panic!("Expected ParseError::LineTooShort"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bond_standard() -> Result<(), ParseError> {
+        let atom_indices: HashMap<usize, AtomIndex> = vec![(2, 1), (5, 4)].into_iter().collect();
+        let bond = parse_bond_line("4 2 2 5 CFG=1", &atom_indices)?;
+
+        assert_eq!(bond.from_atom_id, 1);
+        assert_eq!(bond.to_atom_id, 4);
+        assert_eq!(bond.bond_type, BondType::double());
+        assert_eq!(bond.stereo, BondStereo::Up);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bond_either() -> Result<(), ParseError> {
+        let atom_indices: HashMap<usize, AtomIndex> = vec![(1, 0), (2, 1)].into_iter().collect();
+
+        let single = parse_bond_line("1 1 1 2 CFG=2", &atom_indices)?;
+        let double = parse_bond_line("1 2 1 2 CFG=2", &atom_indices)?;
+
+        assert_eq!(single.stereo, BondStereo::Either);
+        assert_eq!(double.stereo, BondStereo::CisTransEither);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bond_error_unknown_atom() -> Result<(), ParseError> {
+        let atom_indices: HashMap<usize, AtomIndex> = vec![(2, 1), (5, 4)].into_iter().collect();
+
+        match parse_bond_line("4 2 2 7", &atom_indices) {
+            Err(ParseError::InvalidValue { name, value }) => {
+                assert_eq!(name, "atom index");
+                assert_eq!(value, "7");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_skips_unknown_blocks() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "M  V30 BEGIN CTAB
+M  V30 COUNTS 2 1 1 0 0
+M  V30 BEGIN ATOM
+M  V30 1 C 0 0 0 0
+M  V30 2 O 1.2 0 0 0
+M  V30 END ATOM
+M  V30 BEGIN BOND
+M  V30 1 2 1 2
+M  V30 END BOND
+M  V30 BEGIN SGROUP
+M  V30 1 SUP 0 ATOMS=(2 1 2) LABEL=\"C=O\"
+M  V30 END SGROUP
+M  V30 END CTAB
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let mol = read_ct(&mut reader)?;
+
+        assert_eq!(mol.atoms().len(), 2);
+        assert_eq!(mol.bonds().len(), 1);
+        assert!(reader.read_line_optional().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_stereo_groups() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "M  V30 BEGIN CTAB
+M  V30 COUNTS 3 2 0 0 0
+M  V30 BEGIN ATOM
+M  V30 1 C 0 0 0 0
+M  V30 2 C 1.2 0 0 0
+M  V30 4 C 2.4 0 0 0
+M  V30 END ATOM
+M  V30 BEGIN BOND
+M  V30 1 1 1 2
+M  V30 2 1 2 4
+M  V30 END BOND
+M  V30 BEGIN COLLECTION
+M  V30 MDLV30/STEABS ATOMS=(1 2)
+M  V30 MDLV30/HILITE ATOMS=(1 1)
+M  V30 MDLV30/STEREL1 ATOMS=(1 1)
+M  V30 MDLV30/STERAC2 ATOMS=(2 1 4)
+M  V30 END COLLECTION
+M  V30 END CTAB
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+        let mol = read_ct(&mut reader)?;
+
+        assert_eq!(
+            mol.stereo().groups,
+            vec![
+                StereoGroup {
+                    kind: StereoGroupKind::Absolute,
+                    number: 0,
+                    atoms: vec![1],
+                },
+                StereoGroup {
+                    kind: StereoGroupKind::Or,
+                    number: 1,
+                    atoms: vec![0],
+                },
+                StereoGroup {
+                    kind: StereoGroupKind::And,
+                    number: 2,
+                    atoms: vec![0, 2],
+                },
+            ]
+        );
+
+        let mut output = Vec::new();
+        write_ct(&mol, &mut output)?;
+        let output = String::from_utf8(output)?;
+        assert!(output.contains(
+            "M  V30 BEGIN COLLECTION
+M  V30 MDLV30/STEABS ATOMS=(1 2)
+M  V30 MDLV30/STEREL1 ATOMS=(1 1)
+M  V30 MDLV30/STERAC2 ATOMS=(2 1 3)
+M  V30 END COLLECTION
+"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_collection_error_atom_count() -> Result<(), ParseError> {
+        let atom_indices: HashMap<usize, AtomIndex> = vec![(1, 0), (2, 1)].into_iter().collect();
+
+        match parse_collection_line("MDLV30/STEREL1 ATOMS=(2 1)", &atom_indices) {
+            Err(ParseError::InvalidValue { name, value }) => {
+                assert_eq!(name, "atom list");
+                assert_eq!(value, "(2 1)");
+            }
+            _ => panic!("Expected ParseError::InvalidValue"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_error_line_number() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "M  V30 BEGIN CTAB
+M  V30 COUNTS 1 0 0 0 0
+M  V30 BEGIN ATOM
+M  V30 1 C 0 x 0 0
+M  V30 END ATOM
+M  V30 END CTAB
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+
+        match read_ct(&mut reader) {
+            Err(FileReadError::LineParse { line, .. }) => {
+                assert_eq!(line, 4);
+            }
+            _ => panic!("Expected FileReadError::LineParse"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ct_error_duplicate_bond() -> Result<(), Box<dyn std::error::Error>> {
+        let lines = "M  V30 BEGIN CTAB
+M  V30 COUNTS 2 2 0 0 0
+M  V30 BEGIN ATOM
+M  V30 1 C 0 0 0 0
+M  V30 2 O 1.2 0 0 0
+M  V30 END ATOM
+M  V30 BEGIN BOND
+M  V30 1 1 1 2
+M  V30 2 2 2 1
+M  V30 END BOND
+M  V30 END CTAB
+M  END
+";
+        let mut reader = LineReader::new(lines.as_bytes());
+
+        match read_ct(&mut reader) {
+            Err(FileReadError::LineParse {
+                source: ParseError::MoleculeError(MoleculeError::DuplicateBond { .. }),
+                line,
+            }) => {
+                assert_eq!(line, 9);
+            }
+            _ => panic!("Expected MoleculeError::DuplicateBond"),
+        }
+
+        Ok(())
+    }
 }
@@ -1,26 +1,151 @@
-use super::ct_v2000::read_ct;
+use super::ct_v2000::parse_counts;
 use super::utils::{parse_f64_default, parse_u32_default};
-use crate::io::{FileReadError, LineReader, ParseError};
-use crate::mol::{HasProperties, Molecule, MoleculeProperty};
+use super::{ct_v2000, ct_v3000};
+use crate::io::{FileReadError, FileWriteError, LineReader, ParseError};
+use crate::mol::{
+    assign_stereo_from_coordinates, assign_stereo_from_parity, HasProperties, Molecule,
+    MoleculeProperty,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CtabVersion {
+    /// V2000, unless the molecule has more than 999 atoms or bonds, or has stereo groups
+    Auto,
+    V2000,
+    V3000,
+}
+
+#[derive(Clone, Debug)]
+pub struct MolWriteOptions {
+    pub version: CtabVersion,
+}
+
+impl Default for MolWriteOptions {
+    fn default() -> Self {
+        MolWriteOptions {
+            version: CtabVersion::Auto,
+        }
+    }
+}
 
 pub fn read_mol(reader: impl std::io::Read) -> Result<Molecule, FileReadError> {
     let mut line_reader = LineReader::new(reader);
+    read_mol_record(&mut line_reader)
+}
 
+pub fn read_mol_record(
+    line_reader: &mut LineReader<impl std::io::Read>,
+) -> Result<Molecule, FileReadError> {
     // TODO: Ignoring header
     let molecule_name = line_reader.read_line()?;
-    let header_line = parse_header(&line_reader.read_line()?)
-        .map_err(|source| FileReadError::LineParse { source, line: 2 })?;
+    let header_line =
+        parse_header(&line_reader.read_line()?).map_err(|source| FileReadError::LineParse {
+            source,
+            line: line_reader.line_number(),
+        })?;
     let molecule_comment = line_reader.read_line()?;
 
-    let mut molecule = read_ct(&mut line_reader)?;
+    let counts_line =
+        parse_counts(&line_reader.read_line()?).map_err(|source| FileReadError::LineParse {
+            source,
+            line: line_reader.line_number(),
+        })?;
+
+    let mut molecule = match counts_line.version.trim() {
+        "V3000" => ct_v3000::read_ct(line_reader)?,
+        _ => ct_v2000::read_ct(&counts_line, line_reader)?,
+    };
+    // Wedges and coordinates take precedence over atom parities, which are often left unset
+    assign_stereo_from_coordinates(&mut molecule);
+    assign_stereo_from_parity(&mut molecule);
+
     molecule.set_property(MoleculeProperty::Name, molecule_name);
     molecule.set_property(MoleculeProperty::Comment, molecule_comment);
     molecule.set_property(MoleculeProperty::CreationUser, header_line.user);
     molecule.set_property(MoleculeProperty::CreationProgram, header_line.program);
     molecule.set_property(MoleculeProperty::CreationDate, header_line.datetime);
+    molecule.set_property(
+        MoleculeProperty::Dimensions,
+        if header_line.flag_3d { 3u32 } else { 2u32 },
+    );
+    molecule.set_property(MoleculeProperty::ScalingFactorInt, header_line.scaling_int);
+    molecule.set_property(
+        MoleculeProperty::ScalingFactorFloat,
+        header_line.scaling_float,
+    );
+    molecule.set_property(MoleculeProperty::Energy, header_line.energy);
+    molecule.set_property(MoleculeProperty::RegistryNumber, header_line.reg_number);
     Ok(molecule)
 }
 
+pub fn write_mol(
+    molecule: &Molecule,
+    mut writer: impl std::io::Write,
+    options: MolWriteOptions,
+) -> Result<(), FileWriteError> {
+    let property = |property| -> Result<&str, FileWriteError> {
+        Ok(molecule.get_property_string(&property)?.unwrap_or(""))
+    };
+
+    let program = molecule
+        .get_property_string(&MoleculeProperty::CreationProgram)?
+        .unwrap_or("rustchem");
+    let dimensions = if molecule.has_3d_coordinates() {
+        "3D"
+    } else {
+        "2D"
+    };
+    // The scaling factors, energy and registry number are only written if they were read
+    let scaling_int = molecule.get_property::<u32>(&MoleculeProperty::ScalingFactorInt)?;
+    let scaling_float = molecule.get_property::<f64>(&MoleculeProperty::ScalingFactorFloat)?;
+    let energy = molecule.get_property::<f64>(&MoleculeProperty::Energy)?;
+    let reg_number = molecule.get_property::<u32>(&MoleculeProperty::RegistryNumber)?;
+    let header_data = if scaling_int.is_some()
+        || scaling_float.is_some()
+        || energy.is_some()
+        || reg_number.is_some()
+    {
+        format!(
+            "{:2}{:10.5}{:12.5}{:6}",
+            scaling_int.unwrap_or(0),
+            scaling_float.unwrap_or(0.0),
+            energy.unwrap_or(0.0),
+            reg_number.unwrap_or(0)
+        )
+    } else {
+        String::new()
+    };
+
+    writeln!(writer, "{}", property(MoleculeProperty::Name)?)?;
+    writeln!(
+        writer,
+        "{:2.2}{:8.8}{:10.10}{}{}",
+        property(MoleculeProperty::CreationUser)?,
+        program,
+        property(MoleculeProperty::CreationDate)?,
+        dimensions,
+        header_data
+    )?;
+    writeln!(writer, "{}", property(MoleculeProperty::Comment)?)?;
+
+    let version = match options.version {
+        CtabVersion::Auto
+            if molecule.atoms().len() > 999
+                || molecule.bonds().len() > 999
+                || !molecule.stereo().groups.is_empty() =>
+        {
+            CtabVersion::V3000
+        }
+        CtabVersion::Auto => CtabVersion::V2000,
+        version => version,
+    };
+
+    match version {
+        CtabVersion::V3000 => ct_v3000::write_ct(molecule, &mut writer),
+        _ => ct_v2000::write_ct(molecule, &mut writer),
+    }
+}
+
 #[derive(Debug)]
 struct HeaderLine {
     pub user: String,
@@ -68,6 +193,9 @@ fn parse_header(line: &str) -> Result<HeaderLine, ParseError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mol::{
+        Atom, Bond, BondStereo, BondType, Point3d, Radical, TetrahedralParity, TetrahedralStereo,
+    };
     use crate::test_utils::assert_f64_eq;
     use std::fs::File;
 
@@ -87,6 +215,332 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_mol_alanine_v3000() -> Result<(), Box<dyn std::error::Error>> {
+        let file = File::open("./test_files/alanine_v3000.mol")?;
+        let mol = read_mol(file)?;
+
+        let symbols = mol
+            .atoms()
+            .iter()
+            .map(|atom| atom.element.symbol.as_str())
+            .collect::<Vec<&str>>();
+        let charges = mol
+            .atoms()
+            .iter()
+            .map(|atom| atom.formal_charge)
+            .collect::<Vec<i32>>();
+        let isotopes = mol
+            .atoms()
+            .iter()
+            .map(|atom| atom.isotope)
+            .collect::<Vec<Option<u32>>>();
+
+        assert_eq!(symbols, vec!["C", "C", "C", "N", "O", "O"]);
+        assert_eq!(charges, vec![0, 0, 0, 1, 0, -1]);
+        assert_eq!(isotopes, vec![None, None, Some(13), None, None, None]);
+        assert_eq!(mol.atoms()[2].position, Point3d::new(-0.7167, 2.0701, 0.0));
+
+        assert_eq!(mol.bonds().len(), 5);
+        assert_eq!(mol.bonds()[3].from_atom_id, 1);
+        assert_eq!(mol.bonds()[3].to_atom_id, 4);
+        assert_eq!(mol.bonds()[3].bond_type, BondType::double());
+
+        assert_eq!(
+            mol.get_property_string(&MoleculeProperty::Name)?,
+            Some("L-Alanine (13C)")
+        );
+        assert_eq!(
+            mol.get_property_string(&MoleculeProperty::CreationProgram)?,
+            Some("Mrv1815 ")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_mol_v2000_and_v3000_match() -> Result<(), Box<dyn std::error::Error>> {
+        let mol_v2000 = read_mol(File::open("./test_files/alanine_v2000.mol")?)?;
+        let mol_v3000 = read_mol(File::open("./test_files/alanine_v3000.mol")?)?;
+
+        assert_eq!(mol_v2000.atoms().len(), mol_v3000.atoms().len());
+        for (atom_v2000, atom_v3000) in mol_v2000.atoms().iter().zip(mol_v3000.atoms().iter()) {
+            assert_eq!(atom_v2000.element, atom_v3000.element);
+            assert_eq!(atom_v2000.formal_charge, atom_v3000.formal_charge);
+            assert_eq!(atom_v2000.isotope, atom_v3000.isotope);
+        }
+
+        assert_eq!(mol_v2000.bonds().len(), mol_v3000.bonds().len());
+        for (bond_v2000, bond_v3000) in mol_v2000.bonds().iter().zip(mol_v3000.bonds().iter()) {
+            assert_eq!(bond_v2000.from_atom_id, bond_v3000.from_atom_id);
+            assert_eq!(bond_v2000.to_atom_id, bond_v3000.to_atom_id);
+            assert_eq!(bond_v2000.bond_type, bond_v3000.bond_type);
+            assert_eq!(bond_v2000.stereo, bond_v3000.stereo);
+        }
+        assert_eq!(mol_v2000.stereo(), mol_v3000.stereo());
+        Ok(())
+    }
+
+    #[test]
+    fn read_mol_stereo() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_mol(File::open("./test_files/alanine_v2000.mol")?)?;
+
+        assert!(mol.stereo().chiral);
+        assert_eq!(mol.bonds()[1].stereo, BondStereo::Up);
+        assert_eq!(
+            mol.stereo().tetrahedral,
+            vec![TetrahedralStereo {
+                center: 0,
+                neighbors: [1, 2, 3, 0],
+                parity: TetrahedralParity::Anticlockwise,
+            }]
+        );
+
+        // Without the wedge, the configuration comes from the atom parity
+        let mut lines = std::fs::read_to_string("./test_files/alanine_v2000.mol")?;
+        lines = lines.replace("  1  3  1  1  0  0", "  1  3  1  0  0  0");
+        let from_parity = read_mol(lines.as_bytes())?;
+        assert_eq!(from_parity.bonds()[1].stereo, BondStereo::None);
+        assert_eq!(from_parity.stereo(), mol.stereo());
+
+        Ok(())
+    }
+
+    fn assert_equivalent(left: &Molecule, right: &Molecule) {
+        assert_eq!(left.atoms().len(), right.atoms().len());
+        for (atom_left, atom_right) in left.atoms().iter().zip(right.atoms().iter()) {
+            assert_eq!(atom_left.element, atom_right.element);
+            assert_eq!(atom_left.position, atom_right.position);
+            assert_eq!(atom_left.formal_charge, atom_right.formal_charge);
+            assert_eq!(atom_left.isotope, atom_right.isotope);
+            assert_eq!(atom_left.radical, atom_right.radical);
+        }
+
+        assert_eq!(left.bonds().len(), right.bonds().len());
+        for (bond_left, bond_right) in left.bonds().iter().zip(right.bonds().iter()) {
+            assert_eq!(bond_left.from_atom_id, bond_right.from_atom_id);
+            assert_eq!(bond_left.to_atom_id, bond_right.to_atom_id);
+            assert_eq!(bond_left.bond_type, bond_right.bond_type);
+            assert_eq!(bond_left.stereo, bond_right.stereo);
+        }
+        assert_eq!(left.stereo(), right.stereo());
+
+        for property in &[
+            MoleculeProperty::Name,
+            MoleculeProperty::Comment,
+            MoleculeProperty::CreationUser,
+            MoleculeProperty::CreationDate,
+        ] {
+            assert_eq!(
+                left.get_property_string(property)
+                    .unwrap()
+                    .unwrap_or("")
+                    .trim(),
+                right
+                    .get_property_string(property)
+                    .unwrap()
+                    .unwrap_or("")
+                    .trim()
+            );
+        }
+    }
+
+    fn write_to_string(
+        molecule: &Molecule,
+        options: MolWriteOptions,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut output = Vec::new();
+        write_mol(molecule, &mut output, options)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    #[test]
+    fn write_mol_v2000() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_mol(File::open("./test_files/alanine_v2000.mol")?)?;
+        let output = write_to_string(&mol, MolWriteOptions::default())?;
+        let lines = output.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines[0], "L-Alanine (13C)");
+        assert_eq!(
+            lines[1],
+            "GSMACCS-II10169115362D 1   0.00366     0.00000     0"
+        );
+        assert_eq!(lines[2], "Additional Comments");
+        assert_eq!(lines[3], "  6  5  0  0  1  0  0  0  0  0999 V2000");
+        assert_eq!(
+            lines[4],
+            "   -0.6622    0.5342    0.0000 C   0  0  2  0  0  0  0  0  0  0  0  0"
+        );
+        assert_eq!(
+            lines[6],
+            "   -0.7207    2.0817    0.0000 C   1  0  0  0  0  0  0  0  0  0  0  0"
+        );
+        assert_eq!(lines[10], "  1  2  1  0  0  0  0");
+        assert_eq!(lines[11], "  1  3  1  1  0  0  0");
+        assert_eq!(lines[15], "M  CHG  2   4   1   6  -1");
+        assert_eq!(lines[16], "M  ISO  1   3  13");
+        assert_eq!(lines[17], "M  END");
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_v3000() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_mol(File::open("./test_files/alanine_v3000.mol")?)?;
+        let options = MolWriteOptions {
+            version: CtabVersion::V3000,
+        };
+        let output = write_to_string(&mol, options)?;
+        let lines = output.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines[3], "  0  0  0     0  0            999 V3000");
+        assert_eq!(lines[4], "M  V30 BEGIN CTAB");
+        assert_eq!(lines[5], "M  V30 COUNTS 6 5 0 0 1");
+        assert_eq!(lines[7], "M  V30 1 C -0.6585 0.5312 0.0000 0 CFG=2");
+        assert_eq!(lines[9], "M  V30 3 C -0.7167 2.0701 0.0000 0 MASS=13");
+        assert_eq!(lines[10], "M  V30 4 N -1.8519 -0.3674 0.0000 0 CHG=1");
+        assert_eq!(lines[16], "M  V30 2 1 1 3 CFG=1");
+        assert_eq!(lines[18], "M  V30 4 2 2 5");
+        assert_eq!(lines[21], "M  V30 END CTAB");
+        assert_eq!(lines[22], "M  END");
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_roundtrip_v2000() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_mol(File::open("./test_files/alanine_v2000.mol")?)?;
+        let output = write_to_string(&mol, MolWriteOptions::default())?;
+        let roundtrip = read_mol(output.as_bytes())?;
+
+        assert_equivalent(&mol, &roundtrip);
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_roundtrip_v3000() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_mol(File::open("./test_files/alanine_v3000.mol")?)?;
+        let options = MolWriteOptions {
+            version: CtabVersion::V3000,
+        };
+        let output = write_to_string(&mol, options)?;
+        let roundtrip = read_mol(output.as_bytes())?;
+
+        assert_equivalent(&mol, &roundtrip);
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_roundtrip_radicals() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = Molecule::new();
+        for (charge, radical) in &[
+            (0, Radical::Doublet),
+            (1, Radical::Doublet),
+            (0, Radical::Singlet),
+            (0, Radical::Triplet),
+        ] {
+            let mut atom = Atom::from_symbol("C")?;
+            atom.formal_charge = *charge;
+            atom.radical = *radical;
+            mol.add_atom(atom);
+        }
+
+        for version in &[CtabVersion::V2000, CtabVersion::V3000] {
+            let options = MolWriteOptions { version: *version };
+            let output = write_to_string(&mol, options)?;
+            let roundtrip = read_mol(output.as_bytes())?;
+
+            assert_equivalent(&mol, &roundtrip);
+        }
+
+        let output = write_to_string(&mol, MolWriteOptions::default())?;
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines[4],
+            "    0.0000    0.0000    0.0000 C   0  4  0  0  0  0  0  0  0  0  0  0"
+        );
+        assert_eq!(lines[9], "M  RAD  4   1   2   2   2   3   1   4   3");
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_roundtrip_valence() -> Result<(), Box<dyn std::error::Error>> {
+        // A carbene, an aminyl with no hydrogens and a methyl group with its default valence
+        let mut mol = Molecule::new();
+        mol.add_atom(Atom::from_symbol("C")?);
+        mol.add_atom(Atom::from_symbol("N")?);
+        mol.add_atom(Atom::from_symbol("C")?);
+        mol.add_bond(Bond::new(0, 1, BondType::single()))?;
+        mol.add_bond(Bond::new(1, 2, BondType::single()))?;
+        mol.atoms_mut()[0].valence = Some(2);
+        mol.atoms_mut()[1].hydrogen_count = Some(0);
+
+        for version in &[CtabVersion::V2000, CtabVersion::V3000] {
+            let options = MolWriteOptions { version: *version };
+            let output = write_to_string(&mol, options)?;
+            let roundtrip = read_mol(output.as_bytes())?;
+
+            for atom in 0..3 {
+                assert_eq!(
+                    roundtrip.implicit_hydrogen_count(atom),
+                    mol.implicit_hydrogen_count(atom)
+                );
+            }
+            assert_eq!(roundtrip.atoms()[2].valence, None);
+        }
+
+        let output = write_to_string(&mol, MolWriteOptions::default())?;
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines[5],
+            "    0.0000    0.0000    0.0000 N   0  0  0  0  0  2  0  0  0  0  0  0"
+        );
+
+        let options = MolWriteOptions {
+            version: CtabVersion::V3000,
+        };
+        let output = write_to_string(&mol, options)?;
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[7], "M  V30 1 C 0.0000 0.0000 0.0000 0 VAL=2");
+        assert_eq!(lines[9], "M  V30 3 C 0.0000 0.0000 0.0000 0");
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_auto_v3000_for_large_molecules() -> Result<(), Box<dyn std::error::Error>> {
+        let mut atoms = Vec::new();
+        for index in 0..1000 {
+            let mut atom = Atom::from_symbol("C")?;
+            atom.position = Point3d::new(index as f64, 0.0, 0.0);
+            atoms.push(atom);
+        }
+        let bonds = (1..1000)
+            .map(|index| Bond::new(index - 1, index, BondType::single()))
+            .collect();
+        let mol = Molecule::from_graph(atoms, bonds);
+
+        let output = write_to_string(&mol, MolWriteOptions::default())?;
+        let roundtrip = read_mol(output.as_bytes())?;
+
+        assert!(output.lines().nth(3).unwrap().ends_with(" V3000"));
+        assert_equivalent(&mol, &roundtrip);
+        Ok(())
+    }
+
+    #[test]
+    fn write_mol_error_v2000_too_large() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = Molecule::new();
+        for _ in 0..1000 {
+            mol.add_atom(Atom::from_symbol("C")?);
+        }
+        let options = MolWriteOptions {
+            version: CtabVersion::V2000,
+        };
+
+        match write_mol(&mol, Vec::new(), options) {
+            Err(FileWriteError::Unsupported { .. }) => {}
+            _ => panic!("Expected FileWriteError::Unsupported"),
+        }
+        Ok(())
+    }
+
     #[test]
     fn read_mol_alanine_header() -> Result<(), Box<dyn std::error::Error>> {
         let file = File::open("./test_files/alanine_v2000.mol")?;
@@ -114,4 +568,40 @@ mod tests {
         );
         Ok(())
     }
+    #[test]
+    fn read_mol_header_data() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_mol(File::open("./test_files/alanine_v2000.mol")?)?;
+
+        assert_eq!(
+            mol.get_property::<u32>(&MoleculeProperty::Dimensions)?,
+            Some(2)
+        );
+        assert_eq!(
+            mol.get_property::<u32>(&MoleculeProperty::ScalingFactorInt)?,
+            Some(1)
+        );
+        assert_f64_eq(
+            mol.get_property::<f64>(&MoleculeProperty::ScalingFactorFloat)?
+                .unwrap(),
+            0.00366,
+        );
+        assert_eq!(
+            mol.get_property::<f64>(&MoleculeProperty::Energy)?,
+            Some(0.0)
+        );
+        assert_eq!(
+            mol.get_property::<u32>(&MoleculeProperty::RegistryNumber)?,
+            Some(0)
+        );
+
+        // The dimensional code is kept, whatever the coordinates
+        let mut planar = mol.clone();
+        planar.set_property(MoleculeProperty::Dimensions, 3u32);
+        let output = write_to_string(&planar, MolWriteOptions::default())?;
+        assert_eq!(
+            output.lines().nth(1),
+            Some("GSMACCS-II10169115363D 1   0.00366     0.00000     0")
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,438 @@
+use super::format_mol::{read_mol_record, write_mol, MolWriteOptions};
+use crate::io::{FileReadError, FileWriteError, LineReader};
+use crate::mol::{HasProperties, Molecule, MoleculeProperty};
+
+// Reference: https://depth-first.com/articles/2020/07/13/the-sdfile-format/
+
+/// Reads the records of an SD file one at a time.
+///
+/// Each record is read up to its `$$$$` terminator before it is parsed, so an error in
+/// one record is reported (with its 1-based record number) without affecting the
+/// records that follow it.
+pub struct SdfReader<R> {
+    line_reader: LineReader<R>,
+    record: usize,
+}
+
+impl<R: std::io::Read> SdfReader<R> {
+    pub fn new(reader: R) -> SdfReader<R> {
+        SdfReader {
+            line_reader: LineReader::new(reader),
+            record: 0,
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for SdfReader<R> {
+    type Item = Result<Molecule, FileReadError>;
+
+    fn next(&mut self) -> Option<Result<Molecule, FileReadError>> {
+        let first_line = self.line_reader.line_number();
+        let mut lines: Vec<String> = Vec::new();
+        let mut terminated = false;
+
+        while let Some(line) = self.line_reader.read_line_optional() {
+            match line {
+                Ok(line) if line.starts_with("$$$$") => {
+                    terminated = true;
+                    break;
+                }
+                Ok(line) => lines.push(line),
+                Err(err) => {
+                    self.record += 1;
+                    return Some(Err(record_error(err.into(), self.record, first_line)));
+                }
+            }
+        }
+
+        if !terminated && lines.iter().all(|line| line.trim().is_empty()) {
+            return None;
+        }
+
+        self.record += 1;
+        Some(read_record(&lines).map_err(|err| record_error(err, self.record, first_line)))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SdfFields {
+    /// Every `MoleculeProperty::Data` item, ordered by name
+    All,
+    /// The named data items, in the order given (missing items are skipped)
+    Selected(Vec<String>),
+    None,
+}
+
+#[derive(Clone, Debug)]
+pub struct SdfWriteOptions {
+    pub mol_options: MolWriteOptions,
+    pub fields: SdfFields,
+}
+
+impl Default for SdfWriteOptions {
+    fn default() -> Self {
+        SdfWriteOptions {
+            mol_options: MolWriteOptions::default(),
+            fields: SdfFields::All,
+        }
+    }
+}
+
+/// Writes molecules as the records of an SD file.
+pub struct SdfWriter<W> {
+    writer: W,
+    options: SdfWriteOptions,
+}
+
+impl<W: std::io::Write> SdfWriter<W> {
+    pub fn new(writer: W, options: SdfWriteOptions) -> SdfWriter<W> {
+        SdfWriter { writer, options }
+    }
+
+    pub fn write(&mut self, molecule: &Molecule) -> Result<(), FileWriteError> {
+        write_mol(molecule, &mut self.writer, self.options.mol_options.clone())?;
+
+        let names = match &self.options.fields {
+            SdfFields::All => {
+                let mut names = molecule
+                    .properties
+                    .keys()
+                    .filter_map(|property| match property {
+                        MoleculeProperty::Data(name) => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<String>>();
+                names.sort();
+                names
+            }
+            SdfFields::Selected(names) => names.clone(),
+            SdfFields::None => Vec::new(),
+        };
+
+        for name in names {
+            let value = molecule.get_property_as_string(&MoleculeProperty::Data(name.clone()));
+            if let Some(value) = value {
+                writeln!(self.writer, "> <{}>", name)?;
+                for line in value.lines().filter(|line| !line.is_empty()) {
+                    writeln!(self.writer, "{}", line)?;
+                }
+                writeln!(self.writer)?;
+            }
+        }
+
+        writeln!(self.writer, "$$$$")?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn read_record(lines: &[String]) -> Result<Molecule, FileReadError> {
+    let text = lines.join("\n");
+    let mut line_reader = LineReader::new(text.as_bytes());
+
+    let mut molecule = read_mol_record(&mut line_reader)?;
+
+    while let Some(line) = line_reader.read_line_optional() {
+        let line = line?;
+        if !line.starts_with('>') {
+            continue;
+        }
+
+        let mut value_lines: Vec<String> = Vec::new();
+        while let Some(value_line) = line_reader.read_line_optional() {
+            let value_line = value_line?;
+            if value_line.is_empty() {
+                break;
+            }
+            value_lines.push(value_line);
+        }
+
+        if let Some(name) = parse_data_header(&line) {
+            molecule.set_property(MoleculeProperty::Data(name), value_lines.join("\n"));
+        }
+    }
+
+    Ok(molecule)
+}
+
+fn parse_data_header(line: &str) -> Option<String> {
+    /*
+    Data Header: '> [DTn] <FIELD_NAME> [(external registry number)]'
+    */
+
+    let start = line.find('<')?;
+    let length = line[start + 1..].find('>')?;
+    Some(line[start + 1..start + 1 + length].to_string())
+}
+
+fn record_error(err: FileReadError, record: usize, first_line: usize) -> FileReadError {
+    // Line numbers within the record are made relative to the start of the file
+    let err = match err {
+        FileReadError::LineParse { source, line } => FileReadError::LineParse {
+            source,
+            line: first_line + line,
+        },
+        err => err,
+    };
+
+    FileReadError::RecordParse {
+        source: Box::new(err),
+        record,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{CtabVersion, ParseError};
+    use crate::mol::Atom;
+
+    const SDF: &str = "Water
+  rustchem          2D
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+> <ID>
+MOL-1
+
+> 1  <DESCRIPTION>  (XYZ-123)
+First line
+Second line
+
+$$$$
+Broken
+  rustchem          2D
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    x.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+$$$$
+Methane
+  rustchem          2D
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+> <ID>
+MOL-3
+
+$$$$
+";
+
+    #[test]
+    fn read_records() -> Result<(), Box<dyn std::error::Error>> {
+        let records = SdfReader::new(SDF.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 3);
+
+        let water = records[0].as_ref().unwrap();
+        assert_eq!(water.atoms().len(), 1);
+        assert_eq!(
+            water.get_property_string(&MoleculeProperty::Name)?,
+            Some("Water")
+        );
+        assert_eq!(
+            water.get_property_string(&MoleculeProperty::Data("ID".to_string()))?,
+            Some("MOL-1")
+        );
+        assert_eq!(
+            water.get_property_string(&MoleculeProperty::Data("DESCRIPTION".to_string()))?,
+            Some("First line\nSecond line")
+        );
+
+        let methane = records[2].as_ref().unwrap();
+        assert_eq!(methane.atoms()[0].element.symbol, "C");
+        assert_eq!(
+            methane.get_property_string(&MoleculeProperty::Data("ID".to_string()))?,
+            Some("MOL-3")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_records_error_location() -> Result<(), Box<dyn std::error::Error>> {
+        let mut reader = SdfReader::new(SDF.as_bytes());
+        reader.next();
+
+        match reader.next() {
+            Some(Err(FileReadError::RecordParse { source, record })) => {
+                assert_eq!(record, 2);
+                match *source {
+                    FileReadError::LineParse {
+                        source: ParseError::ParseFloat { .. },
+                        line,
+                    } => assert_eq!(line, 19),
+                    _ => panic!("Expected FileReadError::LineParse"),
+                }
+            }
+            _ => panic!("Expected FileReadError::RecordParse"),
+        }
+
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_records_truncated() -> Result<(), Box<dyn std::error::Error>> {
+        let sdf = "Truncated
+  rustchem          2D
+
+  2  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+$$$$
+Methane
+  rustchem          2D
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+$$$$
+";
+        let records = SdfReader::new(sdf.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].is_err());
+        assert_eq!(records[1].as_ref().unwrap().atoms().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_records_without_terminator() -> Result<(), Box<dyn std::error::Error>> {
+        let sdf = "Methane
+  rustchem          2D
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+";
+        let records = SdfReader::new(sdf.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 1);
+        assert!(records[0].is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_records() -> Result<(), Box<dyn std::error::Error>> {
+        let mut molecule = Molecule::new();
+        molecule.add_atom(Atom::from_symbol("O")?);
+        molecule.set_property(MoleculeProperty::Name, "Water".to_string());
+        molecule.set_property(
+            MoleculeProperty::Data("ID".to_string()),
+            "MOL-1".to_string(),
+        );
+        molecule.set_property(MoleculeProperty::Data("WEIGHT".to_string()), 18.015);
+        molecule.set_property(MoleculeProperty::Data("COUNT".to_string()), 3);
+
+        let mut writer = SdfWriter::new(Vec::new(), SdfWriteOptions::default());
+        writer.write(&molecule)?;
+        writer.write(&molecule)?;
+        let output = String::from_utf8(writer.into_inner())?;
+        let lines = output.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines[0], "Water");
+        assert_eq!(lines[5], "M  END");
+        assert_eq!(
+            &lines[6..16],
+            &[
+                "> <COUNT>",
+                "3",
+                "",
+                "> <ID>",
+                "MOL-1",
+                "",
+                "> <WEIGHT>",
+                "18.015",
+                "",
+                "$$$$"
+            ]
+        );
+        assert_eq!(lines[16], "Water");
+        assert_eq!(lines.len(), 32);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_records_selected_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let mut molecule = Molecule::new();
+        molecule.add_atom(Atom::from_symbol("O")?);
+        molecule.set_property(
+            MoleculeProperty::Data("ID".to_string()),
+            "MOL-1".to_string(),
+        );
+        molecule.set_property(MoleculeProperty::Data("WEIGHT".to_string()), 18.015);
+
+        let options = SdfWriteOptions {
+            fields: SdfFields::Selected(vec!["WEIGHT".to_string(), "MISSING".to_string()]),
+            ..SdfWriteOptions::default()
+        };
+        let mut writer = SdfWriter::new(Vec::new(), options);
+        writer.write(&molecule)?;
+        let output = String::from_utf8(writer.into_inner())?;
+        let lines = output.lines().collect::<Vec<&str>>();
+
+        assert_eq!(&lines[6..], &["> <WEIGHT>", "18.015", "", "$$$$"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_records_v3000() -> Result<(), Box<dyn std::error::Error>> {
+        let mut molecule = Molecule::new();
+        molecule.add_atom(Atom::from_symbol("O")?);
+
+        let options = SdfWriteOptions {
+            mol_options: MolWriteOptions {
+                version: CtabVersion::V3000,
+            },
+            fields: SdfFields::None,
+        };
+        let mut writer = SdfWriter::new(Vec::new(), options);
+        writer.write(&molecule)?;
+        let output = String::from_utf8(writer.into_inner())?;
+
+        assert!(output.lines().nth(3).unwrap().ends_with(" V3000"));
+        assert!(output.ends_with("M  END\n$$$$\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_records_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = SdfWriter::new(Vec::new(), SdfWriteOptions::default());
+        for molecule in SdfReader::new(SDF.as_bytes()).filter_map(Result::ok) {
+            writer.write(&molecule)?;
+        }
+        let output = writer.into_inner();
+        let records =
+            SdfReader::new(&output[..]).collect::<Result<Vec<Molecule>, FileReadError>>()?;
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].get_property_string(&MoleculeProperty::Data("DESCRIPTION".to_string()))?,
+            Some("First line\nSecond line")
+        );
+        assert_eq!(
+            records[1].get_property_string(&MoleculeProperty::Data("ID".to_string()))?,
+            Some("MOL-3")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_records_empty() {
+        assert_eq!(SdfReader::new("".as_bytes()).count(), 0);
+        assert_eq!(SdfReader::new("\n\n".as_bytes()).count(), 0);
+    }
+}
@@ -1,6 +1,8 @@
 mod ct_v2000;
 mod ct_v3000;
 mod format_mol;
+mod format_sdf;
 mod utils;
 
-pub use format_mol::read_mol;
+pub use format_mol::{read_mol, write_mol, CtabVersion, MolWriteOptions};
+pub use format_sdf::{SdfFields, SdfReader, SdfWriteOptions, SdfWriter};
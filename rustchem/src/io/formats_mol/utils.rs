@@ -1,5 +1,6 @@
 use crate::io::utils::{parse_f64, parse_i32, parse_u32, parse_usize};
-use crate::io::ParseError;
+use crate::io::{FileWriteError, ParseError};
+use crate::mol::{Atom, AtomIndex, BondType, Molecule, Radical, TetrahedralParity};
 
 pub fn parse_u32_default(val: &str, dest_nature: &str) -> Result<u32, ParseError> {
     if val.trim().is_empty() {
@@ -32,3 +33,98 @@ pub fn parse_usize_default(val: &str, dest_nature: &str) -> Result<usize, ParseE
         parse_usize(val, dest_nature)
     }
 }
+
+pub fn parse_bond_type(val: &str) -> Result<BondType, ParseError> {
+    // 1=single, 2=double, 3=triple; (*) 4=aromatic, 5=single/double, 6=single/aromatic, 7=double/aromatic, 8=any
+    match parse_u32_default(val, "bond type")? {
+        1 => Ok(BondType::single()),
+        2 => Ok(BondType::double()),
+        3 => Ok(BondType::triple()),
+        4 => Ok(BondType::Aromatic),
+        5 => Ok(BondType::single_or_double()),
+        6 => Ok(BondType::single_or_aromatic()),
+        7 => Ok(BondType::double_or_aromatic()),
+        8 => Ok(BondType::Any),
+        _ => Err(ParseError::InvalidValue {
+            name: "bond type".to_string(),
+            value: val.to_string(),
+        }),
+    }
+}
+
+pub fn bond_type_code(bond_type: &BondType) -> Result<u32, FileWriteError> {
+    match bond_type {
+        BondType::Covalent(order @ 1..=3) => Ok(*order as u32),
+        BondType::Aromatic => Ok(4),
+        BondType::Any => Ok(8),
+        _ if *bond_type == BondType::single_or_double() => Ok(5),
+        _ if *bond_type == BondType::single_or_aromatic() => Ok(6),
+        _ if *bond_type == BondType::double_or_aromatic() => Ok(7),
+        _ => Err(FileWriteError::Unsupported {
+            message: format!("Bond type {:?} cannot be written to a Molfile", bond_type),
+        }),
+    }
+}
+
+pub fn mass_difference(atom: &Atom) -> Option<i32> {
+    // Mass differences are relative to the periodic table mass and limited to -3..3
+    let mass_difference = atom.isotope? as i32 - atom.element.average_mass.round() as i32;
+    if (-3..=3).contains(&mass_difference) {
+        Some(mass_difference)
+    } else {
+        None
+    }
+}
+
+pub fn parse_radical(val: &str) -> Result<Radical, ParseError> {
+    // 0=none, 1=singlet, 2=doublet, 3=triplet
+    match parse_i32_default(val, "radical")? {
+        0 => Ok(Radical::None),
+        1 => Ok(Radical::Singlet),
+        2 => Ok(Radical::Doublet),
+        3 => Ok(Radical::Triplet),
+        _ => Err(ParseError::InvalidValue {
+            name: "radical".to_string(),
+            value: val.to_string(),
+        }),
+    }
+}
+
+pub fn radical_code(radical: &Radical) -> i32 {
+    match radical {
+        Radical::None => 0,
+        Radical::Singlet => 1,
+        Radical::Doublet => 2,
+        Radical::Triplet => 3,
+    }
+}
+
+pub fn stereo_parity_code(molecule: &Molecule, atom: AtomIndex) -> u32 {
+    // 0=not stereo, 1=odd, 2=even, relative to the atom indices with hydrogens last
+    let stereo = match molecule.stereo().tetrahedral_at(atom) {
+        Some(stereo) => stereo,
+        None => return 0,
+    };
+    let mut neighbors = stereo.neighbors;
+    neighbors.sort_unstable_by_key(|neighbor| {
+        let is_hydrogen = molecule.atoms()[*neighbor].element.atomic_number == 1;
+        (*neighbor == atom || is_hydrogen, *neighbor)
+    });
+    match stereo.parity_for(&neighbors) {
+        Some(TetrahedralParity::Clockwise) => 1,
+        Some(TetrahedralParity::Anticlockwise) => 2,
+        None => 0,
+    }
+}
+
+pub fn written_valence(molecule: &Molecule, atom: AtomIndex) -> Option<u32> {
+    // Valences are only written where they change the implicit hydrogens from the default
+    let implicit_hydrogen_count = molecule.implicit_hydrogen_count(atom);
+    if molecule.atoms()[atom].valence.is_some()
+        || implicit_hydrogen_count != molecule.default_implicit_hydrogen_count(atom)
+    {
+        Some(molecule.bond_order_sum(atom) + implicit_hydrogen_count)
+    } else {
+        None
+    }
+}
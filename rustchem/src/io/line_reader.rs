@@ -2,18 +2,31 @@ use std::io::{BufRead, BufReader, Lines, Read};
 
 pub struct LineReader<R> {
     lines: Lines<BufReader<R>>,
+    line_number: usize,
 }
 
 impl<R: Read> LineReader<R> {
     pub fn new(reader: R) -> LineReader<R> {
         let buf = BufReader::new(reader);
-        LineReader { lines: buf.lines() }
+        LineReader {
+            lines: buf.lines(),
+            line_number: 0,
+        }
     }
 }
 
 impl<R: Read> LineReader<R> {
     pub fn read_line_optional(&mut self) -> Option<std::io::Result<String>> {
-        self.lines.next()
+        let line = self.lines.next();
+        if line.is_some() {
+            self.line_number += 1;
+        }
+        line
+    }
+
+    /// The (1-based) number of the line most recently read.
+    pub fn line_number(&self) -> usize {
+        self.line_number
     }
 
     pub fn read_line(&mut self) -> std::io::Result<String> {
@@ -1,11 +1,17 @@
 pub mod errors;
 pub mod format_pdb;
+pub mod format_smiles;
 pub mod formats_mol;
 mod line_reader;
 mod utils;
 
 pub use errors::FileReadError;
+pub use errors::FileWriteError;
 pub use errors::ParseError;
 pub use format_pdb::read_pdb;
-pub use formats_mol::read_mol;
+pub use format_smiles::{read_smiles, write_smiles, SmilesOptions};
+pub use formats_mol::{
+    read_mol, write_mol, CtabVersion, MolWriteOptions, SdfFields, SdfReader, SdfWriteOptions,
+    SdfWriter,
+};
 use line_reader::LineReader;
@@ -0,0 +1,81 @@
+use super::{AtomIndex, Bond, BondIndex};
+
+/// Neighbour lists for the atoms of a molecule, giving each neighbour with the bond to it.
+#[derive(Clone, Debug)]
+pub(super) struct Adjacency {
+    neighbors: Vec<Vec<(AtomIndex, BondIndex)>>,
+}
+
+impl Adjacency {
+    pub fn new(num_atoms: usize, bonds: &[Bond]) -> Adjacency {
+        let mut adjacency = Adjacency {
+            neighbors: vec![Vec::new(); num_atoms],
+        };
+        for (index, bond) in bonds.iter().enumerate() {
+            adjacency.add_bond(index, bond);
+        }
+        adjacency
+    }
+
+    pub fn add_atom(&mut self) {
+        self.neighbors.push(Vec::new());
+    }
+
+    pub fn add_bond(&mut self, index: BondIndex, bond: &Bond) {
+        // Bonds that refer to missing atoms, or bond an atom to itself, have no neighbours to record
+        let num_atoms = self.neighbors.len();
+        if bond.from_atom_id < num_atoms
+            && bond.to_atom_id < num_atoms
+            && bond.from_atom_id != bond.to_atom_id
+        {
+            self.neighbors[bond.from_atom_id].push((bond.to_atom_id, index));
+            self.neighbors[bond.to_atom_id].push((bond.from_atom_id, index));
+        }
+    }
+
+    pub fn neighbors(&self, atom: AtomIndex) -> &[(AtomIndex, BondIndex)] {
+        &self.neighbors[atom]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol::BondType;
+
+    #[test]
+    fn new() {
+        let bonds = vec![
+            Bond::new(0, 1, BondType::single()),
+            Bond::new(2, 1, BondType::double()),
+        ];
+        let adjacency = Adjacency::new(3, &bonds);
+
+        assert_eq!(adjacency.neighbors(0), &[(1, 0)]);
+        assert_eq!(adjacency.neighbors(1), &[(0, 0), (2, 1)]);
+        assert_eq!(adjacency.neighbors(2), &[(1, 1)]);
+    }
+
+    #[test]
+    fn add_atom_and_bond() {
+        let mut adjacency = Adjacency::new(2, &[]);
+        adjacency.add_atom();
+        adjacency.add_bond(0, &Bond::new(2, 0, BondType::single()));
+
+        assert_eq!(adjacency.neighbors(0), &[(2, 0)]);
+        assert_eq!(adjacency.neighbors(1), &[]);
+        assert_eq!(adjacency.neighbors(2), &[(0, 0)]);
+    }
+
+    #[test]
+    fn skip_invalid_bonds() {
+        let bonds = vec![
+            Bond::new(0, 5, BondType::single()),
+            Bond::new(1, 1, BondType::single()),
+        ];
+        let adjacency = Adjacency::new(2, &bonds);
+
+        assert!(adjacency.neighbors(0).is_empty());
+        assert!(adjacency.neighbors(1).is_empty());
+    }
+}
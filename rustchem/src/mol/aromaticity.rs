@@ -0,0 +1,512 @@
+use super::{kekulize, AtomIndex, BondIndex, BondType, Molecule, MoleculeError, Ring};
+use std::collections::HashSet;
+
+/// Rules used to decide which rings are aromatic.
+///
+/// Both models apply Hückel's rule, counting the pi electrons each ring atom contributes: one for
+/// an atom with a double bond in a ring, two for an atom with a lone pair it can donate (as in
+/// pyrrole, furan or the cyclopentadienyl anion), and none for an atom with an empty p orbital
+/// (as in the tropylium cation).
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum AromaticityModel {
+    /// Rings are considered one at a time, and atoms with a double bond out of the ring cannot be
+    /// aromatic.
+    Simple,
+    /// Follows the Daylight toolkit: rings are also considered together with the rings fused to
+    /// them (so azulene is aromatic), and an atom with a double bond out of the ring to N, O or S
+    /// contributes no electrons (so 2-pyridone is aromatic).
+    #[default]
+    Daylight,
+}
+
+// Fused ring systems with more rings than this only have their rings checked singly and in pairs
+const MAX_FUSED_RINGS: usize = 10;
+
+/// Sets the aromatic flags on the atoms and bonds of the molecule's aromatic rings, and clears
+/// them everywhere else. Bond types are not changed.
+///
+/// Rings are found from Kekulé bonds, but bonds that are already aromatic count as part of an
+/// aromatic system.
+pub fn perceive_aromaticity(molecule: &mut Molecule, model: AromaticityModel) {
+    let (aromatic_atoms, aromatic_bonds) = find_aromatic_rings(molecule, model);
+
+    for (index, atom) in molecule.atoms_mut().iter_mut().enumerate() {
+        atom.aromatic = aromatic_atoms.contains(&index);
+    }
+    for (index, bond) in molecule.bonds_mut().iter_mut().enumerate() {
+        bond.aromatic = aromatic_bonds.contains(&index);
+    }
+}
+
+/// Perceives aromaticity, then turns the bonds of aromatic rings into `BondType::Aromatic`.
+///
+/// Aromatic atoms whose hydrogens were implied by their Kekulé bonds (such as the nitrogen of
+/// pyrrole) are given an explicit hydrogen count, as they would be in aromatic SMILES.
+pub fn aromatize(molecule: &mut Molecule, model: AromaticityModel) {
+    perceive_aromaticity(molecule, model);
+
+    let donors = (0..molecule.atoms().len())
+        .filter(|atom| {
+            molecule.atoms()[*atom].aromatic
+                && molecule.atoms()[*atom].hydrogen_count.is_none()
+                && molecule.degree(*atom) == 2
+                && molecule
+                    .bonds_of(*atom)
+                    .all(|bond| molecule.bonds()[bond].bond_type == BondType::single())
+                && lone_pair_electrons(molecule, *atom, false) == Some(2)
+                && lone_pair_electrons(molecule, *atom, true).is_none()
+        })
+        .collect::<Vec<AtomIndex>>();
+    for atom in donors {
+        molecule.atoms_mut()[atom].hydrogen_count = Some(1);
+    }
+
+    for bond in molecule.bonds_mut() {
+        if bond.aromatic {
+            bond.bond_type = BondType::Aromatic;
+        }
+    }
+}
+
+/// Turns aromatic bonds into alternating single and double bonds with `kekulize`, then clears
+/// the aromatic flags of all atoms and bonds. The molecule is left unchanged if no Kekulé
+/// structure exists.
+pub fn dearomatize(molecule: &mut Molecule) -> Result<(), MoleculeError> {
+    kekulize(molecule)?;
+
+    for bond in molecule.bonds_mut() {
+        bond.aromatic = false;
+    }
+    for atom in molecule.atoms_mut() {
+        atom.aromatic = false;
+    }
+
+    Ok(())
+}
+
+fn find_aromatic_rings(
+    molecule: &Molecule,
+    model: AromaticityModel,
+) -> (HashSet<AtomIndex>, HashSet<BondIndex>) {
+    let electrons = (0..molecule.atoms().len())
+        .map(|atom| pi_electrons(molecule, atom, model))
+        .collect::<Vec<Option<u32>>>();
+
+    let rings = molecule
+        .rings()
+        .relevant_cycles()
+        .iter()
+        .filter(|ring| ring.atoms.iter().all(|atom| electrons[*atom].is_some()))
+        .collect::<Vec<&Ring>>();
+
+    let is_aromatic = |ring_set: &[&Ring]| {
+        let atoms = ring_set
+            .iter()
+            .flat_map(|ring| ring.atoms.iter().copied())
+            .collect::<HashSet<AtomIndex>>();
+        let total: u32 = atoms.iter().map(|atom| electrons[*atom].unwrap()).sum();
+        total % 4 == 2
+    };
+
+    let mut aromatic_rings = Vec::new();
+    match model {
+        AromaticityModel::Simple => {
+            aromatic_rings.extend(rings.iter().filter(|ring| is_aromatic(&[ring])));
+        }
+        AromaticityModel::Daylight => {
+            for system in fused_systems(&rings) {
+                for ring_set in connected_ring_sets(&system) {
+                    if is_aromatic(&ring_set) {
+                        aromatic_rings.extend(ring_set);
+                    }
+                }
+            }
+        }
+    }
+
+    let atoms = aromatic_rings
+        .iter()
+        .flat_map(|ring| ring.atoms.iter().copied())
+        .collect();
+    let bonds = aromatic_rings
+        .iter()
+        .flat_map(|ring| ring.bonds.iter().copied())
+        .collect();
+    (atoms, bonds)
+}
+
+/// Returns the number of pi electrons the atom can contribute to an aromatic ring, or `None` if
+/// it cannot be part of one.
+fn pi_electrons(molecule: &Molecule, atom: AtomIndex, model: AromaticityModel) -> Option<u32> {
+    if molecule.degree(atom) > 3 {
+        return None;
+    }
+
+    let rings = molecule.rings();
+    let mut ring_double_bonds = 0;
+    let mut exocyclic_double_bonds = Vec::new();
+    let mut has_aromatic_bond = false;
+    for (neighbor, bond) in molecule.neighbor_bonds(atom) {
+        match molecule.bonds()[bond].bond_type {
+            BondType::Covalent(1) => {}
+            BondType::Covalent(2) if rings.is_bond_in_ring(bond) => ring_double_bonds += 1,
+            BondType::Covalent(2) => exocyclic_double_bonds.push(neighbor),
+            BondType::Aromatic => has_aromatic_bond = true,
+            _ => return None,
+        }
+    }
+
+    match (ring_double_bonds, exocyclic_double_bonds.as_slice()) {
+        (0, []) => {}
+        (1, []) => return Some(1),
+        (0, [neighbor]) => {
+            let symbol = molecule.atoms()[*neighbor].element.symbol.as_str();
+            return match model {
+                AromaticityModel::Daylight if matches!(symbol, "N" | "O" | "S") => Some(0),
+                _ => None,
+            };
+        }
+        _ => return None,
+    }
+
+    match lone_pair_electrons(molecule, atom, has_aromatic_bond) {
+        Some(electrons) => Some(electrons),
+        // An atom that is already aromatic shares a double bond with its ring
+        None if has_aromatic_bond => Some(1),
+        None => None,
+    }
+}
+
+/// Returns the pi electrons of an atom without double bonds that has a lone pair (two electrons)
+/// or an empty p orbital (no electrons) in place of a double bond.
+fn lone_pair_electrons(
+    molecule: &Molecule,
+    atom: AtomIndex,
+    has_aromatic_bond: bool,
+) -> Option<u32> {
+    let degree = molecule.degree(atom);
+    let atom = &molecule.atoms()[atom];
+    // Without an explicit count, a Kekulé nitrogen with two neighbours and no double bond carries
+    // a hydrogen, while an aromatic one (as in pyridine) does not
+    let has_hydrogen = match atom.hydrogen_count {
+        Some(hydrogen_count) => hydrogen_count > 0,
+        None => !has_aromatic_bond,
+    };
+
+    match (atom.element.symbol.as_str(), atom.formal_charge) {
+        ("C", -1) => Some(2),
+        ("C", 1) | ("B", 0) => Some(0),
+        ("N", 0) | ("P", 0) | ("As", 0) if degree == 3 || has_hydrogen => Some(2),
+        ("N", -1) | ("P", -1) | ("As", -1) => Some(2),
+        ("O", 0) | ("S", 0) | ("Se", 0) | ("Te", 0) if degree == 2 => Some(2),
+        _ => None,
+    }
+}
+
+/// Groups rings into systems of rings that are fused through shared bonds.
+fn fused_systems<'a>(rings: &[&'a Ring]) -> Vec<Vec<&'a Ring>> {
+    let mut system_of = vec![None; rings.len()];
+    let mut systems = Vec::new();
+
+    for start in 0..rings.len() {
+        if system_of[start].is_some() {
+            continue;
+        }
+        system_of[start] = Some(systems.len());
+        let mut system = vec![start];
+        let mut next = 0;
+        while next < system.len() {
+            let ring = rings[system[next]];
+            for other in 0..rings.len() {
+                if system_of[other].is_none() && are_fused(ring, rings[other]) {
+                    system_of[other] = Some(systems.len());
+                    system.push(other);
+                }
+            }
+            next += 1;
+        }
+        systems.push(system.iter().map(|index| rings[*index]).collect());
+    }
+
+    systems
+}
+
+fn are_fused(ring: &Ring, other: &Ring) -> bool {
+    ring.bonds.iter().any(|bond| other.contains_bond(*bond))
+}
+
+/// Returns each set of rings in a fused system that is itself fused together.
+fn connected_ring_sets<'a>(system: &[&'a Ring]) -> Vec<Vec<&'a Ring>> {
+    if system.len() > MAX_FUSED_RINGS {
+        let mut ring_sets = system.iter().map(|ring| vec![*ring]).collect::<Vec<_>>();
+        for (index, ring) in system.iter().enumerate() {
+            for other in &system[index + 1..] {
+                if are_fused(ring, other) {
+                    ring_sets.push(vec![*ring, *other]);
+                }
+            }
+        }
+        return ring_sets;
+    }
+
+    (1..1usize << system.len())
+        .map(|mask| {
+            (0..system.len())
+                .filter(|index| mask & (1 << index) != 0)
+                .map(|index| system[index])
+                .collect::<Vec<&Ring>>()
+        })
+        .filter(|ring_set| {
+            let mut connected = vec![ring_set[0]];
+            let mut next = 0;
+            while next < connected.len() {
+                for ring in ring_set {
+                    if !connected.contains(ring) && are_fused(connected[next], ring) {
+                        connected.push(ring);
+                    }
+                }
+                next += 1;
+            }
+            connected.len() == ring_set.len()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{read_smiles, ParseError};
+
+    fn aromatic_atoms(smiles: &str, model: AromaticityModel) -> Result<Vec<bool>, ParseError> {
+        let mut mol = read_smiles(smiles)?;
+        perceive_aromaticity(&mut mol, model);
+        Ok(mol.atoms().iter().map(|atom| atom.aromatic).collect())
+    }
+
+    fn is_aromatic(smiles: &str, model: AromaticityModel) -> Result<bool, ParseError> {
+        Ok(aromatic_atoms(smiles, model)?
+            .iter()
+            .any(|aromatic| *aromatic))
+    }
+
+    #[test]
+    fn perceive_single_rings() -> Result<(), ParseError> {
+        let aromatic = [
+            "C1=CC=CC=C1",
+            "C1=CC=NC=C1",
+            "C1=CNC=C1",
+            "C1=COC=C1",
+            "C1=CSC=C1",
+            "C1=C[Se]C=C1",
+            "C1=CC=[O+]C=C1",
+            "[CH-]1C=CC=C1",
+            "[CH+]1C=CC=CC=C1",
+            "C1=CC=CC=CC=CC=CC=CC=C1",
+        ];
+        let not_aromatic = [
+            "C1=CCC=C1",
+            "C1=CC=CC=CC=C1",
+            "C1CCCCC1",
+            "C1=CC=CC1",
+            "C1=CCC=CC1",
+        ];
+
+        for model in [AromaticityModel::Simple, AromaticityModel::Daylight] {
+            for smiles in aromatic.iter() {
+                assert!(is_aromatic(smiles, model)?, "{} should be aromatic", smiles);
+            }
+            for smiles in not_aromatic.iter() {
+                assert!(
+                    !is_aromatic(smiles, model)?,
+                    "{} should not be aromatic",
+                    smiles
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn perceive_flags() -> Result<(), ParseError> {
+        // Toluene, with its methyl group and bond left out
+        let mut mol = read_smiles("CC1=CC=CC=C1")?;
+        perceive_aromaticity(&mut mol, AromaticityModel::default());
+
+        let atoms = mol
+            .atoms()
+            .iter()
+            .map(|atom| atom.aromatic)
+            .collect::<Vec<bool>>();
+        let bonds = mol
+            .bonds()
+            .iter()
+            .map(|bond| bond.aromatic)
+            .collect::<Vec<bool>>();
+        assert_eq!(atoms, vec![false, true, true, true, true, true, true]);
+        assert_eq!(bonds, vec![false, true, true, true, true, true, true]);
+        assert_eq!(mol.bonds()[1].bond_type, BondType::double());
+
+        // Flags left over from earlier are cleared
+        let mut mol = read_smiles("c1ccccc1")?;
+        mol.bonds_mut()[0].bond_type = BondType::single();
+        mol.bonds_mut()[1].bond_type = BondType::single();
+        perceive_aromaticity(&mut mol, AromaticityModel::default());
+
+        assert!(mol.atoms().iter().all(|atom| !atom.aromatic));
+        assert!(mol.bonds().iter().all(|bond| !bond.aromatic));
+
+        Ok(())
+    }
+
+    #[test]
+    fn perceive_fused_rings() -> Result<(), ParseError> {
+        // Naphthalene and indole are aromatic in both models
+        for model in [AromaticityModel::Simple, AromaticityModel::Daylight] {
+            assert!(aromatic_atoms("C1=CC=C2C=CC=CC2=C1", model)?
+                .iter()
+                .all(|aromatic| *aromatic));
+            assert!(aromatic_atoms("C1=CC=C2C(=C1)C=CN2", model)?
+                .iter()
+                .all(|aromatic| *aromatic));
+        }
+
+        // Azulene is only aromatic as a whole
+        let azulene = "C1=CC2=CC=CC=CC2=C1";
+        assert!(!is_aromatic(azulene, AromaticityModel::Simple)?);
+        assert!(aromatic_atoms(azulene, AromaticityModel::Daylight)?
+            .iter()
+            .all(|aromatic| *aromatic));
+
+        // Only the benzene ring of indane is aromatic
+        assert_eq!(
+            aromatic_atoms("C1CC2=CC=CC=C2C1", AromaticityModel::Daylight)?,
+            vec![false, false, true, true, true, true, true, true, false]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn perceive_exocyclic_double_bonds() -> Result<(), ParseError> {
+        // 2-Pyridone and 4-pyrone
+        for smiles in ["O=C1C=CC=CN1", "O=C1C=COC=C1"].iter() {
+            assert!(!is_aromatic(smiles, AromaticityModel::Simple)?);
+            assert_eq!(
+                aromatic_atoms(smiles, AromaticityModel::Daylight)?,
+                vec![false, true, true, true, true, true, true]
+            );
+        }
+
+        // Methylenecyclohexadiene
+        assert!(!is_aromatic("C=C1C=CC=CC1", AromaticityModel::Daylight)?);
+        // Benzoquinone has four electrons in its ring
+        assert!(!is_aromatic(
+            "O=C1C=CC(=O)C=C1",
+            AromaticityModel::Daylight
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn perceive_aromatic_input() -> Result<(), ParseError> {
+        assert!(
+            aromatic_atoms("c1ccc2[nH]ccc2c1", AromaticityModel::Simple)?
+                .iter()
+                .all(|aromatic| *aromatic)
+        );
+        assert_eq!(
+            aromatic_atoms("c1cc[n+](C)cc1", AromaticityModel::Simple)?,
+            vec![true, true, true, true, false, true, true]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn aromatize_and_dearomatize() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("CC1=CC=C2C(=C1)C=CN2")?;
+
+        aromatize(&mut mol, AromaticityModel::default());
+
+        assert_eq!(mol.bonds()[0].bond_type, BondType::single());
+        assert!(mol.bonds()[1..]
+            .iter()
+            .all(|bond| bond.bond_type == BondType::Aromatic));
+
+        dearomatize(&mut mol)?;
+
+        assert!(mol.atoms().iter().all(|atom| !atom.aromatic));
+        assert!(mol.bonds().iter().all(|bond| !bond.aromatic));
+        let double_bonds = mol
+            .bonds()
+            .iter()
+            .filter(|bond| bond.bond_type == BondType::double())
+            .count();
+        assert_eq!(double_bonds, 4);
+        // The nitrogen keeps its lone pair and hydrogen
+        assert_eq!(mol.atoms()[9].hydrogen_count, Some(1));
+        assert!(mol
+            .bonds_of(9)
+            .all(|bond| mol.bonds()[bond].bond_type == BondType::single()));
+
+        aromatize(&mut mol, AromaticityModel::default());
+
+        assert!(mol.atoms()[1..].iter().all(|atom| atom.aromatic));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dearomatize_smiles() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("c1ccc2ccccc2c1.c1ccoc1.O=c1cccc[nH]1")?;
+
+        dearomatize(&mut mol)?;
+
+        assert!(mol
+            .bonds()
+            .iter()
+            .all(|bond| bond.bond_type != BondType::Aromatic));
+        for atom in [0, 1, 2, 3, 4, 10, 11, 12, 14, 17, 18, 19, 20] {
+            assert_eq!(
+                mol.bonds_of(atom)
+                    .filter(|bond| mol.bonds()[*bond].bond_type == BondType::double())
+                    .count(),
+                1
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dearomatize_error() -> Result<(), ParseError> {
+        let mut mol = read_smiles("c1cccc1")?;
+        let original = mol.clone();
+
+        match dearomatize(&mut mol) {
+            Err(MoleculeError::NoKekuleStructure { atoms }) => {
+                assert_eq!(atoms.len(), 1);
+                assert!(atoms[0] < 5);
+            }
+            _ => panic!("Expected MoleculeError::NoKekuleStructure"),
+        }
+
+        // The molecule is left unchanged
+        assert_eq!(mol.atoms().len(), original.atoms().len());
+        assert!(mol
+            .atoms()
+            .iter()
+            .zip(original.atoms())
+            .all(|(atom, original)| atom.aromatic
+                && atom.element == original.element
+                && atom.hydrogen_count == original.hydrogen_count));
+        assert!(mol
+            .bonds()
+            .iter()
+            .all(|bond| bond.bond_type == BondType::Aromatic && bond.aromatic));
+
+        Ok(())
+    }
+}
@@ -1,17 +1,95 @@
-use super::{Element, MoleculeError, Point3d, PropertyMap};
+use super::{Element, HasProperties, Isotope, MoleculeError, Point3d, PropertyMap};
+use std::fmt;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Atom {
     pub element: &'static Element,
     pub position: Point3d,
     pub formal_charge: i32,
     pub isotope: Option<u32>,
+    pub radical: Radical,
+    pub aromatic: bool,
+    /// Number of implicit hydrogens when given explicitly (e.g. by a SMILES bracket atom),
+    /// or `None` when it should be derived from the atom's valence.
+    pub hydrogen_count: Option<u32>,
+    /// Valence when given explicitly (e.g. by a Molfile valence field), from which the number of
+    /// implicit hydrogens is derived if `hydrogen_count` is not given.
+    pub valence: Option<u32>,
     pub properties: PropertyMap<AtomProperty>,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub enum AtomProperty {
     PartialCharge,
+    /// (*) `AtomList` of elements allowed (or excluded) at this position
+    AtomList,
+    /// (*) Substitution count (-1=no substitution, -2=as drawn, 1..6=count, 6 meaning 6 or more)
+    SubstitutionCount,
+    /// (*) Whether the atom must have at least one multiple bond
+    Unsaturated,
+    /// (*) Hydrogen count (-1=no hydrogens unless drawn, 1..n=n or more hydrogens)
+    HydrogenCount,
+    /// (*) Ring bond count (-1=no ring bonds, -2=as drawn, 2..4=count, 4 meaning 4 or more)
+    RingBondCount,
+    /// Rgroup attachment point (1=first point, 2=second point, 3=both)
+    AttachmentPoint,
+    /// Free-text atom label from 'M  ZZC' lines
+    Label,
+    /// SMILES chirality tag (e.g. '@', '@@', '@TB5'), relative to the order neighbours were written in
+    Chirality,
+    /// Atom class from SMILES bracket atoms (e.g. 5 for '[CH3:5]')
+    AtomClass,
+    /// Molfile atom stereo parity (1=odd, 2=even, 3=either), relative to the order of the atom indices
+    StereoParity,
+    /// `CipLabel` (R, S, r or s) set by `assign_cip_labels`
+    CipLabel,
+}
+
+/// Radical state of an atom, described by its spin multiplicity as in Molfiles.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Radical {
+    #[default]
+    None,
+    /// Two paired non-bonding electrons (e.g. a singlet carbene)
+    Singlet,
+    /// One unpaired electron
+    Doublet,
+    /// Two unpaired electrons (e.g. a triplet carbene)
+    Triplet,
+}
+
+impl Radical {
+    /// Number of radical electrons, each of which takes the place of one bond in the atom's valence.
+    pub fn electrons(&self) -> u32 {
+        match self {
+            Radical::None => 0,
+            Radical::Doublet => 1,
+            Radical::Singlet | Radical::Triplet => 2,
+        }
+    }
+}
+
+/// A query for any one of a list of elements (or, if `exclude` is set, any element not in the list).
+#[derive(PartialEq, Clone, Debug)]
+pub struct AtomList {
+    pub elements: Vec<&'static Element>,
+    pub exclude: bool,
+}
+
+impl fmt::Display for AtomList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbols = self
+            .elements
+            .iter()
+            .map(|element| element.symbol.as_str())
+            .collect::<Vec<&str>>();
+
+        if self.exclude {
+            write!(f, "NOT [{}]", symbols.join(","))
+        } else {
+            write!(f, "[{}]", symbols.join(","))
+        }
+    }
 }
 
 pub type AtomIndex = usize;
@@ -23,6 +101,10 @@ impl Atom {
             position: Point3d::new(0.0, 0.0, 0.0),
             formal_charge: 0,
             isotope: None,
+            radical: Radical::None,
+            aromatic: false,
+            hydrogen_count: None,
+            valence: None,
             properties: PropertyMap::new(),
         }
     }
@@ -34,6 +116,23 @@ impl Atom {
     pub fn from_symbol(symbol: &str) -> Result<Atom, MoleculeError> {
         Ok(Atom::new(Element::from_symbol(symbol)?))
     }
+
+    pub fn get_isotope(&self) -> Result<Option<&'static Isotope>, MoleculeError> {
+        match self.isotope {
+            Some(mass_number) => Ok(Some(Isotope::lookup(self.element, mass_number)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl HasProperties<AtomProperty> for Atom {
+    fn get_property_map(&self) -> &PropertyMap<AtomProperty> {
+        &self.properties
+    }
+
+    fn get_property_map_mut(&mut self) -> &mut PropertyMap<AtomProperty> {
+        &mut self.properties
+    }
 }
 
 #[cfg(test)]
@@ -42,13 +141,17 @@ mod tests {
 
     #[test]
     fn new_from_element() -> Result<(), MoleculeError> {
-        let element = Element::from_atomic_number(12)?;
+        let element = Element::from_atomic_number(6)?;
         let atom = Atom::new(element);
 
-        assert_eq!(atom.element.atomic_number, 12);
+        assert_eq!(atom.element.atomic_number, 6);
         assert_eq!(atom.position, Point3d::new(0.0, 0.0, 0.0));
         assert_eq!(atom.formal_charge, 0);
         assert_eq!(atom.isotope, None);
+        assert_eq!(atom.radical, Radical::None);
+        assert!(!atom.aromatic);
+        assert_eq!(atom.hydrogen_count, None);
+        assert_eq!(atom.valence, None);
         assert_eq!(atom.properties.len(), 0);
 
         Ok(())
@@ -56,12 +159,13 @@ mod tests {
 
     #[test]
     fn new_from_atomic_number() -> Result<(), MoleculeError> {
-        let atom = Atom::from_atomic_number(12)?;
+        let atom = Atom::from_atomic_number(6)?;
 
-        assert_eq!(atom.element.atomic_number, 12);
+        assert_eq!(atom.element.atomic_number, 6);
         assert_eq!(atom.position, Point3d::new(0.0, 0.0, 0.0));
         assert_eq!(atom.formal_charge, 0);
         assert_eq!(atom.isotope, None);
+        assert_eq!(atom.radical, Radical::None);
         assert_eq!(atom.properties.len(), 0);
 
         Ok(())
@@ -83,15 +187,47 @@ mod tests {
     fn new_from_symbol() -> Result<(), MoleculeError> {
         let atom = Atom::from_symbol("C")?;
 
-        assert_eq!(atom.element.atomic_number, 12);
+        assert_eq!(atom.element.atomic_number, 6);
         assert_eq!(atom.position, Point3d::new(0.0, 0.0, 0.0));
         assert_eq!(atom.formal_charge, 0);
         assert_eq!(atom.isotope, None);
+        assert_eq!(atom.radical, Radical::None);
         assert_eq!(atom.properties.len(), 0);
 
         Ok(())
     }
 
+    #[test]
+    fn get_isotope() -> Result<(), MoleculeError> {
+        let mut atom = Atom::from_symbol("C")?;
+        assert_eq!(atom.get_isotope()?, None);
+
+        atom.isotope = Some(13);
+        assert_eq!(
+            atom.get_isotope()?.map(|isotope| isotope.mass_number),
+            Some(13)
+        );
+
+        atom.isotope = Some(20);
+        match atom.get_isotope() {
+            Err(MoleculeError::UnknownIsotope { mass_number, .. }) => {
+                assert_eq!(mass_number, 20);
+            }
+            _ => panic!("Expected MoleculeError::UnknownIsotope"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn radical_electrons() {
+        assert_eq!(Radical::None.electrons(), 0);
+        assert_eq!(Radical::Singlet.electrons(), 2);
+        assert_eq!(Radical::Doublet.electrons(), 1);
+        assert_eq!(Radical::Triplet.electrons(), 2);
+        assert_eq!(Radical::default(), Radical::None);
+    }
+
     #[test]
     fn new_from_symbol_error_unknown() -> Result<(), MoleculeError> {
         match Atom::from_symbol("X") {
@@ -1,14 +1,18 @@
-use super::{AtomIndex, PropertyMap};
+use super::{AtomIndex, HasProperties, PropertyMap};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Bond {
     pub from_atom_id: AtomIndex,
     pub to_atom_id: AtomIndex,
     pub bond_type: BondType,
+    /// Whether the bond is part of an aromatic ring, which may be set while the bond type still
+    /// gives its order in a Kekulé structure
+    pub aromatic: bool,
+    pub stereo: BondStereo,
     pub properties: PropertyMap<BondProperty>,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum BondType {
     Covalent(i32),
     Aromatic,
@@ -37,8 +41,30 @@ impl BondType {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
-pub enum BondProperty {}
+/// Stereo marking of a bond in a 2D drawing, as in Molfiles. Wedges start at `from_atom_id`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum BondStereo {
+    #[default]
+    None,
+    /// Wedge, with `to_atom_id` towards the viewer
+    Up,
+    /// Hashed wedge, with `to_atom_id` away from the viewer
+    Down,
+    /// Wavy bond, leaving the configuration at `from_atom_id` unknown
+    Either,
+    /// Crossed double bond, leaving the cis/trans configuration unknown
+    CisTransEither,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum BondProperty {
+    /// SMILES bond direction ('/' or '\') going from `from_atom_id` to `to_atom_id`
+    Direction,
+    /// `CipLabel` (E or Z) set by `assign_cip_labels`
+    CipLabel,
+}
+
+pub type BondIndex = usize;
 
 impl Bond {
     pub fn new(from_atom_id: AtomIndex, to_atom_id: AtomIndex, bond_type: BondType) -> Bond {
@@ -46,11 +72,23 @@ impl Bond {
             from_atom_id,
             to_atom_id,
             bond_type,
+            aromatic: false,
+            stereo: BondStereo::None,
             properties: PropertyMap::new(),
         }
     }
 }
 
+impl HasProperties<BondProperty> for Bond {
+    fn get_property_map(&self) -> &PropertyMap<BondProperty> {
+        &self.properties
+    }
+
+    fn get_property_map_mut(&mut self) -> &mut PropertyMap<BondProperty> {
+        &mut self.properties
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::MoleculeError;
@@ -63,6 +101,7 @@ mod tests {
         assert_eq!(bond.from_atom_id, 1);
         assert_eq!(bond.to_atom_id, 2);
         assert_eq!(bond.bond_type, BondType::single());
+        assert_eq!(bond.stereo, BondStereo::None);
 
         Ok(())
     }
@@ -0,0 +1,797 @@
+use super::{
+    aromatize, AromaticityModel, AtomIndex, AtomProperty, BondType, DoubleBondConfiguration,
+    DoubleBondStereo, Molecule, StereoGroup, StereoGroupKind, TetrahedralParity, TetrahedralStereo,
+};
+
+// 64-bit FNV-1a parameters. Unlike `DefaultHasher`, FNV-1a gives the same hash on every platform
+// and Rust version, so identity hashes can be stored.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Returns classes of atoms that cannot be told apart by their elements and connections, given
+/// as a number for each atom. Classes are numbered from 0 and ordered by atom invariants, so the
+/// numbering does not depend on the order of the atoms. Stereochemistry is not taken into account.
+pub fn symmetry_classes(molecule: &Molecule) -> Vec<usize> {
+    let invariants = molecule
+        .atoms()
+        .iter()
+        .enumerate()
+        .map(|(index, atom)| {
+            (
+                atom.element.atomic_number,
+                atom.properties.contains_key(&AtomProperty::AtomList),
+                atom.isotope.unwrap_or(0),
+                atom.formal_charge,
+                atom.radical.electrons(),
+                atom.aromatic,
+                molecule.degree(index),
+                molecule.total_hydrogen_count(index),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    refine_classes(molecule, dense_ranks(&invariants))
+}
+
+/// Returns a canonical rank for each atom, from 0 to one less than the number of atoms.
+///
+/// Atoms are first ranked by their symmetry classes, which are refined by the configurations of
+/// stereocentres and double bonds relative to their neighbours' ranks, and by the stereo groups
+/// the atoms are in. Remaining ties are broken by favouring one of the tied atoms and refining
+/// again. While a configuration cannot be told because neighbours are tied, each of the tied
+/// atoms is tried in turn, and the ranks that come first by the configurations told after each
+/// choice and then by an encoding of the structure are kept. Atoms that symmetries found so far
+/// map onto an atom already tried are skipped. Molecules that differ only in the order of their
+/// atoms get the same ranks for corresponding atoms, up to symmetry.
+pub fn canonical_ranks(molecule: &Molecule) -> Vec<usize> {
+    break_ties(
+        molecule,
+        refine_stereo_classes(molecule, symmetry_classes(molecule)),
+    )
+}
+
+impl Molecule {
+    /// Returns a hash of the structure that does not depend on the order of the atoms, for
+    /// finding duplicate molecules.
+    ///
+    /// The hash covers the atoms' elements, charges, isotopes, radicals and hydrogen counts, the
+    /// bonds, and the stereochemistry including stereo groups. Properties and coordinates are not
+    /// included. Aromaticity is perceived with the Daylight model first, so aromatic and Kekulé
+    /// forms of a molecule hash the same.
+    ///
+    /// The configurations of an Or or And group mean the same when all inverted, so each
+    /// combination of inverted groups is tried and the smallest encoding is hashed. The time taken
+    /// doubles with each Or or And group.
+    pub fn identity_hash(&self) -> u64 {
+        let mut molecule = self.clone();
+        aromatize(&mut molecule, AromaticityModel::Daylight);
+
+        let relative_groups = molecule
+            .stereo()
+            .groups
+            .iter()
+            .filter(|group| group.kind != StereoGroupKind::Absolute)
+            .cloned()
+            .collect::<Vec<StereoGroup>>();
+        let lowest_encoding = (0..1u64 << relative_groups.len())
+            .map(|inverted_groups| {
+                let mut variant = molecule.clone();
+                for (index, group) in relative_groups.iter().enumerate() {
+                    if inverted_groups >> index & 1 == 1 {
+                        invert_group(&mut variant, group);
+                    }
+                }
+                encoding(&variant, &canonical_ranks(&variant))
+            })
+            .min()
+            .unwrap();
+
+        lowest_encoding
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+}
+
+fn invert_group(molecule: &mut Molecule, group: &StereoGroup) {
+    for stereo in &mut molecule.stereo_mut().tetrahedral {
+        if group.atoms.contains(&stereo.center) {
+            stereo.parity = stereo.parity.inverted();
+        }
+    }
+}
+
+// Describes the structure with atoms in order of their ranks, so that the same structure ranked
+// in the same way always gives the same values
+fn encoding(molecule: &Molecule, ranks: &[usize]) -> Vec<i64> {
+    let stereo = stereo_invariants(molecule, ranks);
+
+    let mut atom_order = (0..molecule.atoms().len()).collect::<Vec<AtomIndex>>();
+    atom_order.sort_by_key(|atom| ranks[*atom]);
+
+    let mut values = vec![molecule.atoms().len() as i64, molecule.bonds().len() as i64];
+    for atom in atom_order {
+        let explicit = &molecule.atoms()[atom];
+        values.extend(&[
+            explicit.element.atomic_number as i64,
+            explicit.properties.contains_key(&AtomProperty::AtomList) as i64,
+            explicit.isotope.unwrap_or(0) as i64,
+            explicit.formal_charge as i64,
+            explicit.radical.electrons() as i64,
+            explicit.aromatic as i64,
+            molecule.total_hydrogen_count(atom) as i64,
+            stereo[atom] as i64,
+        ]);
+    }
+
+    let mut bonds = molecule
+        .bonds()
+        .iter()
+        .map(|bond| {
+            let from = ranks[bond.from_atom_id] as i64;
+            let to = ranks[bond.to_atom_id] as i64;
+            (from.min(to), from.max(to), bond_code(&bond.bond_type))
+        })
+        .collect::<Vec<(i64, i64, i64)>>();
+    bonds.sort_unstable();
+    for (from, to, code) in bonds {
+        values.extend(&[from, to, code]);
+    }
+
+    let mut groups = molecule
+        .stereo()
+        .groups
+        .iter()
+        .map(|group| group_key(group, ranks))
+        .collect::<Vec<(i64, Vec<usize>)>>();
+    groups.sort_unstable();
+    values.push(groups.len() as i64);
+    for (kind, members) in groups {
+        values.extend(&[kind, members.len() as i64]);
+        values.extend(members.iter().map(|rank| *rank as i64));
+    }
+
+    values
+}
+
+// The kind of a stereo group with the sorted ranks of its atoms
+fn group_key(group: &StereoGroup, ranks: &[usize]) -> (i64, Vec<usize>) {
+    let kind = match group.kind {
+        StereoGroupKind::Absolute => 1,
+        StereoGroupKind::Or => 2,
+        StereoGroupKind::And => 3,
+    };
+    let mut members = group
+        .atoms
+        .iter()
+        .map(|atom| ranks[*atom])
+        .collect::<Vec<usize>>();
+    members.sort_unstable();
+    (kind, members)
+}
+
+// Gives each atom its own rank. Ties between atoms related by symmetry are broken by favouring
+// the first of them, but where a configuration cannot yet be told from the ranks, the result
+// depends on which of its tied neighbours is favoured, so each is tried and the ranks that come
+// first are kept.
+fn break_ties(molecule: &Molecule, ranks: Vec<usize>) -> Vec<usize> {
+    let mut search = TieSearch {
+        molecule,
+        first: None,
+        best: None,
+        automorphisms: Vec::new(),
+    };
+    search.visit(ranks, &mut Vec::new(), &mut Vec::new(), &mut Vec::new());
+    search.best.unwrap().ranks
+}
+
+// Fully ranked atoms, with the tied atoms chosen on the way to them and the invariants of the
+// ranks after each choice
+#[derive(Clone)]
+struct Leaf {
+    invariants: Vec<Vec<(usize, u8)>>,
+    encoding: Vec<i64>,
+    ranks: Vec<usize>,
+    path: Vec<AtomIndex>,
+}
+
+// Tries the tied atoms depth first. Leaves are ordered by the invariants after each choice, then
+// by their encodings, so only the choices with the lowest invariant are followed, and not if the
+// invariant comes after that of the best leaf so far. Two leaves with the same encoding give a symmetry of the molecule, which maps the
+// subtree of one choice onto the other, so once a leaf repeats an earlier one the rest of its
+// subtree is skipped, and atoms that a symmetry maps onto an atom already tried are not tried
+// again.
+struct TieSearch<'a> {
+    molecule: &'a Molecule,
+    first: Option<Leaf>,
+    best: Option<Leaf>,
+    automorphisms: Vec<Vec<AtomIndex>>,
+}
+
+impl TieSearch<'_> {
+    // Returns the number of choices to go back to, when a leaf repeated an earlier one. `path`
+    // holds the atoms chosen from tied atoms, `fixed` all the atoms favoured so far and
+    // `invariants` the invariant after each choice.
+    fn visit(
+        &mut self,
+        mut ranks: Vec<usize>,
+        path: &mut Vec<AtomIndex>,
+        fixed: &mut Vec<AtomIndex>,
+        invariants: &mut Vec<Vec<(usize, u8)>>,
+    ) -> Option<usize> {
+        let num_fixed = fixed.len();
+        loop {
+            let mut class_sizes = vec![0; ranks.len()];
+            for rank in &ranks {
+                class_sizes[*rank] += 1;
+            }
+
+            let tied = |tied_rank: usize| {
+                (0..ranks.len())
+                    .filter(|atom| ranks[*atom] == tied_rank)
+                    .collect::<Vec<AtomIndex>>()
+            };
+
+            let stereo_tied_rank = undetermined_stereo_neighbors(self.molecule, &ranks)
+                .iter()
+                .map(|atom| ranks[*atom])
+                .filter(|rank| class_sizes[*rank] > 1)
+                .min();
+            if let Some(tied_rank) = stereo_tied_rank {
+                // Only the choices with the lowest invariant can lead to the first leaf
+                let choices = tied(tied_rank)
+                    .into_iter()
+                    .map(|candidate| {
+                        let favoured = favour(self.molecule, &ranks, candidate);
+                        let invariant = node_invariant(self.molecule, &favoured);
+                        (candidate, favoured, invariant)
+                    })
+                    .collect::<Vec<_>>();
+                let lowest = choices
+                    .iter()
+                    .map(|(_, _, invariant)| invariant)
+                    .min()
+                    .cloned()
+                    .unwrap();
+
+                let depth = path.len();
+                let mut tried = Vec::new();
+                for (candidate, favoured, invariant) in choices {
+                    if invariant != lowest || self.same_orbit(candidate, &tried, fixed) {
+                        continue;
+                    }
+                    tried.push(candidate);
+
+                    invariants.push(invariant);
+                    let worse = self.best.as_ref().is_some_and(|best| {
+                        invariants[..] > best.invariants[..best.invariants.len().min(depth + 1)]
+                    });
+                    if worse {
+                        invariants.pop();
+                        continue;
+                    }
+
+                    path.push(candidate);
+                    fixed.push(candidate);
+                    let back_to = self.visit(favoured, path, fixed, invariants);
+                    path.pop();
+                    fixed.pop();
+                    invariants.pop();
+                    if let Some(back_to) = back_to.filter(|back_to| *back_to < depth) {
+                        fixed.truncate(num_fixed);
+                        return Some(back_to);
+                    }
+                }
+                fixed.truncate(num_fixed);
+                return None;
+            }
+
+            match (0..ranks.len()).find(|rank| class_sizes[*rank] > 1) {
+                Some(tied_rank) => {
+                    let chosen = tied(tied_rank)[0];
+                    fixed.push(chosen);
+                    ranks = favour(self.molecule, &ranks, chosen);
+                }
+                None => {
+                    fixed.truncate(num_fixed);
+                    return self.leaf(ranks, path, invariants);
+                }
+            }
+        }
+    }
+
+    fn leaf(
+        &mut self,
+        ranks: Vec<usize>,
+        path: &[AtomIndex],
+        invariants: &[Vec<(usize, u8)>],
+    ) -> Option<usize> {
+        let encoding = encoding(self.molecule, &ranks);
+
+        for earlier in self.first.iter().chain(&self.best) {
+            if earlier.encoding == encoding {
+                // Maps each atom to the atom with the same rank in the earlier leaf
+                let mut by_rank = vec![0; ranks.len()];
+                for (atom, rank) in earlier.ranks.iter().enumerate() {
+                    by_rank[*rank] = atom;
+                }
+                let automorphism = ranks.iter().map(|rank| by_rank[*rank]).collect();
+                self.automorphisms.push(automorphism);
+
+                let common = path
+                    .iter()
+                    .zip(&earlier.path)
+                    .take_while(|(atom, other)| atom == other)
+                    .count();
+                return Some(common);
+            }
+        }
+
+        let leaf = Leaf {
+            invariants: invariants.to_vec(),
+            encoding,
+            ranks,
+            path: path.to_vec(),
+        };
+        if self.first.is_none() {
+            self.first = Some(leaf.clone());
+        }
+        if self.best.as_ref().is_none_or(|best| {
+            (&leaf.invariants, &leaf.encoding) < (&best.invariants, &best.encoding)
+        }) {
+            self.best = Some(leaf);
+        }
+        None
+    }
+
+    // Whether the automorphisms that keep the favoured atoms in place map the atom onto any of the
+    // atoms already tried
+    fn same_orbit(&self, atom: AtomIndex, tried: &[AtomIndex], fixed: &[AtomIndex]) -> bool {
+        let automorphisms = self
+            .automorphisms
+            .iter()
+            .filter(|automorphism| fixed.iter().all(|atom| automorphism[*atom] == *atom))
+            .collect::<Vec<&Vec<AtomIndex>>>();
+
+        let mut orbit = vec![atom];
+        let mut next = 0;
+        while next < orbit.len() {
+            for automorphism in &automorphisms {
+                let image = automorphism[orbit[next]];
+                if !orbit.contains(&image) {
+                    orbit.push(image);
+                }
+            }
+            next += 1;
+        }
+        tried.iter().any(|tried_atom| orbit.contains(tried_atom))
+    }
+}
+
+// The classes of the atoms with their configurations, which do not depend on the order of the
+// atoms, so that choices can be compared before all ties are broken
+fn node_invariant(molecule: &Molecule, classes: &[usize]) -> Vec<(usize, u8)> {
+    let mut invariant = classes
+        .iter()
+        .copied()
+        .zip(stereo_invariants(molecule, classes))
+        .collect::<Vec<(usize, u8)>>();
+    invariant.sort_unstable();
+    invariant
+}
+
+// Ranks the atom before the others in its class, and refines the classes again
+fn favour(molecule: &Molecule, ranks: &[usize], chosen: AtomIndex) -> Vec<usize> {
+    let keys = ranks
+        .iter()
+        .enumerate()
+        .map(|(index, rank)| (*rank, index != chosen))
+        .collect::<Vec<(usize, bool)>>();
+    refine_stereo_classes(molecule, dense_ranks(&keys))
+}
+
+fn bond_code(bond_type: &BondType) -> i64 {
+    match bond_type {
+        BondType::Covalent(order) => *order as i64,
+        BondType::Aromatic => -1,
+        _ => -2,
+    }
+}
+
+// Splits classes by the classes of the atoms' neighbours and the bonds to them, until no more
+// classes split. Atoms keep their order relative to atoms of other classes.
+fn refine_classes(molecule: &Molecule, mut classes: Vec<usize>) -> Vec<usize> {
+    let mut num_classes = count_classes(&classes);
+    loop {
+        let keys = (0..classes.len())
+            .map(|atom| {
+                let mut neighbor_classes = molecule
+                    .neighbor_bonds(atom)
+                    .map(|(neighbor, bond)| {
+                        (
+                            classes[neighbor],
+                            bond_code(&molecule.bonds()[bond].bond_type),
+                        )
+                    })
+                    .collect::<Vec<(usize, i64)>>();
+                neighbor_classes.sort_unstable();
+                (classes[atom], neighbor_classes)
+            })
+            .collect::<Vec<_>>();
+
+        let new_classes = dense_ranks(&keys);
+        let new_num_classes = count_classes(&new_classes);
+        if new_num_classes == num_classes {
+            return new_classes;
+        }
+        classes = new_classes;
+        num_classes = new_num_classes;
+    }
+}
+
+// Refines classes by their connections and stereo configurations in turn, as configurations
+// can only be told once neighbours are in different classes. Atoms are also split by the kind of
+// stereo group they are in and the classes of the other atoms in it.
+fn refine_stereo_classes(molecule: &Molecule, mut classes: Vec<usize>) -> Vec<usize> {
+    loop {
+        classes = refine_classes(molecule, classes);
+        let num_classes = count_classes(&classes);
+
+        let keys = classes
+            .iter()
+            .zip(stereo_invariants(molecule, &classes))
+            .enumerate()
+            .map(|(atom, (class, stereo))| {
+                let group = molecule
+                    .stereo()
+                    .group_of(atom)
+                    .map(|group| group_key(group, &classes));
+                (*class, stereo, group)
+            })
+            .collect::<Vec<_>>();
+        let new_classes = dense_ranks(&keys);
+        if count_classes(&new_classes) == num_classes {
+            return new_classes;
+        }
+        classes = new_classes;
+    }
+}
+
+// The configuration of each stereocentre and double bond atom relative to the classes of its
+// neighbours, or 0 where there is none or the neighbours are not all in different classes
+fn stereo_invariants(molecule: &Molecule, classes: &[usize]) -> Vec<u8> {
+    let mut invariants = vec![0; classes.len()];
+
+    for stereo in &molecule.stereo().tetrahedral {
+        if let Some(code) = tetrahedral_code(stereo, classes) {
+            invariants[stereo.center] = code;
+        }
+    }
+
+    for stereo in &molecule.stereo().double_bonds {
+        if let Some(code) = double_bond_code(molecule, stereo, classes) {
+            let bond = &molecule.bonds()[stereo.bond];
+            let (from, to) = (bond.from_atom_id, bond.to_atom_id);
+            invariants[from] = invariants[from].max(code);
+            invariants[to] = invariants[to].max(code);
+        }
+    }
+
+    invariants
+}
+
+// The neighbours of stereocentres and double bonds whose configurations cannot be told from the
+// classes
+fn undetermined_stereo_neighbors(molecule: &Molecule, classes: &[usize]) -> Vec<AtomIndex> {
+    let mut neighbors = Vec::new();
+
+    for stereo in &molecule.stereo().tetrahedral {
+        if tetrahedral_code(stereo, classes).is_none() {
+            neighbors.extend(
+                stereo
+                    .neighbors
+                    .iter()
+                    .filter(|neighbor| **neighbor != stereo.center),
+            );
+        }
+    }
+
+    for stereo in &molecule.stereo().double_bonds {
+        if double_bond_code(molecule, stereo, classes).is_none() {
+            let bond = &molecule.bonds()[stereo.bond];
+            let (from, to) = (bond.from_atom_id, bond.to_atom_id);
+            neighbors.extend(molecule.neighbors(from).filter(|neighbor| *neighbor != to));
+            neighbors.extend(molecule.neighbors(to).filter(|neighbor| *neighbor != from));
+        }
+    }
+
+    neighbors
+}
+
+// 1 or 2 for a stereocentre whose neighbours are all in different classes
+fn tetrahedral_code(stereo: &TetrahedralStereo, classes: &[usize]) -> Option<u8> {
+    // An implicit hydrogen or lone pair comes before all the neighbouring atoms
+    let key = |neighbor: AtomIndex| {
+        if neighbor == stereo.center {
+            0
+        } else {
+            classes[neighbor] + 1
+        }
+    };
+    let mut neighbors = stereo.neighbors;
+    neighbors.sort_by_key(|neighbor| key(*neighbor));
+    if neighbors
+        .windows(2)
+        .any(|pair| key(pair[0]) == key(pair[1]))
+    {
+        return None;
+    }
+    match stereo.parity_for(&neighbors)? {
+        TetrahedralParity::Clockwise => Some(1),
+        TetrahedralParity::Anticlockwise => Some(2),
+    }
+}
+
+// 3 (cis) or 4 (trans) for a double bond with a single neighbour in the highest class at each end
+fn double_bond_code(
+    molecule: &Molecule,
+    stereo: &DoubleBondStereo,
+    classes: &[usize],
+) -> Option<u8> {
+    let bond = &molecule.bonds()[stereo.bond];
+    let (from, to) = (bond.from_atom_id, bond.to_atom_id);
+
+    let highest_neighbor = |atom: AtomIndex, other_end: AtomIndex| {
+        let mut neighbors = molecule
+            .neighbors(atom)
+            .filter(|neighbor| *neighbor != other_end)
+            .collect::<Vec<AtomIndex>>();
+        neighbors.sort_by_key(|neighbor| classes[*neighbor]);
+        match neighbors.as_slice() {
+            [.., second, first] if classes[*second] == classes[*first] => None,
+            [.., first] => Some(*first),
+            [] => None,
+        }
+    };
+    let neighbors = [highest_neighbor(from, to)?, highest_neighbor(to, from)?];
+
+    match stereo.configuration_for(neighbors) {
+        DoubleBondConfiguration::Cis => Some(3),
+        DoubleBondConfiguration::Trans => Some(4),
+    }
+}
+
+fn count_classes(classes: &[usize]) -> usize {
+    classes.iter().max().map_or(0, |class| class + 1)
+}
+
+fn dense_ranks<T: Ord>(keys: &[T]) -> Vec<usize> {
+    let mut sorted = keys.iter().collect::<Vec<&T>>();
+    sorted.sort();
+    sorted.dedup();
+    keys.iter()
+        .map(|key| sorted.binary_search(&key).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read_smiles;
+
+    // Returns the molecule with atom `index` moved to `order[index]`
+    fn reordered(molecule: &Molecule, order: &[AtomIndex]) -> Molecule {
+        let mut atoms = molecule.atoms().to_vec();
+        for (index, atom) in molecule.atoms().iter().enumerate() {
+            atoms[order[index]] = atom.clone();
+        }
+        let bonds = molecule
+            .bonds()
+            .iter()
+            .map(|bond| {
+                let mut bond = bond.clone();
+                bond.from_atom_id = order[bond.from_atom_id];
+                bond.to_atom_id = order[bond.to_atom_id];
+                bond
+            })
+            .collect();
+
+        let mut reordered = Molecule::from_graph(atoms, bonds);
+        *reordered.stereo_mut() = molecule.stereo().clone();
+        let atom_map = order.iter().map(|atom| Some(*atom)).collect::<Vec<_>>();
+        let bond_map = (0..molecule.bonds().len()).map(Some).collect::<Vec<_>>();
+        reordered.stereo_mut().remap(&atom_map, &bond_map);
+        reordered
+    }
+
+    fn hash(smiles: &str) -> u64 {
+        read_smiles(smiles).unwrap().identity_hash()
+    }
+
+    #[test]
+    fn symmetry_classes_of_toluene() {
+        let mol = read_smiles("Cc1ccccc1").unwrap();
+        let classes = symmetry_classes(&mol);
+
+        assert_eq!(classes[2], classes[6]);
+        assert_eq!(classes[3], classes[5]);
+        assert_ne!(classes[2], classes[3]);
+        assert_ne!(classes[3], classes[4]);
+        assert_eq!(count_classes(&classes), 5);
+    }
+
+    #[test]
+    fn canonical_ranks_follow_atoms() {
+        let order = [3, 5, 0, 6, 1, 4, 2];
+        for smiles in &[
+            "CC(N)C(=O)O",
+            "C1CCC(O)CC1",
+            "F/C=C/C(Cl)Br",
+            "N[C@@H](C)C(=O)O",
+        ] {
+            let mol = read_smiles(smiles).unwrap();
+            let order = order
+                .iter()
+                .copied()
+                .filter(|atom| *atom < mol.atoms().len())
+                .collect::<Vec<AtomIndex>>();
+            let reordered_mol = reordered(&mol, &order);
+
+            let ranks = canonical_ranks(&mol);
+            let reordered_ranks = canonical_ranks(&reordered_mol);
+            let mut sorted_ranks = ranks.clone();
+            sorted_ranks.sort_unstable();
+            assert_eq!(sorted_ranks, (0..mol.atoms().len()).collect::<Vec<_>>());
+
+            // Atoms related by symmetry may swap ranks, but the ranked structure is the same
+            let symmetric = symmetry_classes(&mol);
+            for atom in 0..mol.atoms().len() {
+                let other = reordered_ranks[order[atom]];
+                let atom_class = symmetric[atom];
+                let other_atom = ranks.iter().position(|rank| *rank == other).unwrap();
+                assert_eq!(symmetric[other_atom], atom_class, "{}", smiles);
+            }
+            assert_eq!(
+                mol.identity_hash(),
+                reordered_mol.identity_hash(),
+                "{}",
+                smiles
+            );
+        }
+
+        // Without symmetry the ranks correspond exactly
+        let mol = read_smiles("OC(N)C(F)Cl").unwrap();
+        let order = [4, 2, 5, 0, 3, 1];
+        let reordered_ranks = canonical_ranks(&reordered(&mol, &order));
+        let ranks = canonical_ranks(&mol);
+        for atom in 0..mol.atoms().len() {
+            assert_eq!(reordered_ranks[order[atom]], ranks[atom]);
+        }
+    }
+
+    #[test]
+    fn canonical_ranks_use_stereo() {
+        // The end carbons of the meso form of pentane-2,3,4-triol are only told apart by their
+        // configurations, while those of the chiral form are related by symmetry
+        let is_split = |smiles| {
+            let mol = read_smiles(smiles).unwrap();
+            let classes = refine_stereo_classes(&mol, symmetry_classes(&mol));
+            assert_eq!(symmetry_classes(&mol)[1], symmetry_classes(&mol)[5]);
+            classes[1] != classes[5]
+        };
+
+        assert!(is_split("C[C@@H](O)C(O)[C@H](C)O"));
+        assert!(!is_split("C[C@@H](O)C(O)[C@@H](C)O"));
+    }
+
+    #[test]
+    fn identity_hashes_of_symmetric_stereo() {
+        // Configurations around rings are only told apart once tied ring atoms are ranked, and
+        // the hash must not depend on which of them comes first
+        let trans = hash("C[C@H]1CC[C@@H](C)CC1");
+        let cis = hash("C[C@H]1CC[C@H](C)CC1");
+        assert_eq!(hash("C[C@@H]1CC[C@H](C)CC1"), trans);
+        assert_eq!(hash("C[C@@H]1CC[C@@H](C)CC1"), cis);
+        assert_ne!(cis, trans);
+
+        for smiles in &[
+            "C[C@H]1CC[C@@H](C)CC1",
+            "C[C@H]1CC[C@H](C)CC1",
+            "C[C@@H](O)C(O)[C@H](C)O",
+            "O[C@H]1[C@H](O)[C@@H](O)[C@H](O)[C@@H](O)[C@H]1O",
+            "C/C=C/C=C/C",
+            "F[C@H]1C[C@@H](F)C1",
+        ] {
+            let mol = read_smiles(smiles).unwrap();
+            let num_atoms = mol.atoms().len();
+            let mut orders = vec![(0..num_atoms).rev().collect::<Vec<AtomIndex>>()];
+            for step in &[3, 5, 7] {
+                if !num_atoms.is_multiple_of(*step) {
+                    orders.push((0..num_atoms).map(|atom| atom * step % num_atoms).collect());
+                }
+            }
+            for shift in 1..num_atoms {
+                orders.push(
+                    (0..num_atoms)
+                        .map(|atom| (atom + shift) % num_atoms)
+                        .collect(),
+                );
+            }
+
+            for order in orders {
+                assert_eq!(
+                    reordered(&mol, &order).identity_hash(),
+                    mol.identity_hash(),
+                    "{} {:?}",
+                    smiles,
+                    order
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn identity_hashes_of_repeated_stereo_fragments() {
+        // Trying every way of breaking the ties between identical copies takes factorial time
+        let fragment = "C[C@H]1CC[C@@H](C)CC1";
+        let mol = read_smiles(&[fragment; 6].join(".")).unwrap();
+        let num_atoms = mol.atoms().len();
+        let order = (0..num_atoms).rev().collect::<Vec<AtomIndex>>();
+        assert_eq!(reordered(&mol, &order).identity_hash(), mol.identity_hash());
+
+        let mixed = [fragment, "C[C@H]1CC[C@H](C)CC1"].repeat(3).join(".");
+        assert_ne!(hash(&mixed), mol.identity_hash());
+    }
+
+    #[test]
+    fn identity_hashes_of_stereo_groups() {
+        // One group of two centres is not the same as a group for each
+        let together = hash("C[C@H](O)[C@H](C)Cl |&1:1,3|");
+        let apart = hash("C[C@H](O)[C@H](C)Cl |&1:1,&2:3|");
+        assert_ne!(together, apart);
+        assert_ne!(together, hash("C[C@H](O)[C@H](C)Cl |o1:1,3|"));
+
+        // The configurations of a group can all be inverted
+        assert_eq!(hash("C[C@@H](O)[C@@H](C)Cl |&1:1,3|"), together);
+        assert_ne!(hash("C[C@@H](O)[C@H](C)Cl |&1:1,3|"), together);
+        assert_eq!(hash("C[C@@H](O)[C@H](C)Cl |&1:1,&2:3|"), apart);
+        assert_eq!(hash("C[C@@H](O)[C@@H](C)Cl |&1:1,&2:3|"), apart);
+        assert_ne!(
+            hash("C[C@@H](O)[C@H](C)Cl |a:1,&1:3|"),
+            hash("C[C@H](O)[C@H](C)Cl |a:1,&1:3|")
+        );
+        assert_eq!(
+            hash("C[C@H](O)[C@@H](C)Cl |a:1,&1:3|"),
+            hash("C[C@H](O)[C@H](C)Cl |a:1,&1:3|")
+        );
+    }
+
+    #[test]
+    fn identity_hashes() {
+        assert_eq!(hash("CCO"), hash("OCC"));
+        assert_eq!(hash("c1ccccc1CN"), hash("NCc1ccccc1"));
+        assert_eq!(hash("N[C@@H](C)C(=O)O"), hash("C[C@H](N)C(=O)O"));
+        assert_eq!(hash("F/C=C/F"), hash("F\\C=C\\F"));
+        assert_eq!(hash("c1ccccc1"), hash("C1=CC=CC=C1"));
+        assert_eq!(hash("Cc1cc[nH]c1"), hash("CC1=CNC=C1"));
+
+        assert_ne!(hash("CCO"), hash("COC"));
+        assert_ne!(hash("CCO"), hash("[13CH3]CO"));
+        assert_ne!(hash("CC[O-]"), hash("CCO"));
+        assert_ne!(hash("N[C@@H](C)C(=O)O"), hash("N[C@H](C)C(=O)O"));
+        assert_ne!(hash("N[C@@H](C)C(=O)O"), hash("NC(C)C(=O)O"));
+        assert_ne!(hash("F/C=C/F"), hash("F/C=C\\F"));
+        assert_ne!(
+            hash("C[C@@H](O)C(O)[C@H](C)O"),
+            hash("C[C@@H](O)C(O)[C@@H](C)O")
+        );
+        assert_ne!(
+            hash("C[C@@H](O)C(O)[C@@H](C)O"),
+            hash("C[C@H](O)C(O)[C@H](C)O")
+        );
+        assert_ne!(hash("C[C@H](O)N"), hash("C[C@H](O)N |&1:1|"));
+        assert_ne!(hash("C.C"), hash("CC"));
+    }
+}
@@ -0,0 +1,749 @@
+use super::{
+    kekulize, AtomIndex, AtomProperty, BondIndex, BondProperty, BondType, DoubleBondConfiguration,
+    DoubleBondStereo, Element, HasProperties, Molecule, TetrahedralParity, TetrahedralStereo,
+};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::repeat_n;
+use std::rc::Rc;
+
+// Labels of stereo units are used when ranking the substituents of other units (rules 3 to 5),
+// so labelling is repeated until the labels settle, up to this many times
+const MAX_PASSES: usize = 4;
+
+/// A Cahn–Ingold–Prelog stereo descriptor.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CipLabel {
+    R,
+    S,
+    /// Pseudoasymmetric centre whose substituents only differ in their own configurations, 'r'
+    PseudoR,
+    /// Pseudoasymmetric centre, 's'
+    PseudoS,
+    E,
+    Z,
+}
+
+impl fmt::Display for CipLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            CipLabel::R => "R",
+            CipLabel::S => "S",
+            CipLabel::PseudoR => "r",
+            CipLabel::PseudoS => "s",
+            CipLabel::E => "E",
+            CipLabel::Z => "Z",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// The sequence rules, applied in order until substituents can be told apart
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum Rule {
+    /// 1a: higher atomic number first
+    AtomicNumber,
+    /// 1b: duplicate atoms of atoms nearer the root first
+    RootDistance,
+    /// 2: higher mass first, so that isotopes rank above the natural element
+    Mass,
+    /// 3: Z double bonds before E double bonds
+    DoubleBond,
+    /// 4a: stereocentres before pseudoasymmetric centres, before other atoms
+    Stereogenic,
+    /// 4b: like pairs of descriptors (RR, SS) before unlike pairs (RS, SR)
+    LikePairs,
+    /// 4c: r before s
+    Pseudoasymmetry,
+    /// 5: R (and r) before S (and s)
+    Chirality,
+}
+
+const RULES: [Rule; 8] = [
+    Rule::AtomicNumber,
+    Rule::RootDistance,
+    Rule::Mass,
+    Rule::DoubleBond,
+    Rule::Stereogenic,
+    Rule::LikePairs,
+    Rule::Pseudoasymmetry,
+    Rule::Chirality,
+];
+
+// A node of the hierarchical digraph, which unfolds the molecule into a tree from the root
+#[derive(Clone, Debug)]
+enum Node {
+    /// An atom of the molecule, reached from the root along `path`
+    Atom {
+        atom: AtomIndex,
+        path: Vec<AtomIndex>,
+    },
+    /// A copy of an atom that closes a ring or stands for the extra order of a multiple bond,
+    /// with the depth of the atom it copies
+    Duplicate {
+        atom: AtomIndex,
+        distance: usize,
+    },
+    ImplicitHydrogen {
+        depth: usize,
+    },
+    /// The lone pair of a centre with three neighbours, ranked below every atom
+    Phantom,
+}
+
+// The path and atom of an atom node, with a rule its children are sorted under
+type SortKey = (Vec<AtomIndex>, AtomIndex, Rule);
+
+struct Digraph<'a> {
+    molecule: &'a Molecule,
+    atom_labels: &'a [Option<CipLabel>],
+    bond_labels: &'a [Option<CipLabel>],
+    /// The children of the atom nodes sorted so far under each rule, keyed by their path and
+    /// atom, so that each branch is only ranked once however often it is compared
+    sorted_children: RefCell<HashMap<SortKey, Rc<Vec<Node>>>>,
+}
+
+impl Digraph<'_> {
+    fn children(&self, node: &Node) -> Vec<Node> {
+        let (atom, path) = match node {
+            Node::Atom { atom, path } => (*atom, path),
+            _ => return Vec::new(),
+        };
+        let depth = path.len();
+        let mut child_path = path.clone();
+        child_path.push(atom);
+
+        let mut children = Vec::new();
+        for (neighbor, bond) in self.molecule.neighbor_bonds(atom) {
+            let extra_order = bond_order(self.molecule, bond) - 1;
+            if path.last() == Some(&neighbor) {
+                children.extend(repeat_n(duplicate(neighbor, depth - 1), extra_order));
+            } else if let Some(distance) = path.iter().position(|other| *other == neighbor) {
+                children.extend(repeat_n(duplicate(neighbor, distance), extra_order + 1));
+            } else {
+                children.push(Node::Atom {
+                    atom: neighbor,
+                    path: child_path.clone(),
+                });
+                children.extend(repeat_n(duplicate(neighbor, depth + 1), extra_order));
+            }
+        }
+
+        let hydrogen_count = self.molecule.implicit_hydrogen_count(atom) as usize;
+        children.extend(repeat_n(
+            Node::ImplicitHydrogen { depth: depth + 1 },
+            hydrogen_count,
+        ));
+        children
+    }
+
+    fn key(&self, node: &Node, rule: Rule) -> i64 {
+        let atom = match node {
+            Node::Atom { atom, .. } | Node::Duplicate { atom, .. } => Some(*atom),
+            Node::ImplicitHydrogen { .. } => None,
+            Node::Phantom => return i64::MIN,
+        };
+
+        match rule {
+            Rule::AtomicNumber => match atom {
+                Some(atom) => self.molecule.atoms()[atom].element.atomic_number as i64,
+                None => 1,
+            },
+            Rule::RootDistance => match node {
+                Node::Atom { path, .. } => -(path.len() as i64),
+                Node::Duplicate { distance, .. } => -(*distance as i64),
+                Node::ImplicitHydrogen { depth } => -(*depth as i64),
+                Node::Phantom => i64::MIN,
+            },
+            Rule::Mass => {
+                let mass = match atom {
+                    Some(atom) => {
+                        let atom = &self.molecule.atoms()[atom];
+                        match atom.isotope {
+                            Some(mass_number) => mass_number as f64,
+                            None => atom.element.average_mass,
+                        }
+                    }
+                    None => {
+                        Element::from_atomic_number(1).map_or(1.0, |hydrogen| hydrogen.average_mass)
+                    }
+                };
+                (mass * 1e6).round() as i64
+            }
+            Rule::DoubleBond => {
+                let bond = match node {
+                    Node::Atom { atom, path } => path
+                        .last()
+                        .and_then(|parent| self.molecule.bond_between(*parent, *atom)),
+                    _ => None,
+                };
+                match bond.and_then(|bond| self.bond_labels[bond]) {
+                    Some(CipLabel::Z) => 2,
+                    Some(CipLabel::E) => 1,
+                    _ => 0,
+                }
+            }
+            Rule::Stereogenic => match self.node_label(node) {
+                Some(CipLabel::R) | Some(CipLabel::S) => 2,
+                Some(CipLabel::PseudoR) | Some(CipLabel::PseudoS) => 1,
+                _ => 0,
+            },
+            // Compared over whole branches by `compare_like_pairs`
+            Rule::LikePairs => 0,
+            Rule::Pseudoasymmetry => match self.node_label(node) {
+                Some(CipLabel::PseudoR) => 2,
+                Some(CipLabel::PseudoS) => 1,
+                _ => 0,
+            },
+            Rule::Chirality => match self.node_label(node) {
+                Some(CipLabel::R) | Some(CipLabel::PseudoR) => 2,
+                Some(CipLabel::S) | Some(CipLabel::PseudoS) => 1,
+                _ => 0,
+            },
+        }
+    }
+
+    // Labels only apply to the atoms themselves, not their duplicates
+    fn node_label(&self, node: &Node) -> Option<CipLabel> {
+        match node {
+            Node::Atom { atom, .. } => self.atom_labels[*atom],
+            _ => None,
+        }
+    }
+
+    fn ordered_children(&self, node: &Node, rule: Rule) -> Rc<Vec<Node>> {
+        let key = match node {
+            Node::Atom { atom, path } => (path.clone(), *atom, rule),
+            _ => return Rc::default(),
+        };
+        if let Some(children) = self.sorted_children.borrow().get(&key) {
+            return Rc::clone(children);
+        }
+
+        let mut children = self.children(node);
+        children.sort_by(|first, second| self.compare_branches(second, first, rule));
+        let children = Rc::new(children);
+        self.sorted_children
+            .borrow_mut()
+            .insert(key, Rc::clone(&children));
+        children
+    }
+
+    // Compares two branches under one rule, sphere by sphere, where each sphere holds the
+    // children of the nodes of the previous sphere in order of their rank
+    fn compare_branches(&self, first: &Node, second: &Node, rule: Rule) -> Ordering {
+        if rule == Rule::LikePairs {
+            return self.compare_like_pairs(first, second);
+        }
+
+        let mut first_sphere = vec![Rc::new(vec![first.clone()])];
+        let mut second_sphere = vec![Rc::new(vec![second.clone()])];
+        let has_nodes = |sphere: &[Rc<Vec<Node>>]| sphere.iter().any(|set| !set.is_empty());
+
+        while has_nodes(&first_sphere) || has_nodes(&second_sphere) {
+            let ordering = self.compare_spheres(&first_sphere, &second_sphere, rule);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            first_sphere = self.next_sphere(&first_sphere, rule);
+            second_sphere = self.next_sphere(&second_sphere, rule);
+        }
+        Ordering::Equal
+    }
+
+    fn next_sphere(&self, sphere: &[Rc<Vec<Node>>], rule: Rule) -> Vec<Rc<Vec<Node>>> {
+        sphere
+            .iter()
+            .flat_map(|set| set.iter())
+            .map(|node| self.ordered_children(node, rule))
+            .collect()
+    }
+
+    // Missing nodes are compared as phantom atoms, which rank below all others
+    fn compare_spheres(
+        &self,
+        first: &[Rc<Vec<Node>>],
+        second: &[Rc<Vec<Node>>],
+        rule: Rule,
+    ) -> Ordering {
+        for set in 0..first.len().max(second.len()) {
+            let first_set = first.get(set).map_or(&[][..], |set| &set[..]);
+            let second_set = second.get(set).map_or(&[][..], |set| &set[..]);
+            for index in 0..first_set.len().max(second_set.len()) {
+                let first_key = first_set
+                    .get(index)
+                    .map_or(i64::MIN, |node| self.key(node, rule));
+                let second_key = second_set
+                    .get(index)
+                    .map_or(i64::MIN, |node| self.key(node, rule));
+                match first_key.cmp(&second_key) {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                }
+            }
+        }
+        Ordering::Equal
+    }
+
+    // Rule 4b: each branch takes the descriptor of its highest ranked stereocentre as reference,
+    // and pairs it with the descriptors of all its stereocentres in order of rank (by rules 1 to
+    // 4a, with like pairs first among tied centres). The first like pair where the other branch
+    // has an unlike pair decides. Where the reference itself is tied, the better one is taken.
+    fn compare_like_pairs(&self, first: &Node, second: &Node) -> Ordering {
+        if self.atom_labels.iter().all(Option::is_none) {
+            return Ordering::Equal;
+        }
+
+        let first_pairs = self.like_pairs(first);
+        let second_pairs = self.like_pairs(second);
+        first_pairs.cmp(&second_pairs)
+    }
+
+    // Whether each descriptor in the branch is like the reference, in order
+    fn like_pairs(&self, branch: &Node) -> Vec<bool> {
+        let groups = self.descriptor_groups(branch);
+        let references = match groups.first() {
+            Some(group) => group.clone(),
+            None => return Vec::new(),
+        };
+
+        references
+            .iter()
+            .map(|reference| {
+                groups
+                    .iter()
+                    .flat_map(|group| {
+                        let mut pairs = group
+                            .iter()
+                            .map(|descriptor| descriptor == reference)
+                            .collect::<Vec<bool>>();
+                        pairs.sort_unstable_by(|a, b| b.cmp(a));
+                        pairs
+                    })
+                    .collect::<Vec<bool>>()
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    // The descriptors of the stereocentres in the branch as R-like (true) or S-like (false),
+    // sphere by sphere, in groups of centres that rules 1 to 4a cannot tell apart
+    fn descriptor_groups(&self, branch: &Node) -> Vec<Vec<bool>> {
+        let descriptor = |node: &Node| match self.node_label(node) {
+            Some(CipLabel::R) | Some(CipLabel::PseudoR) => Some(true),
+            Some(CipLabel::S) | Some(CipLabel::PseudoS) => Some(false),
+            _ => None,
+        };
+
+        let mut groups = Vec::new();
+        groups.extend(descriptor(branch).map(|descriptor| vec![descriptor]));
+        let mut sphere = vec![branch.clone()];
+        while !sphere.is_empty() {
+            let mut next_sphere = Vec::new();
+            for node in &sphere {
+                let mut children = self.children(node);
+                children.sort_by(|first, second| self.compare_before_like_pairs(second, first));
+                for tied in children.chunk_by(|first, second| {
+                    self.compare_before_like_pairs(first, second) == Ordering::Equal
+                }) {
+                    let group = tied.iter().filter_map(descriptor).collect::<Vec<bool>>();
+                    if !group.is_empty() {
+                        groups.push(group);
+                    }
+                }
+                next_sphere.extend(children);
+            }
+            sphere = next_sphere;
+        }
+        groups
+    }
+
+    fn compare_before_like_pairs(&self, first: &Node, second: &Node) -> Ordering {
+        RULES
+            .iter()
+            .take_while(|rule| **rule != Rule::LikePairs)
+            .map(|rule| self.compare_branches(first, second, *rule))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    fn compare(&self, first: &Node, second: &Node) -> (Ordering, Option<Rule>) {
+        for rule in RULES.iter() {
+            match self.compare_branches(first, second, *rule) {
+                Ordering::Equal => {}
+                ordering => return (ordering, Some(*rule)),
+            }
+        }
+        (Ordering::Equal, None)
+    }
+
+    // Orders the substituents from highest to lowest priority, returning `None` if any are tied.
+    // Also returns whether the order depends on the configurations of the substituents (rule 5).
+    fn rank(&self, substituents: &[Node]) -> Option<(Vec<usize>, bool)> {
+        let mut order = (0..substituents.len()).collect::<Vec<usize>>();
+        order.sort_by(|first, second| {
+            self.compare(&substituents[*second], &substituents[*first])
+                .0
+        });
+
+        let mut needs_chirality = false;
+        for pair in order.windows(2) {
+            match self.compare(&substituents[pair[0]], &substituents[pair[1]]) {
+                (Ordering::Equal, _) => return None,
+                (_, rule) => needs_chirality |= rule == Some(Rule::Chirality),
+            }
+        }
+        Some((order, needs_chirality))
+    }
+
+    fn center_label(&self, stereo: &TetrahedralStereo) -> Option<CipLabel> {
+        let center = stereo.center;
+        let substituents = stereo
+            .neighbors
+            .iter()
+            .map(|neighbor| {
+                if *neighbor != center {
+                    Node::Atom {
+                        atom: *neighbor,
+                        path: vec![center],
+                    }
+                } else if self.molecule.implicit_hydrogen_count(center) > 0 {
+                    Node::ImplicitHydrogen { depth: 1 }
+                } else {
+                    Node::Phantom
+                }
+            })
+            .collect::<Vec<Node>>();
+
+        let (order, is_pseudoasymmetric) = self.rank(&substituents)?;
+        let by_priority = [
+            stereo.neighbors[order[0]],
+            stereo.neighbors[order[1]],
+            stereo.neighbors[order[2]],
+            stereo.neighbors[order[3]],
+        ];
+
+        // Looking from the highest priority substituent, the rest turn clockwise for R, which
+        // is the same as the first three turning clockwise with the lowest pointing away
+        let label = match (stereo.parity_for(&by_priority)?, is_pseudoasymmetric) {
+            (TetrahedralParity::Clockwise, false) => CipLabel::R,
+            (TetrahedralParity::Anticlockwise, false) => CipLabel::S,
+            (TetrahedralParity::Clockwise, true) => CipLabel::PseudoR,
+            (TetrahedralParity::Anticlockwise, true) => CipLabel::PseudoS,
+        };
+        Some(label)
+    }
+
+    fn double_bond_label(&self, stereo: &DoubleBondStereo) -> Option<CipLabel> {
+        let bond = self.molecule.bonds().get(stereo.bond)?;
+        let highest_from = self.highest_substituent(bond.from_atom_id, bond.to_atom_id)?;
+        let highest_to = self.highest_substituent(bond.to_atom_id, bond.from_atom_id)?;
+
+        match stereo.configuration_for([highest_from, highest_to]) {
+            DoubleBondConfiguration::Cis => Some(CipLabel::Z),
+            DoubleBondConfiguration::Trans => Some(CipLabel::E),
+        }
+    }
+
+    // The highest priority neighbour of one end of a double bond, other than the other end
+    fn highest_substituent(&self, atom: AtomIndex, other_end: AtomIndex) -> Option<AtomIndex> {
+        let mut substituents = self
+            .molecule
+            .neighbors(atom)
+            .filter(|neighbor| *neighbor != other_end)
+            .map(|neighbor| Node::Atom {
+                atom: neighbor,
+                path: vec![atom],
+            })
+            .collect::<Vec<Node>>();
+        let hydrogen_count = self.molecule.implicit_hydrogen_count(atom) as usize;
+        substituents.extend(repeat_n(
+            Node::ImplicitHydrogen { depth: 1 },
+            hydrogen_count,
+        ));
+
+        let highest = match substituents.len() {
+            1 => 0,
+            _ => self.rank(&substituents)?.0[0],
+        };
+        match &substituents[highest] {
+            Node::Atom { atom, .. } => Some(*atom),
+            _ => None,
+        }
+    }
+}
+
+fn duplicate(atom: AtomIndex, distance: usize) -> Node {
+    Node::Duplicate { atom, distance }
+}
+
+fn bond_order(molecule: &Molecule, bond: BondIndex) -> usize {
+    match molecule.bonds()[bond].bond_type {
+        BondType::Covalent(order) if order > 1 => order as usize,
+        _ => 1,
+    }
+}
+
+/// Sets the Cahn–Ingold–Prelog labels of the molecule's stereocentres (R, S, or r and s for
+/// pseudoasymmetric centres) and double bonds (E or Z) as `CipLabel` properties, replacing any
+/// labels set before.
+///
+/// Substituents are ranked on the hierarchical digraph, with duplicate atoms for ring closures
+/// and multiple bonds, by rules 1a (atomic number), 1b (root distance of duplicates), 2 (mass,
+/// with unlabelled atoms taking their element's average mass), 3 (Z before E), 4a (stereogenic
+/// before non-stereogenic), 4b (like pairs of descriptors before unlike pairs), 4c (r before s)
+/// and 5 (R before S). Centres and double bonds whose substituents cannot be told apart are left
+/// without a label.
+///
+/// Only part of the 2013 rules is followed, so labels agree with the CIP validation suite only
+/// where the rest does not matter: rule 4b pairs the descriptors of stereocentres only, not of
+/// double bonds, the configurations of other stereo units are taken from their own labels rather
+/// than auxiliary descriptors, and aromatic rings are ranked in one Kekulé structure rather than
+/// with the averaged duplicate atoms of mancude rings.
+pub fn assign_cip_labels(molecule: &mut Molecule) {
+    let mut kekule = molecule.clone();
+    if kekule.bonds().iter().any(|bond| bond.aromatic) {
+        // Without a Kekulé structure, aromatic bonds have no duplicate atoms
+        let _ = kekulize(&mut kekule);
+    }
+
+    let mut atom_labels = vec![None; molecule.atoms().len()];
+    let mut bond_labels = vec![None; molecule.bonds().len()];
+    for _ in 0..MAX_PASSES {
+        let digraph = Digraph {
+            molecule: &kekule,
+            atom_labels: &atom_labels,
+            bond_labels: &bond_labels,
+            sorted_children: RefCell::new(HashMap::new()),
+        };
+
+        let mut new_atom_labels = vec![None; atom_labels.len()];
+        for stereo in &molecule.stereo().tetrahedral {
+            if stereo.center < new_atom_labels.len() {
+                new_atom_labels[stereo.center] = digraph.center_label(stereo);
+            }
+        }
+        let mut new_bond_labels = vec![None; bond_labels.len()];
+        for stereo in &molecule.stereo().double_bonds {
+            if stereo.bond < new_bond_labels.len() {
+                new_bond_labels[stereo.bond] = digraph.double_bond_label(stereo);
+            }
+        }
+
+        let is_settled = new_atom_labels == atom_labels && new_bond_labels == bond_labels;
+        atom_labels = new_atom_labels;
+        bond_labels = new_bond_labels;
+        if is_settled {
+            break;
+        }
+    }
+
+    for (atom, label) in molecule.atoms_mut().iter_mut().zip(atom_labels) {
+        atom.properties.remove(&AtomProperty::CipLabel);
+        if let Some(label) = label {
+            atom.set_property(AtomProperty::CipLabel, label);
+        }
+    }
+    for (bond, label) in molecule.bonds_mut().iter_mut().zip(bond_labels) {
+        bond.properties.remove(&BondProperty::CipLabel);
+        if let Some(label) = label {
+            bond.set_property(BondProperty::CipLabel, label);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read_smiles;
+
+    fn atom_labels(smiles: &str) -> Vec<(AtomIndex, String)> {
+        let mut mol = read_smiles(smiles).unwrap();
+        assign_cip_labels(&mut mol);
+        mol.atoms()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, atom)| {
+                atom.get_property_as_string(&AtomProperty::CipLabel)
+                    .map(|label| (index, label))
+            })
+            .collect()
+    }
+
+    fn bond_labels(smiles: &str) -> Vec<(BondIndex, String)> {
+        let mut mol = read_smiles(smiles).unwrap();
+        assign_cip_labels(&mut mol);
+        mol.bonds()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, bond)| {
+                bond.get_property_as_string(&BondProperty::CipLabel)
+                    .map(|label| (index, label))
+            })
+            .collect()
+    }
+
+    fn label(index: usize, label: &str) -> (usize, String) {
+        (index, label.to_string())
+    }
+
+    #[test]
+    fn tetrahedral_labels() {
+        // L-alanine, and the same centre written from the hydrogen
+        assert_eq!(atom_labels("N[C@@H](C)C(=O)O"), vec![label(1, "S")]);
+        assert_eq!(atom_labels("[C@H](N)(C)C(=O)O"), vec![label(0, "S")]);
+        assert_eq!(atom_labels("N[C@H](C)C(=O)O"), vec![label(1, "R")]);
+
+        assert_eq!(atom_labels("[C@@H](F)(Cl)Br"), vec![label(0, "R")]);
+        assert_eq!(atom_labels("F[C@](Cl)(Br)I"), vec![label(1, "S")]);
+
+        // Unlabelled where the substituents are the same
+        assert_eq!(atom_labels("C[C@H](C)O"), Vec::new());
+    }
+
+    #[test]
+    fn duplicate_atoms() {
+        // D-glyceraldehyde, where the aldehyde's doubled oxygen ranks it above CH2OH
+        assert_eq!(atom_labels("OC[C@@H](O)C=O"), vec![label(2, "R")]);
+
+        // (R)-limonene, where the ring branches only differ at their double bond
+        assert_eq!(atom_labels("CC1=CC[C@@H](CC1)C(=C)C"), vec![label(4, "R")]);
+
+        // (S)-2-methylcyclohexanone
+        assert_eq!(atom_labels("C[C@H]1CCCCC1=O"), vec![label(1, "S")]);
+    }
+
+    #[test]
+    fn polycyclic_labels() {
+        // Cholesterol: 3S, 8S, 9S, 10R, 13R, 14S, 17R, 20R
+        let labels = atom_labels(
+            "C[C@H](CCCC(C)C)[C@H]1CC[C@@H]2[C@@]1(CC[C@H]3[C@H]2CC=C4[C@@]3(CC[C@@H](C4)O)C)C",
+        );
+
+        assert_eq!(
+            labels,
+            vec![
+                label(1, "R"),
+                label(8, "R"),
+                label(11, "S"),
+                label(12, "R"),
+                label(15, "S"),
+                label(16, "S"),
+                label(20, "R"),
+                label(23, "S"),
+            ]
+        );
+    }
+
+    #[test]
+    fn large_ring_system_labels() {
+        // Two coronenyl groups, and their perhydro analogues, which are only found to be the same
+        // after unfolding each into a digraph of thousands of nodes
+        let coronenyl = "c1cc2ccc3ccc4ccc5ccc6ccc1c7c2c3c4c5c67";
+        let perhydrocoronenyl = coronenyl.to_uppercase();
+        assert_eq!(
+            atom_labels(&format!("F[C@H]({}){}", coronenyl, coronenyl)),
+            Vec::new()
+        );
+        assert_eq!(
+            atom_labels(&format!(
+                "F[C@H]({}){}",
+                perhydrocoronenyl, perhydrocoronenyl
+            )),
+            Vec::new()
+        );
+        assert_eq!(
+            atom_labels(&format!("F[C@H]({})[2H]", coronenyl)),
+            vec![label(1, "S")]
+        );
+    }
+
+    #[test]
+    fn isotope_labels() {
+        // Ethanol-1-d, told apart by deuterium ranking above hydrogen
+        assert_eq!(atom_labels("C[C@@H]([2H])O"), vec![label(1, "R")]);
+        assert_eq!(atom_labels("C[C@H]([2H])O"), vec![label(1, "S")]);
+        assert_eq!(atom_labels("C[C@@H]([13CH3])O"), vec![label(1, "S")]);
+    }
+
+    #[test]
+    fn double_bond_labels() {
+        assert_eq!(bond_labels("F/C=C/F"), vec![label(1, "E")]);
+        assert_eq!(bond_labels("F/C=C\\F"), vec![label(1, "Z")]);
+
+        // The bromine outranks the methyl group it is trans to
+        assert_eq!(bond_labels("C/C(Br)=C/C"), vec![label(2, "Z")]);
+        assert_eq!(bond_labels("C/C(CC)=C/C"), vec![label(3, "Z")]);
+        assert_eq!(bond_labels("C/C(C)=C/C"), Vec::new());
+    }
+
+    #[test]
+    fn pseudoasymmetric_labels() {
+        // Pentane-2,3,4-triol with 2R and 4S centres, where C3 is pseudoasymmetric
+        let labels = atom_labels("C[C@@H](O)[C@@H](O)[C@@H](O)C");
+        assert_eq!(labels[0], label(1, "R"));
+        assert_eq!(labels[2], label(5, "S"));
+        assert_eq!(labels[1], label(3, "s"));
+
+        let labels = atom_labels("C[C@@H](O)[C@H](O)[C@@H](O)C");
+        assert_eq!(labels[1], label(3, "r"));
+
+        // Without stereocentres on either side, C3 has no label
+        assert_eq!(atom_labels("CC(O)[C@@H](O)C(O)C"), Vec::new());
+    }
+
+    #[test]
+    fn like_pair_labels() {
+        // The 2S,3S branch (like) ranks above the 2R,3S branch (unlike) by rule 4b, although
+        // rule 5 alone would rank the R centre first
+        let labels = atom_labels("F[C@@H]([C@@H](O)[C@@H](O)C)[C@H](O)[C@@H](O)C");
+        assert_eq!(labels[0], label(1, "R"));
+        assert_eq!(labels[1], label(2, "S"));
+        assert_eq!(labels[2], label(4, "S"));
+        assert_eq!(labels[3], label(7, "R"));
+        assert_eq!(labels[4], label(9, "S"));
+
+        // With the like branch second, the label turns over
+        let labels = atom_labels("F[C@@H]([C@H](O)[C@H](O)C)[C@@H](O)[C@H](O)C");
+        assert_eq!(labels[0], label(1, "R"));
+        let labels = atom_labels("F[C@@H]([C@H](O)[C@@H](O)C)[C@H](O)[C@H](O)C");
+        assert_eq!(labels[0], label(1, "S"));
+    }
+
+    #[test]
+    fn pseudoasymmetry_labels() {
+        // The branches tie by rule 4b, and the one with the r centre ranks above the one with
+        // the s centre by rule 4c, although rule 5 alone would rank the branch starting at the
+        // R centre first
+        let labels = atom_labels(
+            "F[C@@H]([C@@H](O)[C@@H]([C@H](O)C)[C@@H](O)C)[C@H](O)[C@H]([C@H](O)C)[C@@H](O)C",
+        );
+        assert_eq!(labels[0], label(1, "R"));
+        assert_eq!(labels[1], label(2, "S"));
+        assert_eq!(labels[2], label(4, "r"));
+        assert_eq!(labels[5], label(11, "R"));
+        assert_eq!(labels[6], label(13, "s"));
+    }
+
+    #[test]
+    fn labels_replace_previous_labels() {
+        let mut mol = read_smiles("N[C@@H](C)C(=O)O").unwrap();
+        assign_cip_labels(&mut mol);
+        mol.stereo_mut().tetrahedral[0].parity = TetrahedralParity::Anticlockwise;
+
+        assign_cip_labels(&mut mol);
+
+        assert!(matches!(
+            mol.atoms()[1].get_property::<CipLabel>(&AtomProperty::CipLabel),
+            Ok(Some(CipLabel::R))
+        ));
+
+        mol.stereo_mut().tetrahedral.clear();
+        assign_cip_labels(&mut mol);
+        assert!(!mol.atoms()[1]
+            .properties
+            .contains_key(&AtomProperty::CipLabel));
+    }
+}
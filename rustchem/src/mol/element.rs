@@ -1,6 +1,7 @@
-use super::MoleculeError;
+use super::isotope::isotopes_of;
+use super::{Isotope, MoleculeError};
 use lazy_static::lazy_static;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use std::fmt;
 
@@ -23,18 +24,61 @@ lazy_static! {
         let mut map: HashMap<String, &'static Element> = HashMap::new();
 
         for element in ELEMENTS_BY_NUMBER.values() {
-            map.insert(element.symbol.clone(), &element);
+            map.insert(element.symbol.clone(), element);
         }
 
         map
     };
+    static ref DUMMY: Element = Element {
+        atomic_number: 0,
+        symbol: "*".to_string(),
+        name: "Dummy".to_string(),
+        period: 0,
+        group: None,
+        average_mass: 0.0,
+        monoisotopic_mass: 0.0,
+        most_common_isotope: 0,
+        electronegativity: None,
+        covalent_radius: None,
+        vdw_radius: None,
+        default_valences: Vec::new(),
+    };
 }
 
 #[derive(Deserialize)]
 pub struct Element {
     pub atomic_number: u32,
     pub symbol: String,
+    pub name: String,
+    pub period: u32,
+    /// IUPAC group (1-18), or `None` for the lanthanides and actinides.
+    pub group: Option<u32>,
+    /// Standard atomic weight, or the mass number of the longest-lived
+    /// isotope for elements without one.
+    pub average_mass: f64,
+    /// Exact mass of the most abundant (or longest-lived) isotope.
+    pub monoisotopic_mass: f64,
     pub most_common_isotope: u32,
+    /// Pauling electronegativity.
+    pub electronegativity: Option<f64>,
+    /// Single-bond covalent radius in Angstroms.
+    pub covalent_radius: Option<f64>,
+    /// Van der Waals radius in Angstroms.
+    pub vdw_radius: Option<f64>,
+    /// Common valences for the neutral element, lowest first.
+    #[serde(deserialize_with = "deserialize_valences")]
+    pub default_valences: Vec<u32>,
+}
+
+fn deserialize_valences<'de, D>(deserializer: D) -> Result<Vec<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    value
+        .split_whitespace()
+        .map(|valence| valence.parse::<u32>().map_err(serde::de::Error::custom))
+        .collect()
 }
 
 impl Element {
@@ -45,12 +89,28 @@ impl Element {
         }
     }
 
+    /// Returns the element with the given symbol, or the dummy element for '*'.
     pub fn from_symbol(symbol: &str) -> Result<&'static Element, MoleculeError> {
         match ELEMENTS_BY_SYMBOL.get(symbol) {
             Some(element) => Ok(element),
+            None if symbol == "*" => Ok(Element::dummy()),
             None => Err(MoleculeError::UnknownElementSymbol(symbol.to_string())),
         }
     }
+
+    /// Returns the dummy element ('*', atomic number 0) of query and wildcard atoms that do not
+    /// stand for any one element. It has no mass, isotopes or valences.
+    pub fn dummy() -> &'static Element {
+        &DUMMY
+    }
+
+    pub fn is_dummy(&self) -> bool {
+        self.atomic_number == 0
+    }
+
+    pub fn isotopes(&self) -> &'static [Isotope] {
+        isotopes_of(self.atomic_number)
+    }
 }
 
 impl PartialEq for Element {
@@ -68,31 +128,89 @@ impl fmt::Debug for Element {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_f64_eq;
 
     #[test]
     fn new_from_atomic_number() -> Result<(), MoleculeError> {
-        let element_c = Element::from_atomic_number(12)?;
-        let element_o = Element::from_atomic_number(16)?;
+        let element_c = Element::from_atomic_number(6)?;
+        let element_o = Element::from_atomic_number(8)?;
 
-        assert_eq!(element_c.atomic_number, 12);
-        assert_eq!(element_o.atomic_number, 16);
+        assert_eq!(element_c.atomic_number, 6);
+        assert_eq!(element_o.atomic_number, 8);
 
         Ok(())
     }
 
     #[test]
     fn new_from_atomic_number_reads_properties() -> Result<(), MoleculeError> {
-        let element_c = Element::from_atomic_number(12)?;
+        let element_c = Element::from_atomic_number(6)?;
 
-        assert_eq!(element_c.atomic_number, 12);
+        assert_eq!(element_c.atomic_number, 6);
         assert_eq!(element_c.symbol, "C");
+        assert_eq!(element_c.name, "Carbon");
+        assert_eq!(element_c.period, 2);
+        assert_eq!(element_c.group, Some(14));
+        assert_f64_eq(element_c.average_mass, 12.011);
+        assert_f64_eq(element_c.monoisotopic_mass, 12.0);
         assert_eq!(element_c.most_common_isotope, 12);
+        assert_eq!(element_c.electronegativity, Some(2.55));
+        assert_eq!(element_c.covalent_radius, Some(0.76));
+        assert_eq!(element_c.vdw_radius, Some(1.70));
+        assert_eq!(element_c.default_valences, vec![4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn new_from_atomic_number_reads_optional_properties() -> Result<(), MoleculeError> {
+        let element_s = Element::from_atomic_number(16)?;
+        let element_ne = Element::from_atomic_number(10)?;
+        let element_ce = Element::from_atomic_number(58)?;
+        let element_og = Element::from_atomic_number(118)?;
+
+        assert_eq!(element_s.default_valences, vec![2, 4, 6]);
+        assert_eq!(element_ne.electronegativity, None);
+        assert_eq!(element_ne.default_valences, vec![0]);
+        assert_eq!(element_ce.group, None);
+        assert_eq!(element_og.covalent_radius, None);
+        assert!(element_og.default_valences.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn periodic_table_complete() -> Result<(), MoleculeError> {
+        for atomic_number in 1..=118 {
+            let element = Element::from_atomic_number(atomic_number)?;
+            assert_eq!(element.atomic_number, atomic_number);
+            assert_eq!(Element::from_symbol(&element.symbol)?, element);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dummy_element() -> Result<(), MoleculeError> {
+        let dummy = Element::from_symbol("*")?;
+
+        assert!(dummy.is_dummy());
+        assert_eq!(dummy, Element::dummy());
+        assert!(!Element::from_symbol("C")?.is_dummy());
+        assert!(dummy.isotopes().is_empty());
+        assert!(dummy.default_valences.is_empty());
 
         Ok(())
     }
 
     #[test]
     fn new_from_atomic_number_error_unknown() -> Result<(), MoleculeError> {
+        match Element::from_atomic_number(0) {
+            Err(MoleculeError::UnknownAtomicNumber(atomic_number)) => {
+                assert_eq!(atomic_number, 0);
+            }
+            _ => panic!("Expected MoleculeError::UnknownAtomicNumber"),
+        }
+
         match Element::from_atomic_number(1234) {
             Err(MoleculeError::UnknownAtomicNumber(atomic_number)) => {
                 assert_eq!(atomic_number, 1234);
@@ -107,9 +225,11 @@ mod tests {
     fn new_from_symbol() -> Result<(), MoleculeError> {
         let element_c = Element::from_symbol("C")?;
         let element_o = Element::from_symbol("O")?;
+        let element_cl = Element::from_symbol("Cl")?;
 
-        assert_eq!(element_c.atomic_number, 12);
-        assert_eq!(element_o.atomic_number, 16);
+        assert_eq!(element_c.atomic_number, 6);
+        assert_eq!(element_o.atomic_number, 8);
+        assert_eq!(element_cl.atomic_number, 17);
 
         Ok(())
     }
@@ -129,12 +249,12 @@ mod tests {
     #[test]
     fn equality() -> Result<(), MoleculeError> {
         assert_eq!(
-            Element::from_atomic_number(12)?,
-            Element::from_atomic_number(12)?
+            Element::from_atomic_number(6)?,
+            Element::from_atomic_number(6)?
         );
         assert_ne!(
-            Element::from_atomic_number(14)?,
-            Element::from_atomic_number(12)?
+            Element::from_atomic_number(7)?,
+            Element::from_atomic_number(6)?
         );
 
         Ok(())
@@ -6,6 +6,25 @@ pub enum MoleculeError {
     UnknownAtomicNumber(u32),
     #[error("Unknown element symbol '{0}'")]
     UnknownElementSymbol(String),
+    #[error("Unknown isotope '{mass_number}{symbol}'")]
+    UnknownIsotope { symbol: String, mass_number: u32 },
+    #[error("Atom index {0} is out of range")]
+    InvalidAtomIndex(usize),
+    #[error("Bond index {0} is out of range")]
+    InvalidBondIndex(usize),
+    #[error("Atom {0} cannot be bonded to itself")]
+    SelfBond(usize),
+    #[error("Atoms {from_atom_id} and {to_atom_id} are already bonded")]
+    DuplicateBond {
+        from_atom_id: usize,
+        to_atom_id: usize,
+    },
+    #[error("{message} at position {position} of molecular formula")]
+    InvalidFormula { message: String, position: usize },
+    #[error("Formula has a negative count of '{0}'")]
+    NegativeCount(String),
+    #[error("No Kekulé structure exists: atoms {atoms:?} cannot be given a double bond")]
+    NoKekuleStructure { atoms: Vec<usize> },
 }
 
 #[derive(Error, Debug)]
@@ -0,0 +1,423 @@
+use super::{AtomProperty, Element, Isotope, Molecule, MoleculeError};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+/// The number of atoms of each element in a molecule, along with its overall charge.
+///
+/// Atoms labelled with an isotope are counted separately from those of the natural element.
+/// Formulas print in Hill order (carbon, then hydrogen, then the other elements alphabetically,
+/// or all elements alphabetically if there is no carbon), with isotopes in brackets after their
+/// element and the charge at the end, as in `C5[13C]H12O6` or `C2H5O2-`. Negative counts, as
+/// left by subtracting one formula from another, print signed in parentheses so that they cannot
+/// be read as a charge, as in `H2O(-1)`.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
+pub struct MolecularFormula {
+    // Counts by atomic number and mass number, with no zero counts so that equal formulas compare
+    // equal
+    counts: BTreeMap<(u32, Option<u32>), i32>,
+    charge: i32,
+}
+
+impl MolecularFormula {
+    pub fn new() -> MolecularFormula {
+        MolecularFormula::default()
+    }
+
+    /// Returns the formula of a molecule, including its implicit hydrogens.
+    ///
+    /// Query atoms with an atom list and dummy atoms are not counted.
+    pub fn from_molecule(molecule: &Molecule) -> MolecularFormula {
+        let mut formula = MolecularFormula::new();
+        let hydrogen =
+            Element::from_atomic_number(1).expect("Embedded elements.csv is missing hydrogen.");
+
+        for (index, atom) in molecule.atoms().iter().enumerate() {
+            if atom.element.is_dummy() || atom.properties.contains_key(&AtomProperty::AtomList) {
+                continue;
+            }
+            formula.add_atoms(atom.element, atom.isotope, 1);
+            formula.add_atoms(
+                hydrogen,
+                None,
+                molecule.implicit_hydrogen_count(index) as i32,
+            );
+            formula.charge += atom.formal_charge;
+        }
+
+        formula
+    }
+
+    /// Adds `count` atoms of the element, or of one of its isotopes if `isotope` gives a mass
+    /// number. A negative count removes atoms.
+    pub fn add_atoms(&mut self, element: &Element, isotope: Option<u32>, count: i32) {
+        let key = (element.atomic_number, isotope);
+        let total = self.counts.get(&key).copied().unwrap_or(0) + count;
+        if total == 0 {
+            self.counts.remove(&key);
+        } else {
+            self.counts.insert(key, total);
+        }
+    }
+
+    /// Returns the number of atoms of the element, including any labelled with an isotope.
+    pub fn count(&self, element: &Element) -> i32 {
+        self.counts
+            .range((element.atomic_number, None)..=(element.atomic_number, Some(u32::MAX)))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Returns the number of atoms of the element with the given isotope label, or with no
+    /// label if `isotope` is `None`.
+    pub fn isotope_count(&self, element: &Element, isotope: Option<u32>) -> i32 {
+        self.counts
+            .get(&(element.atomic_number, isotope))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the element, isotope label and count of each entry of the formula, in Hill order.
+    pub fn counts(&self) -> Vec<(&'static Element, Option<u32>, i32)> {
+        let mut counts = self
+            .counts
+            .iter()
+            .map(|((atomic_number, isotope), count)| {
+                let element = Element::from_atomic_number(*atomic_number)
+                    .expect("Formula refers to an unknown element.");
+                (element, *isotope, *count)
+            })
+            .collect::<Vec<(&'static Element, Option<u32>, i32)>>();
+
+        let has_carbon = self
+            .counts
+            .keys()
+            .any(|(atomic_number, _)| *atomic_number == 6);
+        counts.sort_by(|(a, a_isotope, _), (b, b_isotope, _)| {
+            let rank = |element: &Element| match element.atomic_number {
+                6 if has_carbon => 0,
+                1 if has_carbon => 1,
+                _ => 2,
+            };
+            rank(a)
+                .cmp(&rank(b))
+                .then_with(|| a.symbol.cmp(&b.symbol))
+                .then_with(|| a_isotope.cmp(b_isotope))
+        });
+        counts
+    }
+
+    pub fn charge(&self) -> i32 {
+        self.charge
+    }
+
+    pub fn set_charge(&mut self, charge: i32) {
+        self.charge = charge;
+    }
+}
+
+impl fmt::Display for MolecularFormula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (element, isotope, count) in self.counts() {
+            match isotope {
+                Some(mass_number) => write!(f, "[{}{}]", mass_number, element.symbol)?,
+                None => f.write_str(&element.symbol)?,
+            }
+            if count < 0 {
+                write!(f, "({})", count)?;
+            } else if count != 1 {
+                write!(f, "{}", count)?;
+            }
+        }
+
+        match self.charge {
+            0 => Ok(()),
+            1 => f.write_str("+"),
+            -1 => f.write_str("-"),
+            charge => write!(f, "{:+}", charge),
+        }
+    }
+}
+
+/// Parses formulas such as `C6H12O6`, `[13C]H4` or `C2H5O2-`.
+///
+/// Elements may appear more than once (as in `CH3COOH`), and isotopes are given as a mass number
+/// and symbol in brackets. Negative counts are given signed in parentheses (as in `H2O(-1)`). A
+/// charge may follow at the end as a sign, optionally followed by its size (as in `SO4-2`).
+impl FromStr for MolecularFormula {
+    type Err = MoleculeError;
+
+    fn from_str(formula: &str) -> Result<Self, Self::Err> {
+        let chars = formula.chars().collect::<Vec<char>>();
+        let mut position = 0;
+        let mut result = MolecularFormula::new();
+
+        let read_number = |position: &mut usize| {
+            let start = *position;
+            while *position < chars.len() && chars[*position].is_ascii_digit() {
+                *position += 1;
+            }
+            if *position == start {
+                return Ok(None);
+            }
+            chars[start..*position]
+                .iter()
+                .collect::<String>()
+                .parse::<i32>()
+                .map(Some)
+                .map_err(|_| formula_error("Number is too large", start))
+        };
+        let read_symbol = |position: &mut usize| {
+            let start = *position;
+            if *position < chars.len() && chars[*position].is_ascii_uppercase() {
+                *position += 1;
+                if *position < chars.len() && chars[*position].is_ascii_lowercase() {
+                    *position += 1;
+                }
+                let symbol = chars[start..*position].iter().collect::<String>();
+                Element::from_symbol(&symbol)
+            } else {
+                Err(formula_error("Expected an element symbol", start))
+            }
+        };
+
+        while position < chars.len() && chars[position] != '+' && chars[position] != '-' {
+            let (element, isotope) = if chars[position] == '[' {
+                position += 1;
+                let start = position;
+                let mass_number = read_number(&mut position)?
+                    .ok_or_else(|| formula_error("Expected a mass number", start))?;
+                let element = read_symbol(&mut position)?;
+                Isotope::lookup(element, mass_number as u32)?;
+                if chars.get(position) != Some(&']') {
+                    return Err(formula_error("Expected ']'", position));
+                }
+                position += 1;
+                (element, Some(mass_number as u32))
+            } else {
+                (read_symbol(&mut position)?, None)
+            };
+
+            let count = if chars.get(position) == Some(&'(') {
+                if chars.get(position + 1) != Some(&'-') {
+                    return Err(formula_error("Expected '-'", position + 1));
+                }
+                position += 2;
+                let start = position;
+                let count = read_number(&mut position)?
+                    .ok_or_else(|| formula_error("Expected a count", start))?;
+                if chars.get(position) != Some(&')') {
+                    return Err(formula_error("Expected ')'", position));
+                }
+                position += 1;
+                -count
+            } else {
+                read_number(&mut position)?.unwrap_or(1)
+            };
+            result.add_atoms(element, isotope, count);
+        }
+
+        if position < chars.len() {
+            let sign = if chars[position] == '+' { 1 } else { -1 };
+            position += 1;
+            result.charge = sign * read_number(&mut position)?.unwrap_or(1);
+            if position < chars.len() {
+                return Err(formula_error("Unexpected character after charge", position));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+fn formula_error(message: &str, position: usize) -> MoleculeError {
+    MoleculeError::InvalidFormula {
+        message: message.to_string(),
+        position,
+    }
+}
+
+impl AddAssign for MolecularFormula {
+    fn add_assign(&mut self, other: MolecularFormula) {
+        for ((atomic_number, isotope), count) in other.counts {
+            let element = Element::from_atomic_number(atomic_number)
+                .expect("Formula refers to an unknown element.");
+            self.add_atoms(element, isotope, count);
+        }
+        self.charge += other.charge;
+    }
+}
+
+impl SubAssign for MolecularFormula {
+    fn sub_assign(&mut self, other: MolecularFormula) {
+        for ((atomic_number, isotope), count) in other.counts {
+            let element = Element::from_atomic_number(atomic_number)
+                .expect("Formula refers to an unknown element.");
+            self.add_atoms(element, isotope, -count);
+        }
+        self.charge -= other.charge;
+    }
+}
+
+impl Add for MolecularFormula {
+    type Output = MolecularFormula;
+
+    fn add(mut self, other: MolecularFormula) -> MolecularFormula {
+        self += other;
+        self
+    }
+}
+
+/// Subtracts one formula from another, as for a loss. Counts may become negative, in which case
+/// they are printed signed in parentheses.
+impl Sub for MolecularFormula {
+    type Output = MolecularFormula;
+
+    fn sub(mut self, other: MolecularFormula) -> MolecularFormula {
+        self -= other;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read_smiles;
+
+    fn formula(formula: &str) -> MolecularFormula {
+        formula.parse().unwrap()
+    }
+
+    #[test]
+    fn from_molecule() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("OC[C@H]1OC(O)[C@H](O)[C@@H](O)[C@@H]1O")?;
+        assert_eq!(MolecularFormula::from_molecule(&mol).to_string(), "C6H12O6");
+
+        let mol = read_smiles("[13CH3]C([2H])=O.CC(=O)[O-].[Na+]")?;
+        let formula = MolecularFormula::from_molecule(&mol);
+        assert_eq!(formula.to_string(), "C3[13C]H6[2H]NaO3");
+        assert_eq!(formula.charge(), 0);
+        assert_eq!(formula.count(Element::from_symbol("C")?), 4);
+        assert_eq!(formula.count(Element::from_symbol("H")?), 7);
+        assert_eq!(
+            formula.isotope_count(Element::from_symbol("C")?, Some(13)),
+            1
+        );
+        assert_eq!(formula.isotope_count(Element::from_symbol("H")?, None), 6);
+
+        let mol = read_smiles("[NH4+]")?;
+        assert_eq!(MolecularFormula::from_molecule(&mol).to_string(), "H4N+");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse() -> Result<(), MoleculeError> {
+        let glucose = formula("C6H12O6");
+        assert_eq!(glucose.count(Element::from_symbol("C")?), 6);
+        assert_eq!(glucose.count(Element::from_symbol("H")?), 12);
+        assert_eq!(glucose.count(Element::from_symbol("O")?), 6);
+        assert_eq!(glucose.count(Element::from_symbol("N")?), 0);
+
+        let methane = formula("[13C]H4");
+        assert_eq!(
+            methane.isotope_count(Element::from_symbol("C")?, Some(13)),
+            1
+        );
+        assert_eq!(methane.isotope_count(Element::from_symbol("C")?, None), 0);
+
+        let acetate = formula("C2H5O2-");
+        assert_eq!(acetate.charge(), -1);
+        assert_eq!(acetate.count(Element::from_symbol("O")?), 2);
+
+        assert_eq!(formula("SO4-2").charge(), -2);
+        assert_eq!(formula("Fe+3").charge(), 3);
+        assert_eq!(formula("CH3COOH"), formula("C2H4O2"));
+        assert_eq!(formula(""), MolecularFormula::new());
+
+        let difference = formula("H2O(-1)-");
+        assert_eq!(difference.count(Element::from_symbol("O")?), -1);
+        assert_eq!(difference.charge(), -1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let error_position = |formula: &str| match formula.parse::<MolecularFormula>() {
+            Err(MoleculeError::InvalidFormula { position, .. }) => position,
+            _ => panic!("Expected MoleculeError::InvalidFormula for '{}'", formula),
+        };
+
+        assert_eq!(error_position("c6H6"), 0);
+        assert_eq!(error_position("C6[C]"), 3);
+        assert_eq!(error_position("C6[13C"), 6);
+        assert_eq!(error_position("CH3-1H"), 5);
+        assert_eq!(error_position("H2O(1)"), 4);
+        assert_eq!(error_position("H2O(-)"), 5);
+        assert_eq!(error_position("H2O(-1"), 6);
+
+        assert!(matches!(
+            "C6Xx".parse::<MolecularFormula>(),
+            Err(MoleculeError::UnknownElementSymbol(_))
+        ));
+        assert!(matches!(
+            "[40C]".parse::<MolecularFormula>(),
+            Err(MoleculeError::UnknownIsotope { .. })
+        ));
+    }
+
+    #[test]
+    fn display_hill_order() {
+        let formulas = [
+            ("C6H12O6", "C6H12O6"),
+            ("OH2", "H2O"),
+            ("ClH", "ClH"),
+            ("NaCl", "ClNa"),
+            ("BrCH3", "CH3Br"),
+            ("H[2H]O", "H[2H]O"),
+            ("[13C]C5H6", "C5[13C]H6"),
+            ("[13C]O2", "[13C]O2"),
+            ("C2H5O2-", "C2H5O2-"),
+            ("SO4-2", "O4S-2"),
+            ("H4N+", "H4N+"),
+            ("Fe+3", "Fe+3"),
+        ];
+
+        for (input, expected) in formulas.iter() {
+            assert_eq!(formula(input).to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn add_and_subtract() -> Result<(), MoleculeError> {
+        let water = formula("H2O");
+        let glucose = formula("C6H12O6");
+
+        assert_eq!(glucose.clone() + formula("H+"), formula("C6H13O6+"));
+        assert_eq!(glucose.clone() - water.clone(), formula("C6H10O5"));
+        assert_eq!(glucose.clone() - glucose.clone(), MolecularFormula::new());
+        assert_eq!((water.clone() - formula("O2")).to_string(), "H2O(-1)");
+
+        // Negative counts print unlike a charge, and parse back
+        let difference = formula("C2H4") - formula("CH5+");
+        assert_eq!(difference.to_string(), "CH(-1)-");
+        assert_eq!(formula(&difference.to_string()), difference);
+
+        let mut sodiated = glucose;
+        sodiated += formula("Na+");
+        assert_eq!(sodiated.to_string(), "C6H12NaO6+");
+        sodiated -= formula("Na+");
+        assert_eq!(sodiated, formula("C6H12O6"));
+
+        let mut formula = MolecularFormula::new();
+        formula.add_atoms(Element::from_symbol("O")?, None, 1);
+        formula.add_atoms(Element::from_symbol("H")?, None, 2);
+        formula.set_charge(1);
+        formula.add_atoms(Element::from_symbol("H")?, None, 1);
+        assert_eq!(formula.to_string(), "H3O+");
+
+        Ok(())
+    }
+}
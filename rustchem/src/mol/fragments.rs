@@ -0,0 +1,310 @@
+use super::{AtomIndex, Bond, BondIndex, Element, Molecule};
+use std::cmp::Ordering;
+
+/// How `Molecule::largest_fragment` ranks fragments.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum FragmentRule {
+    /// Most heavy (non-hydrogen) atoms, with ties broken by the larger mass
+    #[default]
+    HeavyAtomCount,
+    /// Largest mass, with ties broken by the larger number of heavy atoms
+    Mass,
+}
+
+impl Molecule {
+    /// Returns the atoms of each connected piece of the molecule.
+    ///
+    /// Components are ordered by their lowest atom index, and list their atoms in ascending order.
+    pub fn connected_components(&self) -> Vec<Vec<AtomIndex>> {
+        let mut component_of = vec![None; self.atoms().len()];
+        let mut components = Vec::new();
+
+        for start in 0..self.atoms().len() {
+            if component_of[start].is_some() {
+                continue;
+            }
+
+            let mut component = vec![start];
+            let mut stack = vec![start];
+            component_of[start] = Some(components.len());
+            while let Some(atom) = stack.pop() {
+                for neighbor in self.neighbors(atom) {
+                    if component_of[neighbor].is_none() {
+                        component_of[neighbor] = Some(components.len());
+                        component.push(neighbor);
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            component.sort_unstable();
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Splits the molecule into one molecule per connected component, in the order given by
+    /// `connected_components`.
+    ///
+    /// Atoms and bonds keep their coordinates and properties, and each fragment gets a copy of the
+    /// molecule's properties.
+    pub fn split_fragments(&self) -> Vec<Molecule> {
+        let components = self.connected_components();
+
+        let mut new_index = vec![0; self.atoms().len()];
+        let mut component_of = vec![0; self.atoms().len()];
+        for (component_index, component) in components.iter().enumerate() {
+            for (index, atom) in component.iter().enumerate() {
+                new_index[*atom] = index;
+                component_of[*atom] = component_index;
+            }
+        }
+
+        let mut fragment_bonds = vec![Vec::new(); components.len()];
+        let mut bond_component = vec![None; self.bonds().len()];
+        for (index, bond) in self.bonds().iter().enumerate() {
+            let (from_atom_id, to_atom_id) = (bond.from_atom_id, bond.to_atom_id);
+            if from_atom_id >= self.atoms().len() || to_atom_id >= self.atoms().len() {
+                continue;
+            }
+
+            let component = component_of[from_atom_id];
+            bond_component[index] = Some((component, fragment_bonds[component].len()));
+            fragment_bonds[component].push(Bond {
+                from_atom_id: new_index[from_atom_id],
+                to_atom_id: new_index[to_atom_id],
+                ..bond.clone()
+            });
+        }
+
+        components
+            .iter()
+            .zip(fragment_bonds)
+            .enumerate()
+            .map(|(component_index, (component, bonds))| {
+                let atoms = component
+                    .iter()
+                    .map(|atom| self.atoms()[*atom].clone())
+                    .collect();
+                let mut fragment = Molecule::from_graph(atoms, bonds);
+                fragment.properties = self.properties.clone();
+
+                // Keep the stereochemistry of the fragment's own atoms and bonds
+                let atom_map = (0..self.atoms().len())
+                    .map(|atom| {
+                        Some(new_index[atom]).filter(|_| component_of[atom] == component_index)
+                    })
+                    .collect::<Vec<Option<AtomIndex>>>();
+                let bond_map = bond_component
+                    .iter()
+                    .map(|entry| match entry {
+                        Some((component, bond)) if *component == component_index => Some(*bond),
+                        _ => None,
+                    })
+                    .collect::<Vec<Option<BondIndex>>>();
+                *fragment.stereo_mut() = self.stereo().clone();
+                fragment.stereo_mut().remap(&atom_map, &bond_map);
+                fragment
+            })
+            .collect()
+    }
+
+    /// Returns a copy of the largest fragment, or `None` if the molecule has no atoms.
+    ///
+    /// Fragments that are still tied after applying `rule` are decided in favour of the one that
+    /// comes first.
+    pub fn largest_fragment(&self, rule: FragmentRule) -> Option<Molecule> {
+        let mut largest: Option<(Molecule, (usize, f64))> = None;
+
+        for fragment in self.split_fragments() {
+            let size = (fragment.heavy_atom_count(), fragment.fragment_mass());
+            let is_larger = match &largest {
+                Some((_, largest_size)) => compare_sizes(size, *largest_size, rule).is_gt(),
+                None => true,
+            };
+            if is_larger {
+                largest = Some((fragment, size));
+            }
+        }
+
+        largest.map(|(fragment, _)| fragment)
+    }
+
+    fn heavy_atom_count(&self) -> usize {
+        self.atoms()
+            .iter()
+            .filter(|atom| atom.element.atomic_number != 1)
+            .count()
+    }
+
+    // Average mass of the atoms, including their implicit hydrogens
+    fn fragment_mass(&self) -> f64 {
+        let hydrogen_mass = Element::from_atomic_number(1)
+            .expect("Embedded elements.csv has no hydrogen.")
+            .average_mass;
+        (0..self.atoms().len())
+            .map(|atom| {
+                self.atoms()[atom].element.average_mass
+                    + self.implicit_hydrogen_count(atom) as f64 * hydrogen_mass
+            })
+            .sum()
+    }
+}
+
+fn compare_sizes(size: (usize, f64), other: (usize, f64), rule: FragmentRule) -> Ordering {
+    let by_count = size.0.cmp(&other.0);
+    let by_mass = size.1.partial_cmp(&other.1).unwrap_or(Ordering::Equal);
+
+    match rule {
+        FragmentRule::HeavyAtomCount => by_count.then(by_mass),
+        FragmentRule::Mass => by_mass.then(by_count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol::{Atom, BondType, HasProperties, MoleculeError, MoleculeProperty, Point3d};
+
+    fn molecule(
+        symbols: &[&str],
+        bonds: &[(AtomIndex, AtomIndex)],
+    ) -> Result<Molecule, MoleculeError> {
+        let atoms = symbols
+            .iter()
+            .map(|symbol| Atom::from_symbol(symbol))
+            .collect::<Result<Vec<Atom>, MoleculeError>>()?;
+        let bonds = bonds
+            .iter()
+            .map(|(from, to)| Bond::new(*from, *to, BondType::single()))
+            .collect();
+        Ok(Molecule::from_graph(atoms, bonds))
+    }
+
+    #[test]
+    fn connected_components() -> Result<(), MoleculeError> {
+        // Sodium acetate with a water molecule
+        let mol = molecule(
+            &["O", "C", "Na", "C", "O", "H", "H", "O"],
+            &[(1, 3), (4, 1), (0, 1), (7, 5), (6, 7)],
+        )?;
+
+        assert_eq!(
+            mol.connected_components(),
+            vec![vec![0, 1, 3, 4], vec![2], vec![5, 6, 7]]
+        );
+        assert!(Molecule::new().connected_components().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn split_fragments() -> Result<(), MoleculeError> {
+        let mut mol = molecule(
+            &["O", "C", "Na", "C", "O", "H", "H", "O"],
+            &[(1, 3), (4, 1), (0, 1), (7, 5), (6, 7)],
+        )?;
+        mol.bonds_mut()[2].bond_type = BondType::double();
+        mol.atoms_mut()[5].position = Point3d::new(1.0, 2.0, 3.0);
+        mol.atoms_mut()[2].formal_charge = 1;
+        mol.set_property(MoleculeProperty::Name, "Sodium acetate hydrate".to_string());
+
+        let fragments = mol.split_fragments();
+
+        assert_eq!(fragments.len(), 3);
+        let symbols = fragments
+            .iter()
+            .map(|fragment| {
+                fragment
+                    .atoms()
+                    .iter()
+                    .map(|atom| atom.element.symbol.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(symbols, vec!["OCCO", "Na", "HHO"]);
+
+        let bonds = fragments[0]
+            .bonds()
+            .iter()
+            .map(|bond| (bond.from_atom_id, bond.to_atom_id))
+            .collect::<Vec<(AtomIndex, AtomIndex)>>();
+        assert_eq!(bonds, vec![(1, 2), (3, 1), (0, 1)]);
+        assert_eq!(fragments[0].bonds()[2].bond_type, BondType::double());
+        assert_eq!(fragments[0].bond_between(0, 1), Some(2));
+        assert_eq!(fragments[1].atoms()[0].formal_charge, 1);
+        assert_eq!(
+            fragments[2].atoms()[0].position,
+            Point3d::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            fragments[2].neighbors(2).collect::<Vec<AtomIndex>>(),
+            vec![0, 1]
+        );
+
+        for fragment in &fragments {
+            assert_eq!(
+                fragment.get_property_as_string(&MoleculeProperty::Name),
+                Some("Sodium acetate hydrate".to_string())
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn largest_fragment() -> Result<(), MoleculeError> {
+        // Chloride (one heavy atom, 35.45) with methane (one heavy atom, 12.011 + 4 H)
+        let mut mol = molecule(
+            &["Cl", "C", "H", "H", "H", "H"],
+            &[(1, 2), (1, 3), (1, 4), (1, 5)],
+        )?;
+
+        let largest = mol.largest_fragment(FragmentRule::HeavyAtomCount).unwrap();
+        assert_eq!(largest.atoms()[0].element.symbol, "Cl");
+
+        // Ethane outnumbers chloride in heavy atoms, but not in mass
+        mol.atoms_mut()[5].element = Atom::from_symbol("C")?.element;
+
+        let largest = mol.largest_fragment(FragmentRule::HeavyAtomCount).unwrap();
+        assert_eq!(largest.atoms().len(), 5);
+        let largest = mol.largest_fragment(FragmentRule::Mass).unwrap();
+        assert_eq!(largest.atoms().len(), 1);
+
+        assert!(Molecule::new()
+            .largest_fragment(FragmentRule::default())
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn largest_fragment_tie() -> Result<(), MoleculeError> {
+        let mut mol = molecule(&["C", "C"], &[])?;
+        mol.atoms_mut()[0].formal_charge = -1;
+        for atom in mol.atoms_mut() {
+            atom.hydrogen_count = Some(0);
+        }
+
+        let largest = mol.largest_fragment(FragmentRule::Mass).unwrap();
+
+        assert_eq!(largest.atoms()[0].formal_charge, -1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn largest_fragment_implicit_hydrogens() -> Result<(), MoleculeError> {
+        // Methane (12.011 + 4 H) outweighs the methyl anion (12.011 + 3 H)
+        let mut mol = molecule(&["C", "C"], &[])?;
+        mol.atoms_mut()[0].formal_charge = -1;
+
+        let largest = mol.largest_fragment(FragmentRule::Mass).unwrap();
+
+        assert_eq!(largest.atoms()[0].formal_charge, 0);
+        assert_eq!(largest.implicit_hydrogen_count(0), 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,833 @@
+use super::{
+    Atom, AtomIndex, AtomProperty, Bond, BondProperty, BondType, Molecule, MoleculeError, Point3d,
+};
+use std::f64::consts::PI;
+
+// Bond length used when an element has no covalent radius
+const DEFAULT_BOND_LENGTH: f64 = 1.0;
+
+/// Options for `add_hydrogens`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct AddHOptions {
+    /// Only add the hydrogens of atoms other than carbon, as used by docking programs that leave
+    /// non-polar hydrogens implicit
+    pub polar_only: bool,
+}
+
+// Arrangement of the bonds and lone pairs around an atom
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Geometry {
+    Linear,
+    TrigonalPlanar,
+    Tetrahedral,
+}
+
+/// Turns the implicit hydrogens of the molecule's atoms into explicit hydrogen atoms, which are
+/// added after the existing atoms with single bonds to their parents.
+///
+/// If the molecule has coordinates, the hydrogens are placed for the geometry of their parent
+/// atom: in 3D (see `Molecule::has_3d_coordinates`) at bond lengths from covalent radii and ideal
+/// bond angles, and in 2D in the largest gap between the parent's bonds, at the drawing's average
+/// bond length. Otherwise they are left at the origin.
+pub fn add_hydrogens(molecule: &mut Molecule, options: AddHOptions) -> Result<(), MoleculeError> {
+    let hydrogen_counts = (0..molecule.atoms().len())
+        .map(|atom| {
+            if options.polar_only && molecule.atoms()[atom].element.atomic_number == 6 {
+                0
+            } else {
+                molecule.implicit_hydrogen_count(atom)
+            }
+        })
+        .collect::<Vec<u32>>();
+
+    let is_3d = molecule.has_3d_coordinates();
+    let is_2d = !is_3d
+        && molecule
+            .atoms()
+            .iter()
+            .any(|atom| atom.position != Point3d::new(0.0, 0.0, 0.0));
+    let average_bond_length = average_bond_length(molecule);
+
+    for (parent, hydrogen_count) in hydrogen_counts.into_iter().enumerate() {
+        if hydrogen_count == 0 {
+            continue;
+        }
+
+        let positions = if is_3d {
+            hydrogen_positions_3d(molecule, parent, hydrogen_count as usize)
+        } else if is_2d {
+            hydrogen_positions_2d(
+                molecule,
+                parent,
+                hydrogen_count as usize,
+                average_bond_length,
+            )
+        } else {
+            vec![Point3d::new(0.0, 0.0, 0.0); hydrogen_count as usize]
+        };
+
+        let first_hydrogen = molecule.atoms().len();
+        for position in positions {
+            let mut hydrogen = Atom::from_atomic_number(1)?;
+            hydrogen.position = position;
+            let hydrogen = molecule.add_atom(hydrogen);
+            molecule.add_bond(Bond::new(parent, hydrogen, BondType::single()))?;
+        }
+        replace_stereo_neighbor(molecule, parent, parent, first_hydrogen);
+
+        // Keep an explicit count only if the valence model would otherwise add hydrogens back
+        molecule.atoms_mut()[parent].hydrogen_count = None;
+        if molecule.implicit_hydrogen_count(parent) != 0 {
+            molecule.atoms_mut()[parent].hydrogen_count = Some(0);
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes explicit hydrogen atoms, adding them to the implicit hydrogen count of their parent.
+///
+/// Hydrogens are kept if they are isotopes, charged or radicals, are not joined to exactly one
+/// non-hydrogen atom by a single bond, or have a bond direction. Hydrogens that set the
+/// configuration of a double bond are also kept, while those of a stereocentre are replaced in
+/// its configuration by the implicit hydrogen.
+pub fn remove_hydrogens(molecule: &mut Molecule) {
+    let mut removed = vec![false; molecule.atoms().len()];
+    let mut hydrogen_counts = (0..molecule.atoms().len())
+        .map(|atom| molecule.implicit_hydrogen_count(atom))
+        .collect::<Vec<u32>>();
+
+    for (hydrogen, removed) in removed.iter_mut().enumerate() {
+        if let Some(parent) = removable_hydrogen_parent(molecule, hydrogen) {
+            *removed = true;
+            hydrogen_counts[parent] += 1;
+            replace_stereo_neighbor(molecule, parent, hydrogen, parent);
+        }
+    }
+
+    let index_map = molecule.retain_atoms(|atom, _| !removed[atom]);
+
+    for (atom, new_index) in index_map.into_iter().enumerate() {
+        let new_index = match new_index {
+            Some(new_index) => new_index,
+            None => continue,
+        };
+
+        // Leave the count to the valence model where it gives the same number of hydrogens
+        molecule.atoms_mut()[new_index].hydrogen_count = None;
+        if molecule.implicit_hydrogen_count(new_index) != hydrogen_counts[atom] {
+            molecule.atoms_mut()[new_index].hydrogen_count = Some(hydrogen_counts[atom]);
+        }
+    }
+}
+
+fn removable_hydrogen_parent(molecule: &Molecule, hydrogen: AtomIndex) -> Option<AtomIndex> {
+    let atom = &molecule.atoms()[hydrogen];
+    if atom.element.atomic_number != 1
+        || atom.isotope.is_some()
+        || atom.formal_charge != 0
+        || atom.radical.electrons() != 0
+        || atom.hydrogen_count.unwrap_or(0) != 0
+        || molecule.degree(hydrogen) != 1
+    {
+        return None;
+    }
+
+    if molecule
+        .stereo()
+        .double_bonds
+        .iter()
+        .any(|stereo| stereo.neighbors.contains(&hydrogen))
+    {
+        return None;
+    }
+
+    let (parent, bond) = molecule.neighbor_bonds(hydrogen).next()?;
+    let bond = &molecule.bonds()[bond];
+    let parent_atom = &molecule.atoms()[parent];
+    if bond.bond_type != BondType::single()
+        || bond.properties.contains_key(&BondProperty::Direction)
+        || parent_atom.element.atomic_number == 1
+        || parent_atom.properties.contains_key(&AtomProperty::AtomList)
+    {
+        return None;
+    }
+
+    Some(parent)
+}
+
+// Replaces a neighbour in the configuration of a stereocentre, where the centre itself stands for
+// its implicit hydrogen
+fn replace_stereo_neighbor(
+    molecule: &mut Molecule,
+    center: AtomIndex,
+    neighbor: AtomIndex,
+    replacement: AtomIndex,
+) {
+    let stereo = molecule.stereo_mut();
+    if let Some(mut stereo_center) = stereo.tetrahedral_at(center).copied() {
+        for stereo_neighbor in stereo_center.neighbors.iter_mut() {
+            if *stereo_neighbor == neighbor {
+                *stereo_neighbor = replacement;
+                stereo.set_tetrahedral(stereo_center);
+                return;
+            }
+        }
+    }
+}
+
+fn geometry(molecule: &Molecule, atom: AtomIndex) -> Geometry {
+    let multiple_bonds = |atom: AtomIndex| {
+        molecule
+            .bonds_of(atom)
+            .filter_map(|bond| match molecule.bonds()[bond].bond_type {
+                BondType::Covalent(order) if order > 1 => Some(order),
+                BondType::Aromatic => Some(2),
+                _ => None,
+            })
+            .collect::<Vec<i32>>()
+    };
+
+    let bonds = multiple_bonds(atom);
+    if bonds.contains(&3) || bonds.len() > 1 && !molecule.atoms()[atom].aromatic {
+        Geometry::Linear
+    } else if !bonds.is_empty() {
+        Geometry::TrigonalPlanar
+    } else if molecule.atoms()[atom].element.atomic_number == 7
+        && molecule
+            .neighbors(atom)
+            .any(|neighbor| !multiple_bonds(neighbor).is_empty())
+    {
+        // Nitrogen conjugated with a multiple bond (as in amides and anilines) is planar
+        Geometry::TrigonalPlanar
+    } else {
+        Geometry::Tetrahedral
+    }
+}
+
+fn bond_length(molecule: &Molecule, atom: AtomIndex) -> f64 {
+    let hydrogen_radius = Atom::from_atomic_number(1)
+        .ok()
+        .and_then(|hydrogen| hydrogen.element.covalent_radius);
+    match (
+        molecule.atoms()[atom].element.covalent_radius,
+        hydrogen_radius,
+    ) {
+        (Some(radius), Some(hydrogen_radius)) => radius + hydrogen_radius,
+        _ => DEFAULT_BOND_LENGTH,
+    }
+}
+
+fn average_bond_length(molecule: &Molecule) -> f64 {
+    let lengths = molecule
+        .bonds()
+        .iter()
+        .map(|bond| {
+            let from = molecule.atoms()[bond.from_atom_id].position;
+            from.distance(molecule.atoms()[bond.to_atom_id].position)
+        })
+        .filter(|length| *length > 0.0)
+        .collect::<Vec<f64>>();
+
+    if lengths.is_empty() {
+        DEFAULT_BOND_LENGTH
+    } else {
+        lengths.iter().sum::<f64>() / lengths.len() as f64
+    }
+}
+
+fn hydrogen_positions_3d(molecule: &Molecule, parent: AtomIndex, count: usize) -> Vec<Point3d> {
+    let center = molecule.atoms()[parent].position;
+    let bond_directions = molecule
+        .neighbors(parent)
+        .filter_map(|neighbor| unit(molecule.atoms()[neighbor].position - center))
+        .collect::<Vec<Point3d>>();
+
+    // A neighbour of the first neighbour fixes the rotation about a single bond, so that
+    // hydrogens are staggered on sp3 atoms and in the plane of a double bond on sp2 atoms
+    let reference = molecule.neighbors(parent).next().and_then(|neighbor| {
+        molecule
+            .neighbors(neighbor)
+            .find(|other| *other != parent)
+            .map(|other| molecule.atoms()[other].position - molecule.atoms()[neighbor].position)
+    });
+
+    let directions = hydrogen_directions(
+        geometry(molecule, parent),
+        &bond_directions,
+        reference,
+        count,
+    );
+    let bond_length = bond_length(molecule, parent);
+    directions
+        .into_iter()
+        .map(|direction| center + direction * bond_length)
+        .collect()
+}
+
+fn hydrogen_directions(
+    geometry: Geometry,
+    bonds: &[Point3d],
+    reference: Option<Point3d>,
+    count: usize,
+) -> Vec<Point3d> {
+    let tetrahedral_angle = (-1.0f64 / 3.0).acos();
+
+    let directions = match (geometry, bonds) {
+        (Geometry::Linear, []) => vec![Point3d::new(1.0, 0.0, 0.0), Point3d::new(-1.0, 0.0, 0.0)],
+        (Geometry::TrigonalPlanar, []) => (0..3)
+            .map(|index| {
+                let angle = 2.0 * PI * index as f64 / 3.0;
+                Point3d::new(angle.cos(), angle.sin(), 0.0)
+            })
+            .collect(),
+        (Geometry::Tetrahedral, []) => {
+            let corner = 1.0 / 3f64.sqrt();
+            vec![
+                Point3d::new(corner, corner, corner),
+                Point3d::new(corner, -corner, -corner),
+                Point3d::new(-corner, corner, -corner),
+                Point3d::new(-corner, -corner, corner),
+            ]
+        }
+        (Geometry::Linear, [bond]) => vec![-*bond],
+        (Geometry::TrigonalPlanar, [bond]) => {
+            let perpendicular = perpendicular(*bond, reference);
+            let angle = 2.0 * PI / 3.0;
+            vec![
+                *bond * angle.cos() + perpendicular * angle.sin(),
+                *bond * angle.cos() - perpendicular * angle.sin(),
+            ]
+        }
+        (Geometry::Tetrahedral, [bond]) => {
+            // Starting opposite the reference gives staggered hydrogens
+            let perpendicular = -perpendicular(*bond, reference);
+            let normal = bond.cross(perpendicular);
+            (0..3)
+                .map(|index| {
+                    let rotation = 2.0 * PI * index as f64 / 3.0;
+                    *bond * tetrahedral_angle.cos()
+                        + (perpendicular * rotation.cos() + normal * rotation.sin())
+                            * tetrahedral_angle.sin()
+                })
+                .collect()
+        }
+        (Geometry::Tetrahedral, [first, second]) => {
+            let bisector = unit(-(*first + *second)).unwrap_or_else(|| perpendicular(*first, None));
+            let normal =
+                unit(first.cross(*second)).unwrap_or_else(|| perpendicular(bisector, None));
+            let angle = tetrahedral_angle / 2.0;
+            vec![
+                bisector * angle.cos() + normal * angle.sin(),
+                bisector * angle.cos() - normal * angle.sin(),
+            ]
+        }
+        // The remaining directions point away from the existing bonds
+        _ => {
+            let sum = bonds
+                .iter()
+                .fold(Point3d::new(0.0, 0.0, 0.0), |sum, bond| sum + *bond);
+            let direction = unit(-sum).unwrap_or_else(|| match bonds {
+                [first, second, ..] => {
+                    unit(first.cross(*second)).unwrap_or_else(|| perpendicular(*first, None))
+                }
+                _ => Point3d::new(1.0, 0.0, 0.0),
+            });
+            vec![direction]
+        }
+    };
+
+    // Hydrogens beyond the ideal directions (on hypervalent atoms) go on a cone about the first
+    // direction, at an angle no other direction makes with it
+    let extra = count.saturating_sub(directions.len());
+    let axis = directions[0];
+    let perpendicular = perpendicular(axis, None);
+    let normal = axis.cross(perpendicular);
+    let cone_angle = PI / 3.0;
+    let extra_directions = (0..extra).map(|index| {
+        let rotation = 2.0 * PI * index as f64 / extra as f64;
+        axis * cone_angle.cos()
+            + (perpendicular * rotation.cos() + normal * rotation.sin()) * cone_angle.sin()
+    });
+
+    directions
+        .into_iter()
+        .chain(extra_directions)
+        .take(count)
+        .collect()
+}
+
+fn hydrogen_positions_2d(
+    molecule: &Molecule,
+    parent: AtomIndex,
+    count: usize,
+    bond_length: f64,
+) -> Vec<Point3d> {
+    let center = molecule.atoms()[parent].position;
+    let mut angles = molecule
+        .neighbors(parent)
+        .map(|neighbor| molecule.atoms()[neighbor].position - center)
+        .filter(|direction| direction.norm() > 0.0)
+        .map(|direction| direction.y.atan2(direction.x))
+        .collect::<Vec<f64>>();
+    angles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let new_angles = match angles.as_slice() {
+        [] => (0..count)
+            .map(|index| 2.0 * PI * index as f64 / count as f64)
+            .collect(),
+        // A single hydrogen goes at 120 degrees (or opposite on linear atoms), others are spread
+        // around the remaining circle
+        [angle] if count == 1 => match geometry(molecule, parent) {
+            Geometry::Linear => vec![angle + PI],
+            _ => vec![angle + 2.0 * PI / 3.0],
+        },
+        _ => {
+            // Spread the hydrogens evenly over the largest gap between the existing bonds
+            let (start, gap) = angles
+                .iter()
+                .enumerate()
+                .map(|(index, angle)| {
+                    let next = angles
+                        .get(index + 1)
+                        .copied()
+                        .unwrap_or(angles[0] + 2.0 * PI);
+                    (*angle, next - angle)
+                })
+                .fold((angles[0], 0.0), |largest, candidate| {
+                    if candidate.1 > largest.1 {
+                        candidate
+                    } else {
+                        largest
+                    }
+                });
+            (1..=count)
+                .map(|index| start + gap * index as f64 / (count + 1) as f64)
+                .collect()
+        }
+    };
+
+    new_angles
+        .into_iter()
+        .map(|angle| center + Point3d::new(angle.cos(), angle.sin(), 0.0) * bond_length)
+        .collect()
+}
+
+fn unit(vector: Point3d) -> Option<Point3d> {
+    let norm = vector.norm();
+    if norm > 1e-6 {
+        Some(vector * (1.0 / norm))
+    } else {
+        None
+    }
+}
+
+// Unit vector perpendicular to `direction`, in the plane of `reference` if it is given
+fn perpendicular(direction: Point3d, reference: Option<Point3d>) -> Point3d {
+    let candidates = [
+        reference,
+        Some(Point3d::new(1.0, 0.0, 0.0)),
+        Some(Point3d::new(0.0, 1.0, 0.0)),
+    ];
+    candidates
+        .iter()
+        .flatten()
+        .find_map(|candidate| unit(*candidate - direction * candidate.dot(direction)))
+        .unwrap_or(Point3d::new(0.0, 0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{read_smiles, write_smiles, SmilesOptions};
+    use crate::mol::{assign_stereo_from_coordinates, HasProperties, MoleculeProperty};
+
+    fn assert_close(left: f64, right: f64) {
+        assert!(
+            (left - right).abs() < 1e-3,
+            "assertion failed: `(left ≈ right)`\n    left: `{:.?}`,\n   right: `{:.?}`",
+            left,
+            right
+        );
+    }
+
+    fn angle(molecule: &Molecule, center: AtomIndex, first: AtomIndex, second: AtomIndex) -> f64 {
+        let center_position = molecule.atoms()[center].position;
+        let first = molecule.atoms()[first].position - center_position;
+        let second = molecule.atoms()[second].position - center_position;
+        (first.dot(second) / (first.norm() * second.norm()))
+            .acos()
+            .to_degrees()
+    }
+
+    fn hydrogens_of(molecule: &Molecule, atom: AtomIndex) -> Vec<AtomIndex> {
+        molecule
+            .neighbors(atom)
+            .filter(|neighbor| molecule.atoms()[*neighbor].element.atomic_number == 1)
+            .collect()
+    }
+
+    fn molecule_3d(
+        symbols: &[&str],
+        positions: &[(f64, f64, f64)],
+        bonds: &[(AtomIndex, AtomIndex, BondType)],
+    ) -> Result<Molecule, MoleculeError> {
+        let mut atoms = Vec::new();
+        for (symbol, (x, y, z)) in symbols.iter().zip(positions) {
+            let mut atom = Atom::from_symbol(symbol)?;
+            atom.position = Point3d::new(*x, *y, *z);
+            atoms.push(atom);
+        }
+        let bonds = bonds
+            .iter()
+            .map(|(from, to, bond_type)| Bond::new(*from, *to, bond_type.clone()))
+            .collect();
+        Ok(Molecule::from_graph(atoms, bonds))
+    }
+
+    #[test]
+    fn add_hydrogens_graph() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("CC(=O)[O-].c1cc[nH]c1")?;
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        assert_eq!(mol.atoms().len(), 9 + 3 + 5);
+        assert_eq!(hydrogens_of(&mol, 0).len(), 3);
+        assert!(hydrogens_of(&mol, 3).is_empty());
+        assert_eq!(hydrogens_of(&mol, 7).len(), 1);
+        for atom in 0..mol.atoms().len() {
+            assert_eq!(mol.implicit_hydrogen_count(atom), 0);
+        }
+        assert_eq!(mol.atoms()[7].hydrogen_count, None);
+        assert_eq!(mol.atoms()[9].position, Point3d::new(0.0, 0.0, 0.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_hydrogens_polar_only() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("NCCO")?;
+
+        add_hydrogens(&mut mol, AddHOptions { polar_only: true })?;
+
+        assert_eq!(hydrogens_of(&mol, 0).len(), 2);
+        assert!(hydrogens_of(&mol, 1).is_empty());
+        assert_eq!(hydrogens_of(&mol, 3).len(), 1);
+        assert_eq!(mol.implicit_hydrogen_count(1), 2);
+        assert_eq!(mol.implicit_hydrogen_count(0), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_hydrogens_tetrahedral() -> Result<(), MoleculeError> {
+        // Ethanol with a staggered methyl group
+        let mut mol = molecule_3d(
+            &["C", "C", "O"],
+            &[(0.0, 0.0, 0.5), (1.54, 0.0, 0.5), (2.05, 1.35, 0.5)],
+            &[(0, 1, BondType::single()), (1, 2, BondType::single())],
+        )?;
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        assert_eq!(mol.atoms().len(), 9);
+        let methyl = hydrogens_of(&mol, 0);
+        assert_eq!(methyl.len(), 3);
+        for hydrogen in &methyl {
+            assert_close(
+                mol.atoms()[0]
+                    .position
+                    .distance(mol.atoms()[*hydrogen].position),
+                1.07,
+            );
+            assert_close(angle(&mol, 0, 1, *hydrogen), 109.471);
+        }
+        assert_close(angle(&mol, 0, methyl[0], methyl[1]), 109.471);
+
+        let methylene = hydrogens_of(&mol, 1);
+        assert_eq!(methylene.len(), 2);
+        assert_close(angle(&mol, 1, methylene[0], methylene[1]), 109.471);
+        assert_close(
+            angle(&mol, 1, 0, methylene[0]),
+            angle(&mol, 1, 2, methylene[0]),
+        );
+
+        let hydroxyl = hydrogens_of(&mol, 2);
+        assert_close(
+            mol.atoms()[2]
+                .position
+                .distance(mol.atoms()[hydroxyl[0]].position),
+            0.97,
+        );
+        assert_close(angle(&mol, 2, 1, hydroxyl[0]), 109.471);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_hydrogens_planar_and_linear() -> Result<(), MoleculeError> {
+        // Acrylonitrile: C=C-C#N, drawn in the xy plane but with a z offset so it counts as 3D
+        let mut mol = molecule_3d(
+            &["C", "C", "C", "N"],
+            &[
+                (0.0, 0.0, 1.0),
+                (1.33, 0.0, 1.0),
+                (2.05, 1.25, 1.0),
+                (2.63, 2.25, 1.0),
+            ],
+            &[
+                (0, 1, BondType::double()),
+                (1, 2, BondType::single()),
+                (2, 3, BondType::triple()),
+            ],
+        )?;
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        let terminal = hydrogens_of(&mol, 0);
+        assert_eq!(terminal.len(), 2);
+        assert_close(angle(&mol, 0, terminal[0], terminal[1]), 120.0);
+        assert_close(angle(&mol, 0, 1, terminal[0]), 120.0);
+        for hydrogen in terminal {
+            assert_close(mol.atoms()[hydrogen].position.z, 1.0);
+        }
+
+        let vinyl = hydrogens_of(&mol, 1);
+        assert_eq!(vinyl.len(), 1);
+        assert_close(angle(&mol, 1, 0, vinyl[0]), angle(&mol, 1, 2, vinyl[0]));
+
+        // An acetylene hydrogen continues the line of the triple bond
+        let mut mol = molecule_3d(
+            &["C", "C"],
+            &[(0.0, 0.0, 0.0), (0.0, 0.0, 1.2)],
+            &[(0, 1, BondType::triple())],
+        )?;
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+        assert_close(angle(&mol, 0, 1, 2), 180.0);
+        assert_close(mol.atoms()[2].position.z, -1.07);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_hydrogens_planar_3d() -> Result<(), Box<dyn std::error::Error>> {
+        // Ethane with both carbons at z = 0, but flagged as 3D as by a Molfile header
+        let mut mol = molecule_3d(
+            &["C", "C"],
+            &[(0.0, 0.0, 0.0), (1.54, 0.0, 0.0)],
+            &[(0, 1, BondType::single())],
+        )?;
+        mol.set_property(MoleculeProperty::Dimensions, 3u32);
+        assert!(mol.has_3d_coordinates());
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        let methyl = hydrogens_of(&mol, 0);
+        assert_eq!(methyl.len(), 3);
+        for hydrogen in &methyl {
+            assert_close(angle(&mol, 0, 1, *hydrogen), 109.471);
+        }
+        assert_close(angle(&mol, 0, methyl[0], methyl[1]), 109.471);
+
+        // Flagged as 2D, the same coordinates take hydrogens in the plane
+        let mut mol = molecule_3d(
+            &["C", "C"],
+            &[(0.0, 0.0, 0.0), (1.54, 0.0, 0.0)],
+            &[(0, 1, BondType::single())],
+        )?;
+        mol.set_property(MoleculeProperty::Dimensions, 2u32);
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+        for hydrogen in 2..mol.atoms().len() {
+            assert_close(mol.atoms()[hydrogen].position.z, 0.0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_hydrogens_hypervalent_3d() -> Result<(), MoleculeError> {
+        // A carbon with three fluorines and three hydrogens has more hydrogens than free
+        // tetrahedral directions, and each still gets its own position
+        let mut mol = molecule_3d(
+            &["C", "F", "F", "F"],
+            &[
+                (0.0, 0.0, 0.0),
+                (0.0, 0.0, 1.35),
+                (1.27, 0.0, -0.45),
+                (-0.64, 1.1, -0.45),
+            ],
+            &[
+                (0, 1, BondType::single()),
+                (0, 2, BondType::single()),
+                (0, 3, BondType::single()),
+            ],
+        )?;
+        mol.atoms_mut()[0].hydrogen_count = Some(3);
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        let hydrogens = hydrogens_of(&mol, 0);
+        assert_eq!(hydrogens.len(), 3);
+        for (index, first) in hydrogens.iter().enumerate() {
+            for second in &hydrogens[index + 1..] {
+                assert!(
+                    mol.atoms()[*first]
+                        .position
+                        .distance(mol.atoms()[*second].position)
+                        > 0.5
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_hydrogens_2d() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = molecule_3d(
+            &["C", "C", "O"],
+            &[(0.0, 0.0, 0.0), (1.5, 0.0, 0.0), (2.25, 1.3, 0.0)],
+            &[(0, 1, BondType::single()), (1, 2, BondType::single())],
+        )?;
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        let expected_length = mol.atoms()[0].position.distance(mol.atoms()[1].position) / 2.0
+            + mol.atoms()[1].position.distance(mol.atoms()[2].position) / 2.0;
+        for hydrogen in 3..mol.atoms().len() {
+            assert_close(mol.atoms()[hydrogen].position.z, 0.0);
+            let parent = mol.neighbors(hydrogen).next().unwrap();
+            assert_close(
+                mol.atoms()[parent]
+                    .position
+                    .distance(mol.atoms()[hydrogen].position),
+                expected_length,
+            );
+        }
+
+        let methyl = hydrogens_of(&mol, 0);
+        assert_close(angle(&mol, 0, 1, methyl[0]), 90.0);
+        assert_close(angle(&mol, 0, 1, methyl[1]), 180.0);
+        assert_close(angle(&mol, 0, 1, methyl[2]), 90.0);
+
+        let methylene = hydrogens_of(&mol, 1);
+        // Spread over the larger gap between the carbon and oxygen
+        assert_close(
+            angle(&mol, 1, methylene[0], methylene[1]),
+            (360.0 - angle(&mol, 1, 0, 2)) / 3.0,
+        );
+
+        let hydroxyl = hydrogens_of(&mol, 2);
+        assert_close(angle(&mol, 2, 1, hydroxyl[0]), 120.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn remove_hydrogens() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("[H]C([H])([H])C([2H])=O.[H][H].[H]N([H])[H]")?;
+
+        super::remove_hydrogens(&mut mol);
+
+        let symbols = mol
+            .atoms()
+            .iter()
+            .map(|atom| atom.element.symbol.as_str())
+            .collect::<String>();
+        assert_eq!(symbols, "CCHOHHN");
+        assert_eq!(mol.atoms()[0].hydrogen_count, None);
+        assert_eq!(mol.implicit_hydrogen_count(0), 3);
+        assert_eq!(mol.implicit_hydrogen_count(1), 0);
+        assert_eq!(mol.bond_between(1, 2), Some(1));
+        assert_eq!(mol.implicit_hydrogen_count(6), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn remove_hydrogens_keeps_stereo() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("[H][C@](F)(Cl)Br.[H]/C(F)=C/F")?;
+        let parity = mol.stereo().tetrahedral[0].parity_for(&[0, 2, 3, 4]);
+
+        super::remove_hydrogens(&mut mol);
+
+        // The stereocentre's hydrogen becomes implicit, while the double bond keeps its own
+        assert_eq!(mol.atoms().len(), 9);
+        assert_eq!(mol.implicit_hydrogen_count(0), 1);
+        assert_eq!(
+            mol.stereo().tetrahedral[0].parity_for(&[0, 1, 2, 3]),
+            parity
+        );
+        assert_eq!(mol.stereo().double_bonds.len(), 1);
+
+        // A SMILES stereocentre keeps its configuration through adding and removing hydrogens
+        let original = read_smiles("C[C@H](N)O")?;
+        let mut mol = original.clone();
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+        super::remove_hydrogens(&mut mol);
+
+        assert_eq!(mol.atoms().len(), 4);
+        assert_eq!(mol.stereo(), original.stereo());
+        assert_eq!(
+            write_smiles(&mol, SmilesOptions::default())?,
+            write_smiles(&original, SmilesOptions::default())?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn hydrogens_keep_stereo_configuration() -> Result<(), MoleculeError> {
+        let mut mol = molecule_3d(
+            &["C", "F", "Cl", "Br"],
+            &[
+                (0.0, 0.0, 0.0),
+                (0.0, 1.0, 0.3),
+                (-0.87, -0.5, 0.3),
+                (0.87, -0.5, 0.3),
+            ],
+            &[
+                (0, 1, BondType::single()),
+                (0, 2, BondType::single()),
+                (0, 3, BondType::single()),
+            ],
+        )?;
+        assign_stereo_from_coordinates(&mut mol);
+        let parity = mol.stereo().tetrahedral[0].parity;
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+
+        let stereo = mol.stereo().tetrahedral[0];
+        assert_eq!(stereo.neighbors, [1, 2, 3, 4]);
+        assert_eq!(stereo.parity, parity);
+        let mut from_coordinates = mol.clone();
+        assign_stereo_from_coordinates(&mut from_coordinates);
+        assert_eq!(from_coordinates.stereo(), mol.stereo());
+
+        super::remove_hydrogens(&mut mol);
+
+        assert_eq!(mol.atoms().len(), 4);
+        assert_eq!(mol.stereo().tetrahedral[0].neighbors, [1, 2, 3, 0]);
+        assert_eq!(mol.stereo().tetrahedral[0].parity, parity);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_and_remove_hydrogens_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("c1ccc2[nH]ccc2c1C(=O)N")?;
+        let hydrogen_counts = (0..mol.atoms().len())
+            .map(|atom| mol.implicit_hydrogen_count(atom))
+            .collect::<Vec<u32>>();
+
+        add_hydrogens(&mut mol, AddHOptions::default())?;
+        super::remove_hydrogens(&mut mol);
+
+        assert_eq!(mol.atoms().len(), hydrogen_counts.len());
+        for (atom, hydrogen_count) in hydrogen_counts.into_iter().enumerate() {
+            assert_eq!(mol.implicit_hydrogen_count(atom), hydrogen_count);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use super::{Element, MoleculeError};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+lazy_static! {
+    static ref ISOTOPES_BY_NUMBER: HashMap<u32, Vec<Isotope>> = {
+        let isotopes_config = include_bytes!("../../data_files/isotopes.csv");
+        let mut config_reader = csv::Reader::from_reader(&isotopes_config[..]);
+
+        let mut map: HashMap<u32, Vec<Isotope>> = HashMap::new();
+
+        for isotope in config_reader.deserialize() {
+            let isotope: Isotope =
+                isotope.expect("Unable to deserialize embedded isotopes.csv configuration file.");
+            map.entry(isotope.atomic_number).or_default().push(isotope);
+        }
+
+        map
+    };
+}
+
+#[derive(Deserialize)]
+pub struct Isotope {
+    pub atomic_number: u32,
+    pub mass_number: u32,
+    /// Exact mass in unified atomic mass units.
+    pub exact_mass: f64,
+    /// Natural abundance as a fraction, or zero for isotopes that do not occur naturally.
+    pub abundance: f64,
+}
+
+impl Isotope {
+    pub fn lookup(element: &Element, mass_number: u32) -> Result<&'static Isotope, MoleculeError> {
+        match isotopes_of(element.atomic_number)
+            .iter()
+            .find(|isotope| isotope.mass_number == mass_number)
+        {
+            Some(isotope) => Ok(isotope),
+            None => Err(MoleculeError::UnknownIsotope {
+                symbol: element.symbol.clone(),
+                mass_number,
+            }),
+        }
+    }
+
+    pub fn element(&self) -> &'static Element {
+        Element::from_atomic_number(self.atomic_number)
+            .expect("Embedded isotopes.csv refers to an unknown element.")
+    }
+}
+
+pub(super) fn isotopes_of(atomic_number: u32) -> &'static [Isotope] {
+    match ISOTOPES_BY_NUMBER.get(&atomic_number) {
+        Some(isotopes) => isotopes,
+        None => &[],
+    }
+}
+
+impl PartialEq for Isotope {
+    fn eq(&self, other: &Self) -> bool {
+        self.atomic_number == other.atomic_number && self.mass_number == other.mass_number
+    }
+}
+
+impl fmt::Debug for Isotope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.mass_number, self.element().symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::assert_f64_eq;
+
+    #[test]
+    fn lookup() -> Result<(), MoleculeError> {
+        let element_c = Element::from_symbol("C")?;
+        let isotope_c13 = Isotope::lookup(element_c, 13)?;
+
+        assert_eq!(isotope_c13.atomic_number, 6);
+        assert_eq!(isotope_c13.mass_number, 13);
+        assert_f64_eq(isotope_c13.exact_mass, 13.00335483507);
+        assert_f64_eq(isotope_c13.abundance, 0.0107);
+        assert_eq!(isotope_c13.element(), element_c);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_radioactive() -> Result<(), MoleculeError> {
+        let isotope_c14 = Isotope::lookup(Element::from_symbol("C")?, 14)?;
+        let isotope_tc99 = Isotope::lookup(Element::from_symbol("Tc")?, 99)?;
+
+        assert_f64_eq(isotope_c14.abundance, 0.0);
+        assert_f64_eq(isotope_tc99.abundance, 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_error_unknown() -> Result<(), MoleculeError> {
+        match Isotope::lookup(Element::from_symbol("C")?, 20) {
+            Err(MoleculeError::UnknownIsotope {
+                symbol,
+                mass_number,
+            }) => {
+                assert_eq!(symbol, "C");
+                assert_eq!(mass_number, 20);
+            }
+            _ => panic!("Expected MoleculeError::UnknownIsotope"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn isotopes_of_element() -> Result<(), MoleculeError> {
+        let mass_numbers = Element::from_symbol("C")?
+            .isotopes()
+            .iter()
+            .map(|isotope| isotope.mass_number)
+            .collect::<Vec<u32>>();
+
+        assert_eq!(mass_numbers, vec![11, 12, 13, 14]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn isotopes_of_all_elements() -> Result<(), MoleculeError> {
+        for atomic_number in 1..=118 {
+            let element = Element::from_atomic_number(atomic_number)?;
+            let isotopes = element.isotopes();
+            let total_abundance: f64 = isotopes.iter().map(|isotope| isotope.abundance).sum();
+
+            assert!(!isotopes.is_empty());
+            assert!(total_abundance == 0.0 || (total_abundance - 1.0).abs() < 1e-3);
+            assert!(Isotope::lookup(element, element.most_common_isotope).is_ok());
+        }
+
+        Ok(())
+    }
+}
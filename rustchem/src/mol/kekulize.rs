@@ -0,0 +1,353 @@
+use super::{AtomIndex, BondIndex, BondType, Molecule, MoleculeError};
+use std::collections::VecDeque;
+
+/// Replaces aromatic bonds with alternating single and double bonds.
+///
+/// Every atom with an aromatic bond gets one double bond if its valence leaves room for it, so
+/// pyrrole-type nitrogens (`[nH]`, or `n` with three neighbours), furan-type oxygens and atoms
+/// such as `[cH-]` keep only single bonds, while `[n+]` and `[o+]` get a double bond. Valences
+/// come from `allowed_valences`, and atoms without an explicit hydrogen count are taken to have
+/// no hydrogens beyond those left over once their double bond is placed.
+///
+/// Aromatic flags on atoms and bonds are kept. If no Kekulé structure exists, the molecule is
+/// left unchanged and the error gives the atoms that could not be given a double bond.
+pub fn kekulize(molecule: &mut Molecule) -> Result<(), MoleculeError> {
+    let num_atoms = molecule.atoms().len();
+    let needs_double_bond = (0..num_atoms)
+        .map(|atom| needs_double_bond(molecule, atom))
+        .collect::<Vec<bool>>();
+
+    let mut graph = vec![Vec::new(); num_atoms];
+    for (index, bond) in molecule.bonds().iter().enumerate() {
+        let (from, to) = (bond.from_atom_id, bond.to_atom_id);
+        if bond.bond_type == BondType::Aromatic
+            && from < num_atoms
+            && to < num_atoms
+            && needs_double_bond[from]
+            && needs_double_bond[to]
+        {
+            graph[from].push((to, index));
+            graph[to].push((from, index));
+        }
+    }
+
+    let mates = maximum_matching(&graph);
+    let unmatched = (0..num_atoms)
+        .filter(|atom| needs_double_bond[*atom] && mates[*atom].is_none())
+        .collect::<Vec<AtomIndex>>();
+    if !unmatched.is_empty() {
+        return Err(MoleculeError::NoKekuleStructure { atoms: unmatched });
+    }
+
+    let mut double_bonds = vec![false; molecule.bonds().len()];
+    for atom in 0..num_atoms {
+        if let Some(mate) = mates[atom] {
+            let (_, bond) = graph[atom]
+                .iter()
+                .find(|(neighbor, _)| *neighbor == mate)
+                .unwrap();
+            double_bonds[*bond] = true;
+        }
+    }
+    for (bond, is_double) in molecule.bonds_mut().iter_mut().zip(double_bonds) {
+        if bond.bond_type == BondType::Aromatic {
+            bond.bond_type = if is_double {
+                BondType::double()
+            } else {
+                BondType::single()
+            };
+        }
+    }
+
+    Ok(())
+}
+
+// Whether an atom with aromatic bonds has a free valence for a double bond
+fn needs_double_bond(molecule: &Molecule, atom: AtomIndex) -> bool {
+    let has_aromatic_bond = molecule
+        .bonds_of(atom)
+        .any(|bond| molecule.bonds()[bond].bond_type == BondType::Aromatic);
+    has_aromatic_bond && molecule.has_room_for_double_bond(atom)
+}
+
+/// Finds a maximum matching in a general graph with Edmonds' blossom algorithm, returning each
+/// vertex's mate.
+fn maximum_matching(graph: &[Vec<(AtomIndex, BondIndex)>]) -> Vec<Option<AtomIndex>> {
+    let mut mates = vec![None; graph.len()];
+
+    // A greedy matching first leaves few vertices to search augmenting paths from
+    for vertex in 0..graph.len() {
+        if mates[vertex].is_none() {
+            if let Some((neighbor, _)) = graph[vertex]
+                .iter()
+                .find(|(other, _)| mates[*other].is_none())
+            {
+                mates[vertex] = Some(*neighbor);
+                mates[*neighbor] = Some(vertex);
+            }
+        }
+    }
+
+    for root in 0..graph.len() {
+        if mates[root].is_none() && !graph[root].is_empty() {
+            BlossomSearch::new(graph).augment_from(root, &mut mates);
+        }
+    }
+
+    mates
+}
+
+/// State of one search for an augmenting path, in which vertices in odd cycles (blossoms) are
+/// contracted onto their base.
+struct BlossomSearch<'a> {
+    graph: &'a [Vec<(AtomIndex, BondIndex)>],
+    parents: Vec<Option<usize>>,
+    bases: Vec<usize>,
+    visited: Vec<bool>,
+    queue: VecDeque<usize>,
+}
+
+impl<'a> BlossomSearch<'a> {
+    fn new(graph: &'a [Vec<(AtomIndex, BondIndex)>]) -> Self {
+        BlossomSearch {
+            graph,
+            parents: vec![None; graph.len()],
+            bases: (0..graph.len()).collect(),
+            visited: vec![false; graph.len()],
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn augment_from(&mut self, root: usize, mates: &mut [Option<usize>]) {
+        self.visited[root] = true;
+        self.queue.push_back(root);
+
+        while let Some(vertex) = self.queue.pop_front() {
+            for &(neighbor, _) in &self.graph[vertex] {
+                if self.bases[vertex] == self.bases[neighbor] || mates[vertex] == Some(neighbor) {
+                    continue;
+                }
+
+                if neighbor == root || mates[neighbor].is_some_and(|m| self.parents[m].is_some()) {
+                    // An odd cycle: contract it into a blossom
+                    let base = self.lowest_common_ancestor(vertex, neighbor, mates);
+                    let mut in_blossom = vec![false; self.graph.len()];
+                    self.mark_path(vertex, base, neighbor, mates, &mut in_blossom);
+                    self.mark_path(neighbor, base, vertex, mates, &mut in_blossom);
+                    for other in 0..self.graph.len() {
+                        if in_blossom[self.bases[other]] {
+                            self.bases[other] = base;
+                            if !self.visited[other] {
+                                self.visited[other] = true;
+                                self.queue.push_back(other);
+                            }
+                        }
+                    }
+                } else if self.parents[neighbor].is_none() {
+                    self.parents[neighbor] = Some(vertex);
+                    match mates[neighbor] {
+                        Some(mate) => {
+                            self.visited[mate] = true;
+                            self.queue.push_back(mate);
+                        }
+                        None => {
+                            // An augmenting path: flip the matching along it
+                            let mut current = Some(neighbor);
+                            while let Some(vertex) = current {
+                                let parent = self.parents[vertex].unwrap();
+                                let next = mates[parent];
+                                mates[vertex] = Some(parent);
+                                mates[parent] = Some(vertex);
+                                current = next;
+                            }
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn lowest_common_ancestor(&self, a: usize, b: usize, mates: &[Option<usize>]) -> usize {
+        let mut on_path = vec![false; self.graph.len()];
+        let mut a = a;
+        loop {
+            a = self.bases[a];
+            on_path[a] = true;
+            match mates[a] {
+                Some(mate) => a = self.parents[mate].unwrap(),
+                None => break,
+            }
+        }
+        let mut b = b;
+        loop {
+            b = self.bases[b];
+            if on_path[b] {
+                return b;
+            }
+            b = self.parents[mates[b].unwrap()].unwrap();
+        }
+    }
+
+    fn mark_path(
+        &mut self,
+        mut vertex: usize,
+        base: usize,
+        mut child: usize,
+        mates: &[Option<usize>],
+        in_blossom: &mut [bool],
+    ) {
+        while self.bases[vertex] != base {
+            let mate = mates[vertex].unwrap();
+            in_blossom[self.bases[vertex]] = true;
+            in_blossom[self.bases[mate]] = true;
+            self.parents[vertex] = Some(child);
+            child = mate;
+            vertex = self.parents[mate].unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read_smiles;
+
+    fn double_bond_counts(molecule: &Molecule) -> Vec<usize> {
+        (0..molecule.atoms().len())
+            .map(|atom| {
+                molecule
+                    .bonds_of(atom)
+                    .filter(|bond| molecule.bonds()[*bond].bond_type == BondType::double())
+                    .count()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn kekulize_benzene() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("c1ccccc1")?;
+
+        kekulize(&mut mol)?;
+
+        assert_eq!(double_bond_counts(&mol), vec![1; 6]);
+        assert!(mol
+            .bonds()
+            .iter()
+            .all(|bond| bond.bond_type != BondType::Aromatic));
+        assert!(mol.atoms().iter().all(|atom| atom.aromatic));
+        assert!(mol.bonds().iter().all(|bond| bond.aromatic));
+
+        Ok(())
+    }
+
+    #[test]
+    fn kekulize_heteroatoms() -> Result<(), Box<dyn std::error::Error>> {
+        // Pyrrole, N-methylpyrrole, furan, thiophene and pyridine
+        let mut mol = read_smiles("c1cc[nH]c1.c1ccn(C)c1.c1ccoc1.c1ccsc1.c1ccncc1")?;
+
+        kekulize(&mut mol)?;
+
+        assert_eq!(
+            double_bond_counts(&mol),
+            [
+                vec![1, 1, 1, 0, 1],
+                vec![1, 1, 1, 0, 0, 1],
+                vec![1, 1, 1, 0, 1],
+                vec![1, 1, 1, 0, 1],
+                vec![1; 6],
+            ]
+            .concat()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn kekulize_charged_atoms() -> Result<(), Box<dyn std::error::Error>> {
+        // N-methylpyridinium, pyridinium, pyrylium, cyclopentadienyl anion, tropylium and the
+        // pyrrolide anion
+        let mut mol = read_smiles(
+            "c1cc[n+](C)cc1.c1cc[nH+]cc1.c1cc[o+]cc1.c1cc[cH-]c1.c1cc[cH+]ccc1.c1cc[n-]c1",
+        )?;
+
+        kekulize(&mut mol)?;
+
+        assert_eq!(
+            double_bond_counts(&mol),
+            [
+                vec![1, 1, 1, 1, 0, 1, 1],
+                vec![1; 6],
+                vec![1; 6],
+                vec![1, 1, 1, 0, 1],
+                vec![1, 1, 1, 0, 1, 1, 1],
+                vec![1, 1, 1, 0, 1],
+            ]
+            .concat()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn kekulize_fused_rings() -> Result<(), Box<dyn std::error::Error>> {
+        // Pyrene, azulene, indole, purine and a 2-pyridone
+        let mut mol = read_smiles(
+            "c1cc2ccc3cccc4ccc(c1)c2c34.c1cc2cccccc2c1.c1ccc2[nH]ccc2c1.c1ncc2[nH]cnc2n1.O=c1cccc[nH]1",
+        )?;
+
+        kekulize(&mut mol)?;
+
+        let counts = double_bond_counts(&mol);
+        assert_eq!(counts[..16], vec![1; 16][..]);
+        assert_eq!(counts[16..26], vec![1; 10][..]);
+        assert_eq!(counts[26..35], [1, 1, 1, 1, 0, 1, 1, 1, 1]);
+        assert_eq!(counts[35..44], [1, 1, 1, 1, 0, 1, 1, 1, 1]);
+        assert_eq!(counts[44..], [1, 1, 1, 1, 1, 1, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn kekulize_fullerene() -> Result<(), Box<dyn std::error::Error>> {
+        // The five-membered rings make the search contract blossoms
+        let mut mol = read_smiles(
+            "C12=C3C4=C5C6=C1C7=C8C9=C1C%10=C%11C(=C29)C3=C2C3=C4C4=C5C5=C9C6=C7C6=C7C8=C1C1=C8\
+             C%10=C%10C%11=C2C2=C3C3=C4C4=C5C5=C%11C%12=C(C6=C95)C7=C1C1=C%12C5=C%11C4=C3C3=C5\
+             C(=C81)C%10=C23",
+        )?;
+        for bond in mol.bonds_mut() {
+            bond.bond_type = BondType::Aromatic;
+        }
+
+        kekulize(&mut mol)?;
+
+        assert_eq!(double_bond_counts(&mol), vec![1; 60]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn kekulize_error() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("c1ccccc1.c1cccc1")?;
+
+        match kekulize(&mut mol) {
+            Err(MoleculeError::NoKekuleStructure { atoms }) => {
+                assert_eq!(atoms.len(), 1);
+                assert!(atoms[0] >= 6);
+            }
+            _ => panic!("Expected MoleculeError::NoKekuleStructure"),
+        }
+        assert!(mol
+            .bonds()
+            .iter()
+            .all(|bond| bond.bond_type == BondType::Aromatic));
+
+        // A pyrrole nitrogen written without its hydrogen
+        let mut mol = read_smiles("c1ccnc1")?;
+
+        assert!(kekulize(&mut mol).is_err());
+
+        Ok(())
+    }
+}
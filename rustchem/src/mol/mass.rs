@@ -0,0 +1,323 @@
+use super::{Element, Isotope, MolecularFormula, Molecule, MoleculeError};
+
+/// Mass of an electron in unified atomic mass units.
+pub const ELECTRON_MASS: f64 = 0.000548579909065;
+
+// Peaks less abundant than this fraction of the largest peak are dropped while the isotope
+// distribution is built up, which keeps the number of peaks small for large molecules
+const PRUNE_THRESHOLD: f64 = 1e-10;
+
+/// A peak of an isotope distribution.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct IsotopePeak {
+    /// Mass in unified atomic mass units.
+    pub mass: f64,
+    /// Fraction of molecules with this mass.
+    pub abundance: f64,
+}
+
+impl MolecularFormula {
+    /// Returns the mass of the formula from the standard atomic weights of its elements.
+    ///
+    /// Atoms labelled with an isotope take the exact mass of the isotope, and ions lose or gain
+    /// the mass of the electrons given by their charge.
+    pub fn average_mass(&self) -> Result<f64, MoleculeError> {
+        self.mass(|element| element.average_mass)
+    }
+
+    /// Returns the mass of the formula with each element as its most abundant isotope.
+    ///
+    /// Isotope labels and charge are taken into account as in `average_mass`.
+    pub fn monoisotopic_mass(&self) -> Result<f64, MoleculeError> {
+        self.mass(|element| element.monoisotopic_mass)
+    }
+
+    /// Returns the peaks of the isotope distribution of the formula, in order of mass.
+    ///
+    /// Peaks within `resolution` of each other are merged into one at their average mass, so a
+    /// resolution of around 0.5 gives one peak per nominal mass, while a small resolution such as
+    /// 0.001 keeps the fine structure. Abundances add up to one, leaving out peaks too small to
+    /// matter. Unlabelled atoms take the natural abundances of their element's isotopes (or the
+    /// most common isotope for elements with none), while labelled atoms are fully enriched.
+    ///
+    /// Fails if an element has a negative count, as can happen after subtracting formulas.
+    pub fn isotope_distribution(&self, resolution: f64) -> Result<Vec<IsotopePeak>, MoleculeError> {
+        let mut distribution = vec![IsotopePeak {
+            mass: -self.charge() as f64 * ELECTRON_MASS,
+            abundance: 1.0,
+        }];
+
+        for (element, isotope, count) in self.counts() {
+            if count < 0 {
+                return Err(MoleculeError::NegativeCount(element.symbol.clone()));
+            }
+            let atom_distribution = match isotope {
+                Some(mass_number) => vec![IsotopePeak {
+                    mass: Isotope::lookup(element, mass_number)?.exact_mass,
+                    abundance: 1.0,
+                }],
+                None => natural_distribution(element),
+            };
+            let element_distribution = power(&atom_distribution, count as u32, resolution);
+            distribution = convolve(&distribution, &element_distribution, resolution);
+        }
+
+        let total: f64 = distribution.iter().map(|peak| peak.abundance).sum();
+        for peak in &mut distribution {
+            peak.abundance /= total;
+        }
+        Ok(distribution)
+    }
+
+    fn mass(&self, element_mass: impl Fn(&Element) -> f64) -> Result<f64, MoleculeError> {
+        let mut mass = -self.charge() as f64 * ELECTRON_MASS;
+        for (element, isotope, count) in self.counts() {
+            let atom_mass = match isotope {
+                Some(mass_number) => Isotope::lookup(element, mass_number)?.exact_mass,
+                None => element_mass(element),
+            };
+            mass += atom_mass * count as f64;
+        }
+        Ok(mass)
+    }
+}
+
+impl Molecule {
+    /// Returns the average mass of the molecule's formula, including implicit hydrogens.
+    pub fn average_mass(&self) -> Result<f64, MoleculeError> {
+        MolecularFormula::from_molecule(self).average_mass()
+    }
+
+    /// Returns the monoisotopic mass of the molecule's formula, including implicit hydrogens.
+    pub fn monoisotopic_mass(&self) -> Result<f64, MoleculeError> {
+        MolecularFormula::from_molecule(self).monoisotopic_mass()
+    }
+
+    /// Returns the isotope distribution of the molecule's formula, as given by
+    /// `MolecularFormula::isotope_distribution`.
+    pub fn isotope_distribution(&self, resolution: f64) -> Result<Vec<IsotopePeak>, MoleculeError> {
+        MolecularFormula::from_molecule(self).isotope_distribution(resolution)
+    }
+}
+
+fn natural_distribution(element: &Element) -> Vec<IsotopePeak> {
+    let peaks = element
+        .isotopes()
+        .iter()
+        .filter(|isotope| isotope.abundance > 0.0)
+        .map(|isotope| IsotopePeak {
+            mass: isotope.exact_mass,
+            abundance: isotope.abundance,
+        })
+        .collect::<Vec<IsotopePeak>>();
+
+    if peaks.is_empty() {
+        vec![IsotopePeak {
+            mass: element.monoisotopic_mass,
+            abundance: 1.0,
+        }]
+    } else {
+        peaks
+    }
+}
+
+// Distribution of `count` atoms, by repeated squaring
+fn power(peaks: &[IsotopePeak], mut count: u32, resolution: f64) -> Vec<IsotopePeak> {
+    let mut result = vec![IsotopePeak {
+        mass: 0.0,
+        abundance: 1.0,
+    }];
+    let mut base = peaks.to_vec();
+
+    while count > 0 {
+        if count & 1 == 1 {
+            result = convolve(&result, &base, resolution);
+        }
+        count >>= 1;
+        if count > 0 {
+            base = convolve(&base, &base, resolution);
+        }
+    }
+
+    result
+}
+
+fn convolve(first: &[IsotopePeak], second: &[IsotopePeak], resolution: f64) -> Vec<IsotopePeak> {
+    let mut peaks = first
+        .iter()
+        .flat_map(|a| {
+            second.iter().map(move |b| IsotopePeak {
+                mass: a.mass + b.mass,
+                abundance: a.abundance * b.abundance,
+            })
+        })
+        .collect::<Vec<IsotopePeak>>();
+    peaks.sort_by(|a, b| a.mass.total_cmp(&b.mass));
+
+    let mut merged: Vec<IsotopePeak> = Vec::with_capacity(peaks.len());
+    for peak in peaks {
+        match merged.last_mut() {
+            Some(last) if peak.mass - last.mass <= resolution => {
+                let abundance = last.abundance + peak.abundance;
+                if abundance > 0.0 {
+                    last.mass =
+                        (last.mass * last.abundance + peak.mass * peak.abundance) / abundance;
+                }
+                last.abundance = abundance;
+            }
+            _ => merged.push(peak),
+        }
+    }
+
+    let largest = merged.iter().map(|peak| peak.abundance).fold(0.0, f64::max);
+    merged.retain(|peak| peak.abundance >= largest * PRUNE_THRESHOLD);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read_smiles;
+
+    fn assert_close(left: f64, right: f64, tolerance: f64) {
+        assert!(
+            (left - right).abs() < tolerance,
+            "assertion failed: `(left ≈ right)`\n    left: `{:.?}`,\n   right: `{:.?}`",
+            left,
+            right
+        );
+    }
+
+    fn formula(formula: &str) -> MolecularFormula {
+        formula.parse().unwrap()
+    }
+
+    #[test]
+    fn masses() -> Result<(), MoleculeError> {
+        let glucose = formula("C6H12O6");
+
+        assert_close(glucose.average_mass()?, 180.156, 1e-9);
+        assert_close(glucose.monoisotopic_mass()?, 180.06338810418, 1e-9);
+
+        let methane = formula("[13C]H3[2H]");
+        assert_close(
+            methane.monoisotopic_mass()?,
+            13.00335483507 + 3.0 * 1.00782503223 + 2.01410177812,
+            1e-9,
+        );
+        assert_close(
+            methane.average_mass()?,
+            13.00335483507 + 3.0 * 1.008 + 2.01410177812,
+            1e-9,
+        );
+
+        let ammonium = formula("H4N+");
+        assert_close(
+            ammonium.monoisotopic_mass()?,
+            14.00307400443 + 4.0 * 1.00782503223 - ELECTRON_MASS,
+            1e-9,
+        );
+        assert_close(
+            formula("O4S-2").monoisotopic_mass()?,
+            31.9720711744 + 4.0 * 15.99491461957 + 2.0 * ELECTRON_MASS,
+            1e-9,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn molecule_masses() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("CC(=O)[O-]")?;
+
+        assert_close(
+            mol.monoisotopic_mass()?,
+            formula("C2H3O2-").monoisotopic_mass()?,
+            1e-9,
+        );
+        assert_close(
+            mol.average_mass()?,
+            formula("C2H3O2-").average_mass()?,
+            1e-9,
+        );
+        assert_eq!(
+            mol.isotope_distribution(0.5)?,
+            formula("C2H3O2-").isotope_distribution(0.5)?
+        );
+
+        let mut mol = read_smiles("C")?;
+        mol.atoms_mut()[0].isotope = Some(40);
+        assert!(matches!(
+            mol.monoisotopic_mass(),
+            Err(MoleculeError::UnknownIsotope { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn isotope_distribution() -> Result<(), MoleculeError> {
+        let peaks = formula("C2").isotope_distribution(0.001)?;
+
+        assert_eq!(peaks.len(), 3);
+        assert_close(peaks[0].mass, 24.0, 1e-9);
+        assert_close(peaks[0].abundance, 0.9893 * 0.9893, 1e-12);
+        assert_close(peaks[1].mass, 25.00335483507, 1e-9);
+        assert_close(peaks[1].abundance, 2.0 * 0.9893 * 0.0107, 1e-12);
+        assert_close(peaks[2].mass, 26.00670967014, 1e-9);
+        assert_close(peaks[2].abundance, 0.0107 * 0.0107, 1e-12);
+
+        // Labelled atoms only shift the distribution
+        let labelled = formula("C[13C]").isotope_distribution(0.001)?;
+        assert_eq!(labelled.len(), 2);
+        assert_close(labelled[0].mass, 25.00335483507, 1e-9);
+        assert_close(labelled[0].abundance, 0.9893, 1e-12);
+
+        // 13C and 2H differ by under 0.003 Da, so only separate at high resolution
+        let nominal = formula("CH4").isotope_distribution(0.5)?;
+        let fine = formula("CH4").isotope_distribution(0.001)?;
+        assert!(fine.len() > nominal.len());
+        assert_close(
+            nominal[1].abundance,
+            fine[1].abundance + fine[2].abundance,
+            1e-12,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn isotope_distribution_peptide() -> Result<(), MoleculeError> {
+        // Around 5 kDa, where the monoisotopic peak is no longer the largest
+        let peptide = formula("C220H350N60O70S2");
+        let peaks = peptide.isotope_distribution(0.5)?;
+
+        let total: f64 = peaks.iter().map(|peak| peak.abundance).sum();
+        assert_close(total, 1.0, 1e-12);
+        assert_close(peaks[0].mass, peptide.monoisotopic_mass()?, 1e-6);
+        for pair in peaks.windows(2) {
+            assert_close(pair[1].mass - pair[0].mass, 1.0, 0.01);
+        }
+
+        let largest = (0..peaks.len())
+            .max_by(|a, b| peaks[*a].abundance.total_cmp(&peaks[*b].abundance))
+            .unwrap();
+        assert_eq!(largest, 2);
+
+        // The mean of the distribution is the average mass
+        let mean: f64 = peaks.iter().map(|peak| peak.mass * peak.abundance).sum();
+        assert_close(mean, peptide.average_mass()?, 0.1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn isotope_distribution_negative_count() {
+        let loss = formula("H2") - formula("H2O");
+
+        assert!(matches!(
+            loss.isotope_distribution(0.5),
+            Err(MoleculeError::NegativeCount(symbol)) if symbol == "O"
+        ));
+    }
+}
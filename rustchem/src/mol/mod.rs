@@ -1,15 +1,45 @@
+mod adjacency;
+mod aromaticity;
 mod atom;
 mod bond;
+mod canonical;
+mod cip;
 mod element;
 mod errors;
+mod formula;
+mod fragments;
+mod hydrogens;
+mod isotope;
+mod kekulize;
+mod mass;
 mod molecule;
 mod point3d;
 mod property_map;
+mod rings;
+mod stereo;
+mod valence;
+mod validation;
 
-pub use atom::{Atom, AtomIndex};
-pub use bond::{Bond, BondType};
+pub use aromaticity::{aromatize, dearomatize, perceive_aromaticity, AromaticityModel};
+pub use atom::{Atom, AtomIndex, AtomList, AtomProperty, Radical};
+pub use bond::{Bond, BondIndex, BondProperty, BondStereo, BondType};
+pub use canonical::{canonical_ranks, symmetry_classes};
+pub use cip::{assign_cip_labels, CipLabel};
 pub use element::Element;
 pub use errors::{MoleculeError, PropertyError};
+pub use formula::MolecularFormula;
+pub use fragments::FragmentRule;
+pub use hydrogens::{add_hydrogens, remove_hydrogens, AddHOptions};
+pub use isotope::Isotope;
+pub use kekulize::kekulize;
+pub use mass::{IsotopePeak, ELECTRON_MASS};
 pub use molecule::{Molecule, MoleculeProperty};
 pub use point3d::Point3d;
-pub use property_map::{HasProperties, PropertyMap};
+pub use property_map::{HasProperties, PropertyMap, PropertyValue};
+pub use rings::{Ring, RingInfo};
+pub use stereo::{
+    assign_stereo_from_coordinates, assign_stereo_from_parity, DoubleBondConfiguration,
+    DoubleBondStereo, Stereo, StereoGroup, StereoGroupKind, TetrahedralParity, TetrahedralStereo,
+};
+pub use valence::allowed_valences;
+pub use validation::{validate, Severity, ValidationCode, ValidationIssue};
@@ -1,36 +1,301 @@
-use super::{Atom, Bond, HasProperties, PropertyMap};
+use super::adjacency::Adjacency;
+use super::rings::RingInfo;
+use super::{Atom, AtomIndex, Bond, BondIndex, HasProperties, MoleculeError, PropertyMap, Stereo};
+use std::cell::OnceCell;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Molecule {
-    pub atoms: Vec<Atom>,
-    pub bonds: Vec<Bond>,
+    atoms: Vec<Atom>,
+    bonds: Vec<Bond>,
     pub properties: PropertyMap<MoleculeProperty>,
+    stereo: Stereo,
+    // Built on first use, and dropped whenever the bonds may have changed
+    adjacency: OnceCell<Adjacency>,
+    rings: OnceCell<RingInfo>,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub enum MoleculeProperty {
     Comment,
     CreationUser,
     CreationProgram,
     CreationDate,
     Name,
+    /// Dimensions of the coordinates (2 or 3), from the Molfile header's dimensional code
+    Dimensions,
+    /// Integer scaling factor from the Molfile header
+    ScalingFactorInt,
+    /// Floating point scaling factor from the Molfile header
+    ScalingFactorFloat,
+    /// Energy from the modelling program that wrote the Molfile
+    Energy,
+    /// Internal registry number from the Molfile header
+    RegistryNumber,
+    /// A named data item, such as those attached to SD file records
+    Data(String),
 }
 
 impl Molecule {
     pub fn new() -> Molecule {
-        Molecule {
-            atoms: Vec::new(),
-            bonds: Vec::new(),
-            properties: PropertyMap::new(),
-        }
+        Molecule::from_graph(Vec::new(), Vec::new())
     }
 
+    /// Creates a molecule from its atoms and bonds as they are, without checking the bonds.
+    ///
+    /// Use `add_bond` to build up a molecule whose bonds are known to be valid.
     pub fn from_graph(atoms: Vec<Atom>, bonds: Vec<Bond>) -> Molecule {
         Molecule {
             atoms,
             bonds,
             properties: PropertyMap::new(),
+            stereo: Stereo::default(),
+            adjacency: OnceCell::new(),
+            rings: OnceCell::new(),
+        }
+    }
+
+    pub fn atoms(&self) -> &[Atom] {
+        &self.atoms
+    }
+
+    pub fn atoms_mut(&mut self) -> &mut [Atom] {
+        &mut self.atoms
+    }
+
+    pub fn bonds(&self) -> &[Bond] {
+        &self.bonds
+    }
+
+    pub fn bonds_mut(&mut self) -> &mut [Bond] {
+        self.invalidate_graph();
+        &mut self.bonds
+    }
+
+    pub fn stereo(&self) -> &Stereo {
+        &self.stereo
+    }
+
+    /// Returns the stereochemistry for editing. Atom and bond indices are kept up to date as atoms
+    /// and bonds are removed, but configurations are not checked against the structure.
+    pub fn stereo_mut(&mut self) -> &mut Stereo {
+        &mut self.stereo
+    }
+
+    /// Returns whether the atom coordinates are 3D: as given by the `Dimensions` property where
+    /// it is set (as the Molfile reader does), or otherwise if any atom is off the z = 0 plane.
+    pub fn has_3d_coordinates(&self) -> bool {
+        match self.get_property::<u32>(&MoleculeProperty::Dimensions) {
+            Ok(Some(dimensions)) => dimensions == 3,
+            _ => self.atoms.iter().any(|atom| atom.position.z != 0.0),
+        }
+    }
+
+    pub fn add_atom(&mut self, atom: Atom) -> AtomIndex {
+        if let Some(adjacency) = self.adjacency.get_mut() {
+            adjacency.add_atom();
+        }
+        self.rings.take();
+        self.atoms.push(atom);
+        self.atoms.len() - 1
+    }
+
+    /// Adds a bond, rejecting bonds to missing atoms, from an atom to itself, or between atoms
+    /// that are already bonded.
+    pub fn add_bond(&mut self, bond: Bond) -> Result<BondIndex, MoleculeError> {
+        for atom in &[bond.from_atom_id, bond.to_atom_id] {
+            if *atom >= self.atoms.len() {
+                return Err(MoleculeError::InvalidAtomIndex(*atom));
+            }
+        }
+        if bond.from_atom_id == bond.to_atom_id {
+            return Err(MoleculeError::SelfBond(bond.from_atom_id));
+        }
+        if self
+            .bond_between(bond.from_atom_id, bond.to_atom_id)
+            .is_some()
+        {
+            return Err(MoleculeError::DuplicateBond {
+                from_atom_id: bond.from_atom_id,
+                to_atom_id: bond.to_atom_id,
+            });
+        }
+
+        let index = self.bonds.len();
+        if let Some(adjacency) = self.adjacency.get_mut() {
+            adjacency.add_bond(index, &bond);
+        }
+        self.rings.take();
+        self.bonds.push(bond);
+        Ok(index)
+    }
+
+    /// Removes an atom along with its bonds. Later atoms and bonds move down to fill the gaps.
+    pub fn remove_atom(&mut self, atom: AtomIndex) -> Result<Atom, MoleculeError> {
+        if atom >= self.atoms.len() {
+            return Err(MoleculeError::InvalidAtomIndex(atom));
+        }
+
+        let atom_map = (0..self.atoms.len())
+            .map(|index| match index {
+                _ if index < atom => Some(index),
+                _ if index > atom => Some(index - 1),
+                _ => None,
+            })
+            .collect::<Vec<Option<AtomIndex>>>();
+        self.retain_bonds(
+            |bond| bond.from_atom_id != atom && bond.to_atom_id != atom,
+            &atom_map,
+        );
+        for bond in &mut self.bonds {
+            if bond.from_atom_id > atom {
+                bond.from_atom_id -= 1;
+            }
+            if bond.to_atom_id > atom {
+                bond.to_atom_id -= 1;
+            }
+        }
+
+        self.invalidate_graph();
+        Ok(self.atoms.remove(atom))
+    }
+
+    /// Removes a bond. Later bonds move down to fill the gap.
+    pub fn remove_bond(&mut self, bond: BondIndex) -> Result<Bond, MoleculeError> {
+        if bond >= self.bonds.len() {
+            return Err(MoleculeError::InvalidBondIndex(bond));
+        }
+
+        let atom_map = (0..self.atoms.len()).map(Some).collect::<Vec<_>>();
+        let bond_map = (0..self.bonds.len())
+            .map(|index| match index {
+                _ if index < bond => Some(index),
+                _ if index > bond => Some(index - 1),
+                _ => None,
+            })
+            .collect::<Vec<Option<BondIndex>>>();
+        self.stereo.remap(&atom_map, &bond_map);
+
+        self.invalidate_graph();
+        Ok(self.bonds.remove(bond))
+    }
+
+    /// Keeps only the atoms for which `keep` returns true, along with the bonds between them.
+    ///
+    /// Returns the new index of each original atom, or `None` for atoms that were removed.
+    pub fn retain_atoms(
+        &mut self,
+        mut keep: impl FnMut(AtomIndex, &Atom) -> bool,
+    ) -> Vec<Option<AtomIndex>> {
+        let mut num_kept = 0;
+        let index_map = self
+            .atoms
+            .iter()
+            .enumerate()
+            .map(|(index, atom)| {
+                if keep(index, atom) {
+                    num_kept += 1;
+                    Some(num_kept - 1)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<Option<AtomIndex>>>();
+
+        let mut index = 0;
+        self.atoms.retain(|_| {
+            index += 1;
+            index_map[index - 1].is_some()
+        });
+
+        let new_index = |atom: AtomIndex| index_map.get(atom).copied().flatten();
+        self.retain_bonds(
+            |bond| new_index(bond.from_atom_id).is_some() && new_index(bond.to_atom_id).is_some(),
+            &index_map,
+        );
+        for bond in &mut self.bonds {
+            bond.from_atom_id = new_index(bond.from_atom_id).unwrap();
+            bond.to_atom_id = new_index(bond.to_atom_id).unwrap();
         }
+
+        self.invalidate_graph();
+        index_map
+    }
+
+    // Keeps the bonds for which `keep` returns true, updating the stereochemistry for the removed
+    // bonds and for the atoms given by `atom_map`
+    fn retain_bonds(&mut self, keep: impl Fn(&Bond) -> bool, atom_map: &[Option<AtomIndex>]) {
+        let mut num_kept = 0;
+        let bond_map = self
+            .bonds
+            .iter()
+            .map(|bond| {
+                if keep(bond) {
+                    num_kept += 1;
+                    Some(num_kept - 1)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<Option<BondIndex>>>();
+
+        let mut index = 0;
+        self.bonds.retain(|_| {
+            index += 1;
+            bond_map[index - 1].is_some()
+        });
+        self.stereo.remap(atom_map, &bond_map);
+    }
+
+    fn invalidate_graph(&mut self) {
+        self.adjacency.take();
+        self.rings.take();
+    }
+
+    fn adjacency(&self) -> &Adjacency {
+        self.adjacency
+            .get_or_init(|| Adjacency::new(self.atoms.len(), &self.bonds))
+    }
+
+    /// Returns the atoms bonded to `atom`.
+    ///
+    /// Like the other neighbour queries, this panics if `atom` is not an atom of the molecule.
+    pub fn neighbors(&self, atom: AtomIndex) -> impl Iterator<Item = AtomIndex> + '_ {
+        self.adjacency()
+            .neighbors(atom)
+            .iter()
+            .map(|(neighbor, _)| *neighbor)
+    }
+
+    /// Returns the bonds to `atom`.
+    pub fn bonds_of(&self, atom: AtomIndex) -> impl Iterator<Item = BondIndex> + '_ {
+        self.adjacency()
+            .neighbors(atom)
+            .iter()
+            .map(|(_, bond)| *bond)
+    }
+
+    /// Returns the atoms bonded to `atom`, each with the bond to it.
+    pub fn neighbor_bonds(
+        &self,
+        atom: AtomIndex,
+    ) -> impl Iterator<Item = (AtomIndex, BondIndex)> + '_ {
+        self.adjacency().neighbors(atom).iter().copied()
+    }
+
+    pub fn bond_between(&self, atom: AtomIndex, other_atom: AtomIndex) -> Option<BondIndex> {
+        self.neighbor_bonds(atom)
+            .find(|(neighbor, _)| *neighbor == other_atom)
+            .map(|(_, bond)| bond)
+    }
+
+    pub fn degree(&self, atom: AtomIndex) -> usize {
+        self.adjacency().neighbors(atom).len()
+    }
+
+    /// Returns the rings of the molecule, perceiving them on first use after the molecule was
+    /// last edited.
+    pub fn rings(&self) -> &RingInfo {
+        self.rings.get_or_init(|| RingInfo::new(self))
     }
 }
 
@@ -49,3 +314,196 @@ impl HasProperties<MoleculeProperty> for Molecule {
         &mut self.properties
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol::{BondType, MoleculeError, TetrahedralParity, TetrahedralStereo};
+
+    fn propanol() -> Result<Molecule, MoleculeError> {
+        let atoms = vec![
+            Atom::from_symbol("C")?,
+            Atom::from_symbol("C")?,
+            Atom::from_symbol("C")?,
+            Atom::from_symbol("O")?,
+        ];
+        let bonds = vec![
+            Bond::new(0, 1, BondType::single()),
+            Bond::new(1, 2, BondType::single()),
+            Bond::new(3, 1, BondType::single()),
+        ];
+        Ok(Molecule::from_graph(atoms, bonds))
+    }
+
+    #[test]
+    fn neighbor_queries() -> Result<(), MoleculeError> {
+        let mol = propanol()?;
+
+        assert_eq!(mol.neighbors(1).collect::<Vec<AtomIndex>>(), vec![0, 2, 3]);
+        assert_eq!(mol.bonds_of(1).collect::<Vec<BondIndex>>(), vec![0, 1, 2]);
+        assert_eq!(
+            mol.neighbor_bonds(3)
+                .collect::<Vec<(AtomIndex, BondIndex)>>(),
+            vec![(1, 2)]
+        );
+        assert_eq!(mol.degree(0), 1);
+        assert_eq!(mol.degree(1), 3);
+        assert_eq!(mol.bond_between(3, 1), Some(2));
+        assert_eq!(mol.bond_between(1, 3), Some(2));
+        assert_eq!(mol.bond_between(0, 3), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_bond() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+        assert_eq!(mol.degree(0), 1);
+
+        let index = mol.add_bond(Bond::new(0, 3, BondType::single()))?;
+
+        assert_eq!(index, 3);
+        assert_eq!(mol.bonds().len(), 4);
+        assert_eq!(mol.bond_between(3, 0), Some(3));
+        assert_eq!(mol.degree(0), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_bond_errors() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+
+        match mol.add_bond(Bond::new(0, 4, BondType::single())) {
+            Err(MoleculeError::InvalidAtomIndex(index)) => assert_eq!(index, 4),
+            _ => panic!("Expected MoleculeError::InvalidAtomIndex"),
+        }
+        match mol.add_bond(Bond::new(2, 2, BondType::single())) {
+            Err(MoleculeError::SelfBond(index)) => assert_eq!(index, 2),
+            _ => panic!("Expected MoleculeError::SelfBond"),
+        }
+        match mol.add_bond(Bond::new(1, 3, BondType::double())) {
+            Err(MoleculeError::DuplicateBond {
+                from_atom_id,
+                to_atom_id,
+            }) => {
+                assert_eq!(from_atom_id, 1);
+                assert_eq!(to_atom_id, 3);
+            }
+            _ => panic!("Expected MoleculeError::DuplicateBond"),
+        }
+        assert_eq!(mol.bonds().len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn remove_atom() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+
+        let atom = mol.remove_atom(1)?;
+
+        assert_eq!(atom.element.symbol, "C");
+        assert_eq!(mol.atoms().len(), 3);
+        assert_eq!(mol.bonds().len(), 0);
+
+        let mut mol = propanol()?;
+        mol.remove_atom(0)?;
+
+        assert_eq!(mol.atoms()[2].element.symbol, "O");
+        let bonds = mol
+            .bonds()
+            .iter()
+            .map(|bond| (bond.from_atom_id, bond.to_atom_id))
+            .collect::<Vec<(AtomIndex, AtomIndex)>>();
+        assert_eq!(bonds, vec![(0, 1), (2, 0)]);
+        assert_eq!(mol.neighbors(0).collect::<Vec<AtomIndex>>(), vec![1, 2]);
+
+        match mol.remove_atom(3) {
+            Err(MoleculeError::InvalidAtomIndex(index)) => assert_eq!(index, 3),
+            _ => panic!("Expected MoleculeError::InvalidAtomIndex"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn remove_bond() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+
+        let bond = mol.remove_bond(1)?;
+
+        assert_eq!(bond.to_atom_id, 2);
+        assert_eq!(mol.bonds().len(), 2);
+        assert_eq!(mol.degree(2), 0);
+        assert_eq!(mol.bond_between(1, 3), Some(1));
+
+        match mol.remove_bond(2) {
+            Err(MoleculeError::InvalidBondIndex(index)) => assert_eq!(index, 2),
+            _ => panic!("Expected MoleculeError::InvalidBondIndex"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn retain_atoms() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+
+        let index_map = mol.retain_atoms(|index, atom| index != 0 && atom.element.symbol == "C");
+
+        assert_eq!(index_map, vec![None, Some(0), Some(1), None]);
+        assert_eq!(mol.atoms().len(), 2);
+        assert_eq!(mol.bonds().len(), 1);
+        assert_eq!(mol.bond_between(0, 1), Some(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn edits_update_stereo() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+        mol.stereo_mut().set_tetrahedral(TetrahedralStereo {
+            center: 1,
+            neighbors: [0, 2, 3, 1],
+            parity: TetrahedralParity::Clockwise,
+        });
+
+        mol.remove_bond(0)?;
+        assert_eq!(mol.stereo().tetrahedral[0].neighbors, [0, 2, 3, 1]);
+
+        mol.retain_atoms(|index, _| index != 2);
+        assert!(mol.stereo().is_empty());
+
+        let mut mol = propanol()?;
+        mol.stereo_mut().set_tetrahedral(TetrahedralStereo {
+            center: 1,
+            neighbors: [1, 2, 3, 0],
+            parity: TetrahedralParity::Anticlockwise,
+        });
+        mol.add_atom(Atom::from_symbol("N")?);
+        mol.remove_atom(4)?;
+        assert_eq!(mol.stereo().tetrahedral[0].neighbors, [1, 2, 3, 0]);
+        mol.remove_atom(0)?;
+        assert!(mol.stereo().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn neighbor_queries_after_edit() -> Result<(), MoleculeError> {
+        let mut mol = propanol()?;
+        assert_eq!(mol.degree(0), 1);
+
+        let index = mol.add_atom(Atom::from_symbol("N")?);
+        assert_eq!(index, 4);
+        assert_eq!(mol.degree(4), 0);
+
+        mol.bonds_mut()[0].to_atom_id = 4;
+        assert_eq!(mol.neighbors(0).collect::<Vec<AtomIndex>>(), vec![4]);
+        assert_eq!(mol.bond_between(1, 0), None);
+        assert_eq!(mol.degree(1), 2);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(PartialEq, Debug)]
+use std::ops::{Add, Mul, Neg, Sub};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub struct Point3d {
     pub x: f64,
     pub y: f64,
@@ -9,6 +11,59 @@ impl Point3d {
     pub fn new(x: f64, y: f64, z: f64) -> Point3d {
         Point3d { x, y, z }
     }
+
+    pub fn dot(&self, other: Point3d) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: Point3d) -> Point3d {
+        Point3d::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// Length of the point taken as a vector from the origin.
+    pub fn norm(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn distance(&self, other: Point3d) -> f64 {
+        (*self - other).norm()
+    }
+}
+
+impl Add for Point3d {
+    type Output = Point3d;
+
+    fn add(self, other: Point3d) -> Point3d {
+        Point3d::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Point3d {
+    type Output = Point3d;
+
+    fn sub(self, other: Point3d) -> Point3d {
+        Point3d::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Point3d {
+    type Output = Point3d;
+
+    fn mul(self, factor: f64) -> Point3d {
+        Point3d::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Neg for Point3d {
+    type Output = Point3d;
+
+    fn neg(self) -> Point3d {
+        self * -1.0
+    }
 }
 
 #[cfg(test)]
@@ -32,4 +87,31 @@ mod tests {
         assert_ne!(Point3d::new(1.0, 2.3, 3.5), Point3d::new(1.0, 2.0, 3.5));
         assert_ne!(Point3d::new(1.0, 2.0, 3.1), Point3d::new(1.0, 2.0, 3.5));
     }
+
+    #[test]
+    fn arithmetic() {
+        let a = Point3d::new(1.0, 2.0, 3.0);
+        let b = Point3d::new(-2.0, 0.5, 1.0);
+
+        assert_eq!(a + b, Point3d::new(-1.0, 2.5, 4.0));
+        assert_eq!(a - b, Point3d::new(3.0, 1.5, 2.0));
+        assert_eq!(a * 2.0, Point3d::new(2.0, 4.0, 6.0));
+        assert_eq!(-a, Point3d::new(-1.0, -2.0, -3.0));
+    }
+
+    #[test]
+    fn vector_products() {
+        let x = Point3d::new(1.0, 0.0, 0.0);
+        let y = Point3d::new(0.0, 1.0, 0.0);
+
+        assert_f64_eq(x.dot(y), 0.0);
+        assert_f64_eq(
+            Point3d::new(1.0, 2.0, 3.0).dot(Point3d::new(4.0, 5.0, 6.0)),
+            32.0,
+        );
+        assert_eq!(x.cross(y), Point3d::new(0.0, 0.0, 1.0));
+        assert_eq!(y.cross(x), Point3d::new(0.0, 0.0, -1.0));
+        assert_f64_eq(Point3d::new(3.0, 4.0, 12.0).norm(), 13.0);
+        assert_f64_eq(x.distance(y), 2f64.sqrt());
+    }
 }
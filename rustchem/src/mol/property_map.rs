@@ -1,9 +1,45 @@
 use crate::mol::errors::PropertyError;
 use std::any::{type_name, Any};
 use std::collections::HashMap;
+use std::fmt;
 use std::hash::Hash;
 
-pub type PropertyMap<T> = HashMap<T, Box<dyn Any>>;
+pub type PropertyMap<T> = HashMap<T, Box<dyn PropertyValue>>;
+
+/// A value that can be stored in a `PropertyMap`.
+///
+/// Values are written out (e.g. as SD file data items) using their `Display` formatting.
+pub trait PropertyValue: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn to_property_string(&self) -> String;
+    fn clone_box(&self) -> Box<dyn PropertyValue>;
+}
+
+impl<U: Any + Clone + fmt::Display> PropertyValue for U {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_property_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn clone_box(&self) -> Box<dyn PropertyValue> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn PropertyValue> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl fmt::Debug for dyn PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.to_property_string())
+    }
+}
 
 pub trait HasProperties<T: 'static + Eq + Hash> {
     fn get_property_map(&self) -> &PropertyMap<T>;
@@ -19,7 +55,7 @@ pub trait HasProperties<T: 'static + Eq + Hash> {
 
     fn get_property_ref<U: 'static>(&self, property: &T) -> Result<Option<&U>, PropertyError> {
         match self.get_property_map().get(property) {
-            Some(value) => match value.downcast_ref::<U>() {
+            Some(value) => match value.as_any().downcast_ref::<U>() {
                 Some(value) => Ok(Some(value)),
                 None => Err(PropertyError::IncorrectType {
                     expected_type: type_name::<U>().to_string(),
@@ -37,7 +73,13 @@ pub trait HasProperties<T: 'static + Eq + Hash> {
         }
     }
 
-    fn set_property<U: 'static>(&mut self, property: T, value: U) {
+    fn get_property_as_string(&self, property: &T) -> Option<String> {
+        self.get_property_map()
+            .get(property)
+            .map(|value| value.to_property_string())
+    }
+
+    fn set_property<U: PropertyValue>(&mut self, property: T, value: U) {
         self.get_property_map_mut()
             .insert(property, Box::new(value));
     }
@@ -164,12 +206,14 @@ mod tests {
         assert_eq!(
             map.get(&TestProperty::StringProp)
                 .unwrap()
+                .as_any()
                 .downcast_ref::<&str>(),
             Some(&"My value")
         );
         assert_eq!(
             map.get(&TestProperty::IntProp)
                 .unwrap()
+                .as_any()
                 .downcast_ref::<i32>(),
             Some(&42)
         );
@@ -177,4 +221,23 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn get_property_as_string() -> Result<(), PropertyError> {
+        let mut obj = TestStruct::new();
+        obj.set_property(TestProperty::StringProp, "My value".to_string());
+        obj.set_property(TestProperty::IntProp, 42);
+
+        assert_eq!(
+            obj.get_property_as_string(&TestProperty::StringProp),
+            Some("My value".to_string())
+        );
+        assert_eq!(
+            obj.get_property_as_string(&TestProperty::IntProp),
+            Some("42".to_string())
+        );
+        assert_eq!(obj.get_property_as_string(&TestProperty::Undefined), None);
+
+        Ok(())
+    }
 }
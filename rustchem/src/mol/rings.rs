@@ -0,0 +1,792 @@
+use super::{AtomIndex, BondIndex, Molecule};
+use std::collections::{HashSet, VecDeque};
+
+/// A ring of atoms, listed in order around the ring.
+///
+/// The ring starts at its lowest atom index and continues towards the lower-numbered of that
+/// atom's two ring neighbours. `bonds[i]` joins `atoms[i]` to the next atom round the ring.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Ring {
+    pub atoms: Vec<AtomIndex>,
+    pub bonds: Vec<BondIndex>,
+}
+
+impl Ring {
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    pub fn contains_atom(&self, atom: AtomIndex) -> bool {
+        self.atoms.contains(&atom)
+    }
+
+    pub fn contains_bond(&self, bond: BondIndex) -> bool {
+        self.bonds.contains(&bond)
+    }
+}
+
+/// Rings perceived in a molecule, as returned by `Molecule::rings`.
+///
+/// The smallest set of smallest rings (SSSR) is one minimum cycle basis of the molecule. It is
+/// not unique (cubane has six four-membered rings but only five are in any SSSR), so this also
+/// gives the relevant cycles: the union of all possible SSSRs, which is unique. Ring counts are
+/// taken from the SSSR, while ring membership and smallest ring sizes do not depend on which
+/// SSSR was chosen.
+#[derive(Clone, Debug)]
+pub struct RingInfo {
+    sssr: Vec<Ring>,
+    relevant_cycles: Vec<Ring>,
+    atom_ring_counts: Vec<usize>,
+    bond_ring_counts: Vec<usize>,
+    atom_smallest_rings: Vec<Option<usize>>,
+    bond_smallest_rings: Vec<Option<usize>>,
+}
+
+impl RingInfo {
+    pub fn new(molecule: &Molecule) -> RingInfo {
+        let num_atoms = molecule.atoms().len();
+        let num_bonds = molecule.bonds().len();
+
+        let (sssr, relevant_cycles) = find_rings(molecule);
+
+        let mut atom_ring_counts = vec![0; num_atoms];
+        let mut bond_ring_counts = vec![0; num_bonds];
+        for ring in &sssr {
+            ring.atoms
+                .iter()
+                .for_each(|atom| atom_ring_counts[*atom] += 1);
+            ring.bonds
+                .iter()
+                .for_each(|bond| bond_ring_counts[*bond] += 1);
+        }
+
+        // Relevant cycles include the smallest ring through every ring atom and bond, and are
+        // sorted by size
+        let mut atom_smallest_rings = vec![None; num_atoms];
+        let mut bond_smallest_rings = vec![None; num_bonds];
+        for ring in &relevant_cycles {
+            for atom in &ring.atoms {
+                atom_smallest_rings[*atom].get_or_insert(ring.len());
+            }
+            for bond in &ring.bonds {
+                bond_smallest_rings[*bond].get_or_insert(ring.len());
+            }
+        }
+
+        RingInfo {
+            sssr,
+            relevant_cycles,
+            atom_ring_counts,
+            bond_ring_counts,
+            atom_smallest_rings,
+            bond_smallest_rings,
+        }
+    }
+
+    /// Returns the smallest set of smallest rings, ordered by size.
+    pub fn sssr(&self) -> &[Ring] {
+        &self.sssr
+    }
+
+    /// Returns the relevant cycles (the union of all SSSRs), ordered by size.
+    pub fn relevant_cycles(&self) -> &[Ring] {
+        &self.relevant_cycles
+    }
+
+    /// Returns whether the atom is part of a ring.
+    ///
+    /// Like the other ring queries, this panics if `atom` is not an atom of the molecule.
+    pub fn is_in_ring(&self, atom: AtomIndex) -> bool {
+        self.atom_smallest_rings[atom].is_some()
+    }
+
+    pub fn is_bond_in_ring(&self, bond: BondIndex) -> bool {
+        self.bond_smallest_rings[bond].is_some()
+    }
+
+    /// Returns the number of SSSR rings that contain the atom.
+    pub fn ring_count(&self, atom: AtomIndex) -> usize {
+        self.atom_ring_counts[atom]
+    }
+
+    pub fn bond_ring_count(&self, bond: BondIndex) -> usize {
+        self.bond_ring_counts[bond]
+    }
+
+    /// Returns the size of the smallest ring that contains the atom, or `None` if it is not in a
+    /// ring.
+    pub fn smallest_ring_size(&self, atom: AtomIndex) -> Option<usize> {
+        self.atom_smallest_rings[atom]
+    }
+
+    pub fn bond_smallest_ring_size(&self, bond: BondIndex) -> Option<usize> {
+        self.bond_smallest_rings[bond]
+    }
+}
+
+/// Returns the SSSR and the relevant cycles.
+///
+/// Every ring lies within one ring system (a biconnected component with a cycle), so each system
+/// is searched on its own. Candidate cycles are grouped into families as in Vismara's algorithm
+/// (Electronic Journal of Combinatorics 4, 1997): a family joins the shortest paths from a root
+/// atom to two others, and is represented by one of its cycles while only the numbers of paths
+/// are counted. Taking the families in order of size, a family is relevant if its cycle is
+/// independent of all smaller cycles. Relevant families are then expanded into all of their
+/// cycles, from which the SSSR is built greedily.
+fn find_rings(molecule: &Molecule) -> (Vec<Ring>, Vec<Ring>) {
+    let mut sssr = Vec::new();
+    let mut relevant_cycles = Vec::new();
+    for system in RingSystem::find_all(molecule) {
+        system.find_rings(&mut sssr, &mut relevant_cycles);
+    }
+
+    sssr.sort_by(|a, b| (a.len(), &a.atoms).cmp(&(b.len(), &b.atoms)));
+    relevant_cycles.sort_by(|a, b| (a.len(), &a.atoms).cmp(&(b.len(), &b.atoms)));
+    (sssr, relevant_cycles)
+}
+
+// A path from the root, as the atoms along it (not including the root) with the bond into each
+type Path = Vec<(AtomIndex, BondIndex)>;
+
+/// A biconnected component of the molecule that contains a cycle. Its atoms and bonds are
+/// numbered from 0 in the order of their indices in the molecule, and paths within the system
+/// use these numbers.
+struct RingSystem {
+    atoms: Vec<AtomIndex>,
+    bonds: Vec<BondIndex>,
+    neighbors: Vec<Vec<(AtomIndex, BondIndex)>>,
+}
+
+/// The cycles made by joining each pair of shortest paths from the root to `first` and `second`
+/// (through `apex` for even cycles) with `closing_bond`.
+struct Family {
+    root: AtomIndex,
+    first: AtomIndex,
+    second: AtomIndex,
+    apex: Option<(AtomIndex, BondIndex)>,
+    closing_bond: BondIndex,
+    prototype: Path,
+    num_cycles: u64,
+}
+
+/// Shortest paths from a root found by breadth-first search, with the number of paths to each
+/// atom and the atom before it on one of them.
+struct ShortestPaths {
+    root: AtomIndex,
+    distances: Vec<Option<usize>>,
+    counts: Vec<u64>,
+    previous: Vec<Option<(AtomIndex, BondIndex)>>,
+}
+
+impl ShortestPaths {
+    fn prototype(&self, atom: AtomIndex) -> Path {
+        let mut path = Vec::new();
+        let mut current = atom;
+        while let Some((previous, bond)) = self.previous[current] {
+            path.push((current, bond));
+            current = previous;
+        }
+        path.reverse();
+        path
+    }
+}
+
+impl RingSystem {
+    /// Splits the bonds of the molecule into biconnected components with Tarjan's algorithm,
+    /// keeping those that contain a cycle.
+    fn find_all(molecule: &Molecule) -> Vec<RingSystem> {
+        let num_atoms = molecule.atoms().len();
+        let mut order = vec![None; num_atoms];
+        let mut low = vec![0; num_atoms];
+        let mut visited = 0;
+        let mut bond_stack = Vec::new();
+        let mut systems = Vec::new();
+
+        for start in 0..num_atoms {
+            if order[start].is_some() {
+                continue;
+            }
+            order[start] = Some(visited);
+            low[start] = visited;
+            visited += 1;
+
+            // Atoms on the depth-first path, with the bond into each and its next neighbour
+            let mut stack: Vec<(AtomIndex, Option<BondIndex>, usize)> = vec![(start, None, 0)];
+            while let Some(top) = stack.last_mut() {
+                let (atom, parent_bond, cursor) = *top;
+                top.2 += 1;
+
+                if let Some((neighbor, bond)) = molecule.neighbor_bonds(atom).nth(cursor) {
+                    if Some(bond) == parent_bond {
+                        continue;
+                    }
+                    match order[neighbor] {
+                        None => {
+                            order[neighbor] = Some(visited);
+                            low[neighbor] = visited;
+                            visited += 1;
+                            bond_stack.push(bond);
+                            stack.push((neighbor, Some(bond), 0));
+                        }
+                        Some(neighbor_order) if neighbor_order < order[atom].unwrap() => {
+                            bond_stack.push(bond);
+                            low[atom] = low[atom].min(neighbor_order);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                stack.pop();
+                if let (Some((parent, _, _)), Some(parent_bond)) = (stack.last(), parent_bond) {
+                    let parent = *parent;
+                    low[parent] = low[parent].min(low[atom]);
+                    // The parent separates the atom's component from the rest of the molecule
+                    if low[atom] >= order[parent].unwrap() {
+                        let position = bond_stack
+                            .iter()
+                            .rposition(|bond| *bond == parent_bond)
+                            .unwrap();
+                        let bonds = bond_stack.split_off(position);
+                        systems.extend(RingSystem::new(molecule, bonds));
+                    }
+                }
+            }
+        }
+
+        systems
+    }
+
+    fn new(molecule: &Molecule, mut bonds: Vec<BondIndex>) -> Option<RingSystem> {
+        bonds.sort_unstable();
+        let mut atoms = bonds
+            .iter()
+            .flat_map(|bond| {
+                let bond = &molecule.bonds()[*bond];
+                [bond.from_atom_id, bond.to_atom_id]
+            })
+            .collect::<Vec<AtomIndex>>();
+        atoms.sort_unstable();
+        atoms.dedup();
+        if bonds.len() < atoms.len() {
+            return None;
+        }
+
+        let mut neighbors = vec![Vec::new(); atoms.len()];
+        for (index, bond) in bonds.iter().enumerate() {
+            let bond = &molecule.bonds()[*bond];
+            let from = atoms.binary_search(&bond.from_atom_id).unwrap();
+            let to = atoms.binary_search(&bond.to_atom_id).unwrap();
+            neighbors[from].push((to, index));
+            neighbors[to].push((from, index));
+        }
+
+        Some(RingSystem {
+            atoms,
+            bonds,
+            neighbors,
+        })
+    }
+
+    fn find_rings(&self, sssr: &mut Vec<Ring>, relevant_cycles: &mut Vec<Ring>) {
+        let cycle_rank = self.bonds.len() + 1 - self.atoms.len();
+
+        let mut families = (0..self.atoms.len())
+            .flat_map(|root| self.families_from_root(root))
+            .collect::<Vec<Family>>();
+        families.sort_by_key(|family| family.prototype.len());
+
+        let mut basis = CycleBasis::new(self.bonds.len());
+        let mut seen = HashSet::new();
+
+        for size_class in families.chunk_by(|a, b| a.prototype.len() == b.prototype.len()) {
+            if basis.len() == cycle_rank {
+                break;
+            }
+
+            let relevant = size_class
+                .iter()
+                .filter(|family| {
+                    basis.is_independent(
+                        &basis.vector(family.prototype.iter().map(|(_, bond)| *bond)),
+                    )
+                })
+                .collect::<Vec<&Family>>();
+
+            for family in relevant {
+                for cycle in self.family_cycles(family) {
+                    let vector = basis.vector(cycle.iter().map(|(_, bond)| *bond));
+                    if !seen.insert(vector.clone()) {
+                        continue;
+                    }
+                    let ring = ring_from_cycle(
+                        &cycle
+                            .iter()
+                            .map(|(atom, bond)| (self.atoms[*atom], self.bonds[*bond]))
+                            .collect(),
+                    );
+                    if basis.insert(&vector) {
+                        sssr.push(ring.clone());
+                    }
+                    relevant_cycles.push(ring);
+                }
+            }
+        }
+    }
+
+    /// Returns the families of candidate cycles whose highest-numbered atom is `root`.
+    fn families_from_root(&self, root: AtomIndex) -> Vec<Family> {
+        let distances = self.shortest_paths(root, self.atoms.len()).distances;
+        let restricted = self.shortest_paths(root, root);
+
+        // Atoms that can be reached from the root by a shortest path through lower-numbered atoms
+        let in_vr = |atom: AtomIndex| {
+            restricted.distances[atom].is_some() && restricted.distances[atom] == distances[atom]
+        };
+        let family = |first: AtomIndex, second: AtomIndex, apex, closing_bond| {
+            let path_first = restricted.prototype(first);
+            let path_second = restricted.prototype(second);
+            if !are_disjoint(&path_first, &path_second) {
+                return None;
+            }
+            let mut path = path_first;
+            path.extend(apex);
+            Some(Family {
+                root,
+                first,
+                second,
+                apex,
+                closing_bond,
+                prototype: close_cycle(root, path, &path_second, closing_bond),
+                num_cycles: restricted.counts[first].saturating_mul(restricted.counts[second]),
+            })
+        };
+
+        let mut families = Vec::new();
+        for y in (0..root).filter(|atom| in_vr(*atom)) {
+            let distance_y = restricted.distances[y].unwrap();
+            let mut predecessors = Vec::new();
+
+            for (z, bond) in &self.neighbors[y] {
+                let z = *z;
+                if z != root && (z > root || !in_vr(z)) {
+                    continue;
+                }
+                let distance_z = restricted.distances[z].unwrap();
+
+                if distance_z + 1 == distance_y {
+                    predecessors.push((z, *bond));
+                } else if distance_z == distance_y && z < y {
+                    // Odd cycles through the bond from y to z
+                    families.extend(family(y, z, None, *bond));
+                }
+            }
+
+            // Even cycles through a pair of bonds into y from atoms closer to the root
+            for (index, (p, bond_p)) in predecessors.iter().enumerate() {
+                for (q, bond_q) in &predecessors[index + 1..] {
+                    families.extend(family(*p, *q, Some((y, *bond_p)), *bond_q));
+                }
+            }
+        }
+
+        families
+    }
+
+    fn family_cycles(&self, family: &Family) -> Vec<Path> {
+        if family.num_cycles == 1 {
+            return vec![family.prototype.clone()];
+        }
+
+        let restricted = self.shortest_paths(family.root, family.root);
+        let mut cycles = Vec::new();
+        for path_first in self.all_paths(&restricted, family.first) {
+            for path_second in self.all_paths(&restricted, family.second) {
+                if are_disjoint(&path_first, &path_second) {
+                    let mut path = path_first.clone();
+                    path.extend(family.apex);
+                    cycles.push(close_cycle(
+                        family.root,
+                        path,
+                        &path_second,
+                        family.closing_bond,
+                    ));
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Breadth-first search from `root`, only passing through atoms numbered below `limit`.
+    fn shortest_paths(&self, root: AtomIndex, limit: AtomIndex) -> ShortestPaths {
+        let num_atoms = self.atoms.len();
+        let mut distances = vec![None; num_atoms];
+        let mut counts = vec![0u64; num_atoms];
+        let mut previous = vec![None; num_atoms];
+        distances[root] = Some(0);
+        counts[root] = 1;
+
+        let mut queue = VecDeque::from(vec![root]);
+        while let Some(atom) = queue.pop_front() {
+            let distance = distances[atom].unwrap();
+            for (neighbor, bond) in &self.neighbors[atom] {
+                if *neighbor >= limit {
+                    continue;
+                }
+                if distances[*neighbor].is_none() {
+                    distances[*neighbor] = Some(distance + 1);
+                    previous[*neighbor] = Some((atom, *bond));
+                    queue.push_back(*neighbor);
+                }
+                if distances[*neighbor] == Some(distance + 1) {
+                    counts[*neighbor] = counts[*neighbor].saturating_add(counts[atom]);
+                }
+            }
+        }
+
+        ShortestPaths {
+            root,
+            distances,
+            counts,
+            previous,
+        }
+    }
+
+    /// Returns every shortest path from the root to `atom` found by the restricted search.
+    fn all_paths(&self, paths: &ShortestPaths, atom: AtomIndex) -> Vec<Path> {
+        if atom == paths.root {
+            return vec![Vec::new()];
+        }
+
+        let distance = paths.distances[atom].unwrap();
+        let mut all_paths = Vec::new();
+        for (neighbor, bond) in &self.neighbors[atom] {
+            if *neighbor <= paths.root && paths.distances[*neighbor] == Some(distance - 1) {
+                for mut path in self.all_paths(paths, *neighbor) {
+                    path.push((atom, *bond));
+                    all_paths.push(path);
+                }
+            }
+        }
+        all_paths
+    }
+}
+
+fn are_disjoint(path: &Path, other: &Path) -> bool {
+    path.iter()
+        .all(|(atom, _)| other.iter().all(|(other_atom, _)| other_atom != atom))
+}
+
+/// Joins two paths from the root with a bond between their ends, giving a closed path that goes
+/// out along `path` and back along `other`.
+fn close_cycle(root: AtomIndex, mut path: Path, other: &Path, bond: BondIndex) -> Path {
+    let mut previous_bond = bond;
+    for (atom, bond) in other.iter().rev() {
+        path.push((*atom, previous_bond));
+        previous_bond = *bond;
+    }
+    path.push((root, previous_bond));
+    path
+}
+
+fn ring_from_cycle(cycle: &Path) -> Ring {
+    // Each bond leads into its atom, so bonds[i] joins atoms[i] to atoms[i + 1]
+    let size = cycle.len();
+    let atoms = cycle
+        .iter()
+        .map(|(atom, _)| *atom)
+        .collect::<Vec<AtomIndex>>();
+    let bonds = (0..size)
+        .map(|index| cycle[(index + 1) % size].1)
+        .collect::<Vec<BondIndex>>();
+
+    let start = (0..size).min_by_key(|index| atoms[*index]).unwrap();
+    let next = atoms[(start + 1) % size];
+    let previous = atoms[(start + size - 1) % size];
+
+    if size < 3 || next < previous {
+        Ring {
+            atoms: (0..size).map(|i| atoms[(start + i) % size]).collect(),
+            bonds: (0..size).map(|i| bonds[(start + i) % size]).collect(),
+        }
+    } else {
+        Ring {
+            atoms: (0..size)
+                .map(|i| atoms[(start + size - i) % size])
+                .collect(),
+            bonds: (0..size)
+                .map(|i| bonds[(start + 2 * size - i - 1) % size])
+                .collect(),
+        }
+    }
+}
+
+/// Cycles as sets of bonds, kept in row echelon form over GF(2) to test for independence.
+struct CycleBasis {
+    num_words: usize,
+    rows: Vec<(usize, Vec<u64>)>,
+}
+
+impl CycleBasis {
+    fn new(num_bonds: usize) -> CycleBasis {
+        CycleBasis {
+            num_words: num_bonds.div_ceil(64),
+            rows: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn vector(&self, bonds: impl Iterator<Item = BondIndex>) -> Vec<u64> {
+        let mut vector = vec![0; self.num_words];
+        for bond in bonds {
+            vector[bond / 64] ^= 1 << (bond % 64);
+        }
+        vector
+    }
+
+    fn reduce(&self, vector: &[u64]) -> Vec<u64> {
+        let mut vector = vector.to_vec();
+        for (pivot, row) in &self.rows {
+            if vector[pivot / 64] & (1 << (pivot % 64)) != 0 {
+                vector
+                    .iter_mut()
+                    .zip(row)
+                    .for_each(|(word, bits)| *word ^= bits);
+            }
+        }
+        vector
+    }
+
+    fn is_independent(&self, vector: &[u64]) -> bool {
+        self.reduce(vector).iter().any(|word| *word != 0)
+    }
+
+    /// Adds the cycle to the basis if it is independent of it, returning whether it was added.
+    fn insert(&mut self, vector: &[u64]) -> bool {
+        let reduced = self.reduce(vector);
+        match reduced.iter().position(|word| *word != 0) {
+            Some(word) => {
+                let pivot = word * 64 + reduced[word].trailing_zeros() as usize;
+                self.rows.push((pivot, reduced));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{read_smiles, ParseError};
+    use crate::mol::{Atom, Bond, BondType};
+
+    fn ring_sizes(rings: &[Ring]) -> Vec<usize> {
+        rings.iter().map(|ring| ring.len()).collect()
+    }
+
+    #[test]
+    fn acyclic() -> Result<(), ParseError> {
+        let mol = read_smiles("CC(C)CO.[Na+]")?;
+        let rings = mol.rings();
+
+        assert!(rings.sssr().is_empty());
+        assert!(rings.relevant_cycles().is_empty());
+        assert!(!rings.is_in_ring(1));
+        assert!(!rings.is_bond_in_ring(0));
+        assert_eq!(rings.ring_count(1), 0);
+        assert_eq!(rings.smallest_ring_size(1), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cyclohexane() -> Result<(), ParseError> {
+        let mol = read_smiles("C1CCCCC1C")?;
+        let rings = mol.rings();
+
+        assert_eq!(
+            rings.sssr(),
+            &[Ring {
+                atoms: vec![0, 1, 2, 3, 4, 5],
+                bonds: vec![0, 1, 2, 3, 4, 5],
+            }]
+        );
+        assert_eq!(rings.relevant_cycles(), rings.sssr());
+        assert!(rings.is_in_ring(5));
+        assert!(!rings.is_in_ring(6));
+        assert!(rings.is_bond_in_ring(5));
+        assert!(!rings.is_bond_in_ring(6));
+        assert_eq!(rings.smallest_ring_size(0), Some(6));
+        assert_eq!(rings.bond_smallest_ring_size(6), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ring_order() -> Result<(), ParseError> {
+        let mol = read_smiles("C1CC2CCC1C2")?;
+        let rings = mol.rings();
+
+        assert_eq!(ring_sizes(rings.sssr()), vec![5, 5]);
+        for ring in rings.sssr() {
+            for (index, bond) in ring.bonds.iter().enumerate() {
+                let bond = &mol.bonds()[*bond];
+                let next = ring.atoms[(index + 1) % ring.len()];
+                let mut ends = [bond.from_atom_id, bond.to_atom_id];
+                ends.sort_unstable();
+                let mut expected = [ring.atoms[index], next];
+                expected.sort_unstable();
+                assert_eq!(ends, expected);
+            }
+            assert_eq!(ring.atoms[0], *ring.atoms.iter().min().unwrap());
+            assert!(ring.atoms[1] < ring.atoms[ring.len() - 1]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn fused_rings() -> Result<(), ParseError> {
+        // Naphthalene, with the 10-membered envelope ring left out of the SSSR
+        let mol = read_smiles("c1ccc2ccccc2c1")?;
+        let rings = mol.rings();
+
+        assert_eq!(ring_sizes(rings.sssr()), vec![6, 6]);
+        assert_eq!(ring_sizes(rings.relevant_cycles()), vec![6, 6]);
+        assert_eq!(rings.ring_count(0), 1);
+        assert_eq!(rings.ring_count(3), 2);
+        assert_eq!(rings.bond_ring_count(mol.bond_between(3, 8).unwrap()), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn spiro_and_bridged() -> Result<(), ParseError> {
+        // Spiro[2.4]heptane and norbornane
+        let mol = read_smiles("C12(CC1)CCCC2.C1CC2CCC1C2")?;
+        let rings = mol.rings();
+
+        assert_eq!(ring_sizes(rings.sssr()), vec![3, 5, 5, 5]);
+        assert_eq!(rings.ring_count(0), 2);
+        assert_eq!(rings.smallest_ring_size(0), Some(3));
+        assert_eq!(rings.smallest_ring_size(4), Some(5));
+        assert_eq!(rings.ring_count(9), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cubane() -> Result<(), ParseError> {
+        let mol = read_smiles("C12C3C4C1C5C2C3C45")?;
+        let rings = mol.rings();
+
+        assert_eq!(ring_sizes(rings.sssr()), vec![4; 5]);
+        assert_eq!(ring_sizes(rings.relevant_cycles()), vec![4; 6]);
+        assert!((0..8).all(|atom| rings.smallest_ring_size(atom) == Some(4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn even_and_odd_cycles() -> Result<(), ParseError> {
+        // Bicyclo[2.2.2]octane has three 6-membered relevant cycles, any two of which form an SSSR
+        let mol = read_smiles("C12CCC(CC1)CC2")?;
+        let rings = mol.rings();
+
+        assert_eq!(ring_sizes(rings.sssr()), vec![6, 6]);
+        assert_eq!(ring_sizes(rings.relevant_cycles()), vec![6, 6, 6]);
+
+        // Adamantane
+        let mol = read_smiles("C1C2CC3CC1CC(C2)C3")?;
+        let rings = mol.rings();
+
+        assert_eq!(ring_sizes(rings.sssr()), vec![6, 6, 6]);
+        assert_eq!(ring_sizes(rings.relevant_cycles()), vec![6, 6, 6, 6]);
+
+        // Decalin, and cyclohexane fused to cycloheptane
+        let mol = read_smiles("C1CCC2CCCCC2C1")?;
+
+        assert_eq!(ring_sizes(mol.rings().sssr()), vec![6, 6]);
+
+        let mol = read_smiles("C1CCC2CCCCCC2C1")?;
+
+        assert_eq!(ring_sizes(mol.rings().sssr()), vec![6, 7]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fullerene() -> Result<(), ParseError> {
+        let mol = read_smiles(
+            "C12=C3C4=C5C6=C1C7=C8C9=C1C%10=C%11C(=C29)C3=C2C3=C4C4=C5C5=C9C6=C7C6=C7C8=C1C1=C8\
+             C%10=C%10C%11=C2C2=C3C3=C4C4=C5C5=C%11C%12=C(C6=C95)C7=C1C1=C%12C5=C%11C4=C3C3=C5\
+             C(=C81)C%10=C23",
+        )?;
+        let rings = mol.rings();
+
+        assert_eq!(mol.atoms().len(), 60);
+        assert_eq!(mol.bonds().len(), 90);
+        assert_eq!(rings.sssr().len(), 31);
+        assert_eq!(
+            ring_sizes(rings.relevant_cycles()),
+            [vec![5; 12], vec![6; 20]].concat()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn large_molecule() -> Result<(), ParseError> {
+        // A 1000-residue peptide of Trp-Pro-Phe-Cys repeats, with disulfide bridges joining pairs
+        // of cysteines into macrocycles that take in a proline ring
+        let mut smiles = String::new();
+        for _ in 0..250 {
+            smiles.push_str(
+                "N[C@@H](Cc1c[nH]c2ccccc12)C(=O)N1CCC[C@H]1C(=O)N[C@@H](Cc1ccccc1)C(=O)\
+                 N[C@@H](CS%10)C(=O)",
+            );
+        }
+        smiles.push('O');
+        let mol = read_smiles(&smiles)?;
+        let rings = mol.rings();
+
+        assert_eq!(mol.atoms().len(), 9501);
+        assert_eq!(rings.sssr().len(), 1125);
+        assert_eq!(rings.relevant_cycles().len(), 1125);
+        let sulfur = mol
+            .atoms()
+            .iter()
+            .position(|atom| atom.element.symbol == "S")
+            .unwrap();
+        assert_eq!(rings.smallest_ring_size(sulfur), Some(17));
+        assert_eq!(rings.ring_count(sulfur), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cache_invalidated_on_edit() -> Result<(), ParseError> {
+        let mut mol = read_smiles("C1CCCCC1")?;
+        assert!(mol.rings().is_in_ring(0));
+
+        mol.remove_bond(5)?;
+        assert!(mol.rings().sssr().is_empty());
+        assert!(!mol.rings().is_in_ring(0));
+
+        mol.add_bond(Bond::new(0, 2, BondType::single()))?;
+        assert_eq!(mol.rings().smallest_ring_size(1), Some(3));
+
+        let index = mol.add_atom(Atom::from_symbol("N")?);
+        assert!(!mol.rings().is_in_ring(index));
+
+        Ok(())
+    }
+}
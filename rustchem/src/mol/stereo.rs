@@ -0,0 +1,880 @@
+use super::{
+    symmetry_classes, AtomIndex, AtomProperty, BondIndex, BondStereo, BondType, HasProperties,
+    Molecule, Point3d,
+};
+
+// Double bonds in rings smaller than this can only be cis, so have no stereo configuration
+const MIN_STEREO_RING_SIZE: usize = 8;
+
+// Elements whose lone pair holds its position, so that they can be stereocentres with three
+// neighbours and no hydrogen (as in sulfoxides and phosphines)
+const LONE_PAIR_CENTER_SYMBOLS: [&str; 4] = ["P", "S", "As", "Se"];
+
+/// The direction in which the last three neighbours of a tetrahedral centre turn, looking from
+/// the first neighbour towards the centre.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TetrahedralParity {
+    /// As written '@@' in SMILES
+    Clockwise,
+    /// As written '@' in SMILES
+    Anticlockwise,
+}
+
+impl TetrahedralParity {
+    pub fn inverted(&self) -> TetrahedralParity {
+        match self {
+            TetrahedralParity::Clockwise => TetrahedralParity::Anticlockwise,
+            TetrahedralParity::Anticlockwise => TetrahedralParity::Clockwise,
+        }
+    }
+}
+
+/// The configuration of a tetrahedral stereocentre.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TetrahedralStereo {
+    pub center: AtomIndex,
+    /// The four neighbours, with `center` itself standing in for an implicit hydrogen or lone pair
+    pub neighbors: [AtomIndex; 4],
+    pub parity: TetrahedralParity,
+}
+
+impl TetrahedralStereo {
+    /// Returns the parity with the neighbours taken in another order, or `None` if `neighbors`
+    /// is not an ordering of the centre's neighbours.
+    pub fn parity_for(&self, neighbors: &[AtomIndex; 4]) -> Option<TetrahedralParity> {
+        let mut order = [0; 4];
+        for (position, neighbor) in neighbors.iter().enumerate() {
+            order[position] = self.neighbors.iter().position(|other| other == neighbor)?;
+        }
+
+        // Each swap needed to sort the order inverts the parity
+        let mut swaps = 0;
+        for first in 0..4 {
+            for second in first + 1..4 {
+                if order[first] > order[second] {
+                    swaps += 1;
+                }
+            }
+        }
+        let mut sorted = order;
+        sorted.sort_unstable();
+        if sorted != [0, 1, 2, 3] {
+            return None;
+        }
+
+        if swaps % 2 == 0 {
+            Some(self.parity)
+        } else {
+            Some(self.parity.inverted())
+        }
+    }
+}
+
+/// Whether the reference neighbours of a double bond are on the same side.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DoubleBondConfiguration {
+    Cis,
+    Trans,
+}
+
+impl DoubleBondConfiguration {
+    pub fn inverted(&self) -> DoubleBondConfiguration {
+        match self {
+            DoubleBondConfiguration::Cis => DoubleBondConfiguration::Trans,
+            DoubleBondConfiguration::Trans => DoubleBondConfiguration::Cis,
+        }
+    }
+}
+
+/// The configuration of a double bond.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct DoubleBondStereo {
+    pub bond: BondIndex,
+    /// A neighbour of the bond's `from_atom_id` and a neighbour of its `to_atom_id`
+    pub neighbors: [AtomIndex; 2],
+    pub configuration: DoubleBondConfiguration,
+}
+
+impl DoubleBondStereo {
+    /// Returns the configuration relative to other neighbours of the bond's atoms, flipping it for
+    /// each end where the neighbour differs from the one in `neighbors`.
+    pub fn configuration_for(&self, neighbors: [AtomIndex; 2]) -> DoubleBondConfiguration {
+        if (neighbors[0] == self.neighbors[0]) == (neighbors[1] == self.neighbors[1]) {
+            self.configuration
+        } else {
+            self.configuration.inverted()
+        }
+    }
+}
+
+/// How the configurations of the stereocentres in an enhanced stereo group are known.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum StereoGroupKind {
+    /// The configurations are as drawn ('STEABS' in molfiles, 'a' in CXSMILES)
+    Absolute,
+    /// The configurations are either as drawn or all inverted, but it is not known which
+    /// ('STEREL' in molfiles, 'o' in CXSMILES)
+    Or,
+    /// A mixture of the configurations as drawn and all inverted ('STERAC' in molfiles, '&' in
+    /// CXSMILES)
+    And,
+}
+
+/// An enhanced stereo group: stereocentres whose configurations are known in the same way.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct StereoGroup {
+    pub kind: StereoGroupKind,
+    /// Number of an Or or And group as read (e.g. 2 for 'STERAC2' or '&2'), 0 for absolute groups
+    pub number: u32,
+    pub atoms: Vec<AtomIndex>,
+}
+
+/// The stereochemistry of a molecule. Atoms and bonds without an entry have an unknown (or no)
+/// configuration.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Stereo {
+    pub tetrahedral: Vec<TetrahedralStereo>,
+    pub double_bonds: Vec<DoubleBondStereo>,
+    /// Enhanced stereo groups
+    pub groups: Vec<StereoGroup>,
+    /// Molfile chiral flag, set when the configurations are absolute rather than relative
+    pub chiral: bool,
+}
+
+impl Stereo {
+    pub fn tetrahedral_at(&self, atom: AtomIndex) -> Option<&TetrahedralStereo> {
+        self.tetrahedral.iter().find(|stereo| stereo.center == atom)
+    }
+
+    pub fn double_bond_at(&self, bond: BondIndex) -> Option<&DoubleBondStereo> {
+        self.double_bonds.iter().find(|stereo| stereo.bond == bond)
+    }
+
+    /// Sets the configuration of a centre, replacing any it already had.
+    pub fn set_tetrahedral(&mut self, stereo: TetrahedralStereo) {
+        self.tetrahedral
+            .retain(|other| other.center != stereo.center);
+        self.tetrahedral.push(stereo);
+    }
+
+    /// Sets the configuration of a double bond, replacing any it already had.
+    pub fn set_double_bond(&mut self, stereo: DoubleBondStereo) {
+        self.double_bonds.retain(|other| other.bond != stereo.bond);
+        self.double_bonds.push(stereo);
+    }
+
+    /// Returns the group that the stereocentre belongs to, if any.
+    pub fn group_of(&self, atom: AtomIndex) -> Option<&StereoGroup> {
+        self.groups.iter().find(|group| group.atoms.contains(&atom))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tetrahedral.is_empty() && self.double_bonds.is_empty() && self.groups.is_empty()
+    }
+
+    /// Returns whether the atom is a centre or a reference neighbour of any configuration.
+    pub fn refers_to_atom(&self, atom: AtomIndex) -> bool {
+        self.tetrahedral
+            .iter()
+            .any(|stereo| stereo.center == atom || stereo.neighbors.contains(&atom))
+            || self
+                .double_bonds
+                .iter()
+                .any(|stereo| stereo.neighbors.contains(&atom))
+    }
+
+    /// Renumbers atoms and bonds after an edit, given the new index of each original atom and
+    /// bond. Configurations that refer to removed atoms or bonds are dropped, as are groups left
+    /// without atoms.
+    pub(super) fn remap(&mut self, atom_map: &[Option<AtomIndex>], bond_map: &[Option<BondIndex>]) {
+        let new_atom = |atom: AtomIndex| atom_map.get(atom).copied().flatten();
+
+        self.tetrahedral = self
+            .tetrahedral
+            .iter()
+            .filter_map(|stereo| {
+                let center = new_atom(stereo.center)?;
+                let mut neighbors = [center; 4];
+                for (new_neighbor, neighbor) in neighbors.iter_mut().zip(&stereo.neighbors) {
+                    if *neighbor != stereo.center {
+                        *new_neighbor = new_atom(*neighbor)?;
+                    }
+                }
+                Some(TetrahedralStereo {
+                    center,
+                    neighbors,
+                    parity: stereo.parity,
+                })
+            })
+            .collect();
+
+        self.double_bonds = self
+            .double_bonds
+            .iter()
+            .filter_map(|stereo| {
+                Some(DoubleBondStereo {
+                    bond: bond_map.get(stereo.bond).copied().flatten()?,
+                    neighbors: [
+                        new_atom(stereo.neighbors[0])?,
+                        new_atom(stereo.neighbors[1])?,
+                    ],
+                    configuration: stereo.configuration,
+                })
+            })
+            .collect();
+
+        self.groups = self
+            .groups
+            .iter()
+            .map(|group| StereoGroup {
+                kind: group.kind,
+                number: group.number,
+                atoms: group
+                    .atoms
+                    .iter()
+                    .filter_map(|atom| new_atom(*atom))
+                    .collect(),
+            })
+            .filter(|group| !group.atoms.is_empty())
+            .collect();
+    }
+}
+
+/// Sets the configurations of stereocentres and double bonds from the atom coordinates.
+///
+/// With 3D coordinates the configurations follow from the positions of the atoms. With 2D
+/// coordinates, centres take their configuration from the wedges that start at them, while
+/// double bonds follow from the drawing. Only atoms with four different neighbours (counting an
+/// implicit hydrogen or lone pair) and double bonds with different neighbours at each end are
+/// given a configuration, and wavy or crossed bonds leave the configuration unknown.
+pub fn assign_stereo_from_coordinates(molecule: &mut Molecule) {
+    let is_3d = molecule.atoms().iter().any(|atom| atom.position.z != 0.0);
+    let is_2d = molecule
+        .atoms()
+        .iter()
+        .any(|atom| atom.position != Point3d::new(0.0, 0.0, 0.0));
+    if !is_3d && !is_2d {
+        return;
+    }
+
+    let classes = symmetry_classes(molecule);
+    let mut tetrahedral = Vec::new();
+    for center in 0..molecule.atoms().len() {
+        let neighbors = match stereocenter_neighbors(molecule, center, &classes) {
+            Some(neighbors) => neighbors,
+            None => continue,
+        };
+        if let Some(parity) = geometric_parity(molecule, center, &neighbors, is_3d) {
+            tetrahedral.push(TetrahedralStereo {
+                center,
+                neighbors,
+                parity,
+            });
+        }
+    }
+
+    let mut double_bonds = Vec::new();
+    for bond in 0..molecule.bonds().len() {
+        let neighbors = match stereo_bond_neighbors(molecule, bond, &classes) {
+            Some(neighbors) => neighbors,
+            None => continue,
+        };
+        if let Some(configuration) = geometric_configuration(molecule, bond, neighbors) {
+            double_bonds.push(DoubleBondStereo {
+                bond,
+                neighbors,
+                configuration,
+            });
+        }
+    }
+
+    let stereo = molecule.stereo_mut();
+    for stereo_center in tetrahedral {
+        stereo.set_tetrahedral(stereo_center);
+    }
+    for stereo_bond in double_bonds {
+        stereo.set_double_bond(stereo_bond);
+    }
+}
+
+/// Sets the configurations of atoms with an odd or even Molfile stereo parity, other than those
+/// that already have a configuration.
+///
+/// The parity is relative to the order of the atom indices, with hydrogens (including an implicit
+/// hydrogen) taken to come last: looking with the last neighbour pointing away, the others turn
+/// clockwise in order of index for an odd parity, and anticlockwise for an even parity.
+pub fn assign_stereo_from_parity(molecule: &mut Molecule) {
+    let mut tetrahedral = Vec::new();
+    for (center, atom) in molecule.atoms().iter().enumerate() {
+        let parity = match atom.get_property::<u32>(&AtomProperty::StereoParity) {
+            Ok(Some(1)) => TetrahedralParity::Clockwise,
+            Ok(Some(2)) => TetrahedralParity::Anticlockwise,
+            _ => continue,
+        };
+        if molecule.stereo().tetrahedral_at(center).is_some() {
+            continue;
+        }
+
+        let mut neighbors = molecule.neighbors(center).collect::<Vec<AtomIndex>>();
+        neighbors.sort_unstable_by_key(|neighbor| {
+            (
+                molecule.atoms()[*neighbor].element.atomic_number == 1,
+                *neighbor,
+            )
+        });
+        if neighbors.len() == 3 {
+            neighbors.push(center);
+        }
+        if neighbors.len() == 4 {
+            tetrahedral.push(TetrahedralStereo {
+                center,
+                neighbors: [neighbors[0], neighbors[1], neighbors[2], neighbors[3]],
+                parity,
+            });
+        }
+    }
+
+    for stereo_center in tetrahedral {
+        molecule.stereo_mut().set_tetrahedral(stereo_center);
+    }
+}
+
+// Neighbours of an atom that could be a stereocentre in ascending order, followed by the atom
+// itself for an implicit hydrogen or lone pair
+fn stereocenter_neighbors(
+    molecule: &Molecule,
+    center: AtomIndex,
+    classes: &[usize],
+) -> Option<[AtomIndex; 4]> {
+    let atom = &molecule.atoms()[center];
+    if atom.aromatic || atom.properties.contains_key(&AtomProperty::AtomList) {
+        return None;
+    }
+
+    let mut neighbors = molecule.neighbors(center).collect::<Vec<AtomIndex>>();
+    neighbors.sort_unstable();
+    let hydrogen_count = molecule.implicit_hydrogen_count(center);
+    match (neighbors.len(), hydrogen_count) {
+        (4, 0) => {}
+        (3, 1) => {
+            // An explicit hydrogen would match the implicit one
+            if neighbors.iter().any(|neighbor| {
+                let neighbor = &molecule.atoms()[*neighbor];
+                neighbor.element.atomic_number == 1 && neighbor.isotope.is_none()
+            }) {
+                return None;
+            }
+            neighbors.push(center);
+        }
+        (3, 0) if LONE_PAIR_CENTER_SYMBOLS.contains(&atom.element.symbol.as_str()) => {
+            neighbors.push(center);
+        }
+        _ => return None,
+    }
+
+    for (index, neighbor) in neighbors.iter().enumerate() {
+        if neighbors[index + 1..]
+            .iter()
+            .any(|other| *other != center && classes[*other] == classes[*neighbor])
+        {
+            return None;
+        }
+    }
+
+    Some([neighbors[0], neighbors[1], neighbors[2], neighbors[3]])
+}
+
+fn geometric_parity(
+    molecule: &Molecule,
+    center: AtomIndex,
+    neighbors: &[AtomIndex; 4],
+    is_3d: bool,
+) -> Option<TetrahedralParity> {
+    let center_position = molecule.atoms()[center].position;
+    let mut has_wedge = false;
+
+    let mut directions = Vec::with_capacity(4);
+    for neighbor in neighbors.iter().filter(|neighbor| **neighbor != center) {
+        let mut direction = molecule.atoms()[*neighbor].position - center_position;
+        if !is_3d {
+            direction.z = 0.0;
+            let bond = molecule.bond_between(center, *neighbor)?;
+            let bond = &molecule.bonds()[bond];
+            if bond.from_atom_id == center {
+                match bond.stereo {
+                    BondStereo::Up => {
+                        has_wedge = true;
+                        direction = unit(direction)? + Point3d::new(0.0, 0.0, 1.0);
+                    }
+                    BondStereo::Down => {
+                        has_wedge = true;
+                        direction = unit(direction)? + Point3d::new(0.0, 0.0, -1.0);
+                    }
+                    BondStereo::Either => return None,
+                    _ => {}
+                }
+            }
+        }
+        directions.push(unit(direction)?);
+    }
+    if !is_3d && !has_wedge {
+        return None;
+    }
+
+    // An implicit hydrogen or lone pair points away from the other neighbours
+    if directions.len() == 3 {
+        let sum = directions[0] + directions[1] + directions[2];
+        directions.push(unit(-sum)?);
+    }
+
+    let first = directions[0];
+    let volume = (directions[1] - first).dot((directions[2] - first).cross(directions[3] - first));
+    if volume > 1e-3 {
+        Some(TetrahedralParity::Clockwise)
+    } else if volume < -1e-3 {
+        Some(TetrahedralParity::Anticlockwise)
+    } else {
+        None
+    }
+}
+
+// The lowest numbered neighbour at each end of a double bond that could have a configuration
+fn stereo_bond_neighbors(
+    molecule: &Molecule,
+    bond: BondIndex,
+    classes: &[usize],
+) -> Option<[AtomIndex; 2]> {
+    let stereo_bond = &molecule.bonds()[bond];
+    let (from, to) = (stereo_bond.from_atom_id, stereo_bond.to_atom_id);
+    if stereo_bond.bond_type != BondType::double()
+        || stereo_bond.aromatic
+        || stereo_bond.stereo == BondStereo::CisTransEither
+        || from >= molecule.atoms().len()
+        || to >= molecule.atoms().len()
+        || molecule
+            .rings()
+            .bond_smallest_ring_size(bond)
+            .is_some_and(|size| size < MIN_STEREO_RING_SIZE)
+    {
+        return None;
+    }
+
+    let mut neighbors = [0; 2];
+    for (end, (atom, other_end)) in [(from, to), (to, from)].iter().enumerate() {
+        let mut others = Vec::new();
+        for (neighbor, neighbor_bond) in molecule.neighbor_bonds(*atom) {
+            if neighbor == *other_end {
+                continue;
+            }
+            let neighbor_bond = &molecule.bonds()[neighbor_bond];
+            // Cumulated double bonds and wavy bonds to the double bond leave it without a
+            // configuration
+            if neighbor_bond.bond_type != BondType::single()
+                || (neighbor_bond.stereo == BondStereo::Either
+                    && neighbor_bond.from_atom_id == *atom)
+            {
+                return None;
+            }
+            others.push(neighbor);
+        }
+
+        let hydrogen_count = molecule.implicit_hydrogen_count(*atom);
+        match (others.len(), hydrogen_count) {
+            (1, _) if hydrogen_count <= 1 => {}
+            (2, 0) if classes[others[0]] != classes[others[1]] => {}
+            _ => return None,
+        }
+        neighbors[end] = *others.iter().min()?;
+    }
+
+    Some(neighbors)
+}
+
+fn geometric_configuration(
+    molecule: &Molecule,
+    bond: BondIndex,
+    neighbors: [AtomIndex; 2],
+) -> Option<DoubleBondConfiguration> {
+    let bond = &molecule.bonds()[bond];
+    let position = |atom: AtomIndex| molecule.atoms()[atom].position;
+    let axis = unit(position(bond.to_atom_id) - position(bond.from_atom_id))?;
+
+    // Compare the directions of the neighbours across the bond, ignoring their components along it
+    let across = |atom: AtomIndex, neighbor: AtomIndex| {
+        let direction = position(neighbor) - position(atom);
+        unit(direction - axis * direction.dot(axis))
+    };
+    let first = across(bond.from_atom_id, neighbors[0])?;
+    let second = across(bond.to_atom_id, neighbors[1])?;
+
+    let alignment = first.dot(second);
+    if alignment > 0.1 {
+        Some(DoubleBondConfiguration::Cis)
+    } else if alignment < -0.1 {
+        Some(DoubleBondConfiguration::Trans)
+    } else {
+        None
+    }
+}
+
+fn unit(vector: Point3d) -> Option<Point3d> {
+    let norm = vector.norm();
+    if norm > 1e-6 {
+        Some(vector * (1.0 / norm))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol::{Atom, Bond, MoleculeError};
+
+    fn molecule_2d(
+        symbols: &[&str],
+        positions: &[(f64, f64)],
+        bonds: &[(AtomIndex, AtomIndex, BondType, BondStereo)],
+    ) -> Result<Molecule, MoleculeError> {
+        let mut molecule = Molecule::new();
+        for (symbol, (x, y)) in symbols.iter().zip(positions) {
+            let mut atom = Atom::from_symbol(symbol)?;
+            atom.position = Point3d::new(*x, *y, 0.0);
+            molecule.add_atom(atom);
+        }
+        for (from, to, bond_type, stereo) in bonds {
+            let mut bond = Bond::new(*from, *to, bond_type.clone());
+            bond.stereo = *stereo;
+            molecule.add_bond(bond)?;
+        }
+        Ok(molecule)
+    }
+
+    // Bromochlorofluoromethane drawn with the given stereo on the bond to bromine
+    fn bromochlorofluoromethane(stereo: BondStereo) -> Result<Molecule, MoleculeError> {
+        molecule_2d(
+            &["C", "F", "Cl", "Br"],
+            &[(0.0, 0.0), (0.0, 1.0), (-0.87, -0.5), (0.87, -0.5)],
+            &[
+                (0, 1, BondType::single(), BondStereo::None),
+                (0, 2, BondType::single(), BondStereo::None),
+                (0, 3, BondType::single(), stereo),
+            ],
+        )
+    }
+
+    #[test]
+    fn parity_for() {
+        let stereo = TetrahedralStereo {
+            center: 0,
+            neighbors: [1, 2, 3, 4],
+            parity: TetrahedralParity::Clockwise,
+        };
+
+        assert_eq!(
+            stereo.parity_for(&[1, 2, 3, 4]),
+            Some(TetrahedralParity::Clockwise)
+        );
+        assert_eq!(
+            stereo.parity_for(&[2, 1, 3, 4]),
+            Some(TetrahedralParity::Anticlockwise)
+        );
+        assert_eq!(
+            stereo.parity_for(&[2, 3, 1, 4]),
+            Some(TetrahedralParity::Clockwise)
+        );
+        assert_eq!(
+            stereo.parity_for(&[4, 1, 2, 3]),
+            Some(TetrahedralParity::Anticlockwise)
+        );
+        assert_eq!(stereo.parity_for(&[1, 2, 3, 5]), None);
+        assert_eq!(stereo.parity_for(&[1, 2, 3, 3]), None);
+    }
+
+    #[test]
+    fn configuration_for() {
+        let stereo = DoubleBondStereo {
+            bond: 0,
+            neighbors: [2, 4],
+            configuration: DoubleBondConfiguration::Cis,
+        };
+
+        assert_eq!(
+            stereo.configuration_for([2, 4]),
+            DoubleBondConfiguration::Cis
+        );
+        assert_eq!(
+            stereo.configuration_for([3, 4]),
+            DoubleBondConfiguration::Trans
+        );
+        assert_eq!(
+            stereo.configuration_for([2, 5]),
+            DoubleBondConfiguration::Trans
+        );
+        assert_eq!(
+            stereo.configuration_for([3, 5]),
+            DoubleBondConfiguration::Cis
+        );
+    }
+
+    #[test]
+    fn tetrahedral_from_wedges() -> Result<(), MoleculeError> {
+        let mut up = bromochlorofluoromethane(BondStereo::Up)?;
+        let mut down = bromochlorofluoromethane(BondStereo::Down)?;
+        let mut either = bromochlorofluoromethane(BondStereo::Either)?;
+        let mut plain = bromochlorofluoromethane(BondStereo::None)?;
+
+        assign_stereo_from_coordinates(&mut up);
+        assign_stereo_from_coordinates(&mut down);
+        assign_stereo_from_coordinates(&mut either);
+        assign_stereo_from_coordinates(&mut plain);
+
+        // Looking from the implicit hydrogen, below the page for an up wedge, F, Cl and Br turn
+        // clockwise, so looking from F the others turn anticlockwise
+        assert_eq!(
+            up.stereo().tetrahedral,
+            vec![TetrahedralStereo {
+                center: 0,
+                neighbors: [1, 2, 3, 0],
+                parity: TetrahedralParity::Anticlockwise,
+            }]
+        );
+        assert_eq!(
+            down.stereo().tetrahedral[0].parity,
+            TetrahedralParity::Clockwise
+        );
+        assert!(either.stereo().is_empty());
+        assert!(plain.stereo().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tetrahedral_from_3d() -> Result<(), MoleculeError> {
+        let mut mol = bromochlorofluoromethane(BondStereo::None)?;
+        mol.atoms_mut()[3].position.z = 0.8;
+        let mut mirror = mol.clone();
+        mirror.atoms_mut()[3].position.z = -0.8;
+
+        assign_stereo_from_coordinates(&mut mol);
+        assign_stereo_from_coordinates(&mut mirror);
+
+        assert_eq!(
+            mol.stereo().tetrahedral[0].parity,
+            TetrahedralParity::Anticlockwise
+        );
+        assert_eq!(
+            mirror.stereo().tetrahedral[0].parity,
+            TetrahedralParity::Clockwise
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tetrahedral_needs_different_neighbors() -> Result<(), MoleculeError> {
+        // Dichlorofluoromethane, and propan-2-ol (where the methyl groups only differ by index)
+        let mut mol = bromochlorofluoromethane(BondStereo::Up)?;
+        mol.atoms_mut()[3] = Atom::from_symbol("Cl")?;
+        mol.atoms_mut()[3].position = Point3d::new(0.87, -0.5, 0.0);
+        let mut propanol = molecule_2d(
+            &["C", "O", "C", "C"],
+            &[(0.0, 0.0), (0.0, 1.0), (-0.87, -0.5), (0.87, -0.5)],
+            &[
+                (0, 1, BondType::single(), BondStereo::None),
+                (0, 2, BondType::single(), BondStereo::None),
+                (0, 3, BondType::single(), BondStereo::Up),
+            ],
+        )?;
+
+        assign_stereo_from_coordinates(&mut mol);
+        assign_stereo_from_coordinates(&mut propanol);
+
+        assert!(mol.stereo().is_empty());
+        assert!(propanol.stereo().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn double_bonds_from_coordinates() -> Result<(), MoleculeError> {
+        // 1,2-difluoroethene drawn as cis, and as trans with a crossed double bond
+        let bonds = |stereo| {
+            vec![
+                (0, 1, BondType::double(), stereo),
+                (0, 2, BondType::single(), BondStereo::None),
+                (1, 3, BondType::single(), BondStereo::None),
+            ]
+        };
+        let mut cis = molecule_2d(
+            &["C", "C", "F", "F"],
+            &[(0.0, 0.0), (1.0, 0.0), (-0.5, 0.87), (1.5, 0.87)],
+            &bonds(BondStereo::None),
+        )?;
+        let mut trans = molecule_2d(
+            &["C", "C", "F", "F"],
+            &[(0.0, 0.0), (1.0, 0.0), (-0.5, 0.87), (1.5, -0.87)],
+            &bonds(BondStereo::None),
+        )?;
+        let mut either = molecule_2d(
+            &["C", "C", "F", "F"],
+            &[(0.0, 0.0), (1.0, 0.0), (-0.5, 0.87), (1.5, -0.87)],
+            &bonds(BondStereo::CisTransEither),
+        )?;
+
+        assign_stereo_from_coordinates(&mut cis);
+        assign_stereo_from_coordinates(&mut trans);
+        assign_stereo_from_coordinates(&mut either);
+
+        assert_eq!(
+            cis.stereo().double_bonds,
+            vec![DoubleBondStereo {
+                bond: 0,
+                neighbors: [2, 3],
+                configuration: DoubleBondConfiguration::Cis,
+            }]
+        );
+        assert_eq!(
+            trans
+                .stereo()
+                .double_bond_at(0)
+                .map(|stereo| stereo.configuration),
+            Some(DoubleBondConfiguration::Trans)
+        );
+        assert!(either.stereo().is_empty());
+
+        // 1,1-difluoroethene has no configuration
+        let mut mol = molecule_2d(
+            &["C", "C", "F", "F"],
+            &[(0.0, 0.0), (1.0, 0.0), (-0.5, 0.87), (-0.5, -0.87)],
+            &[
+                (0, 1, BondType::double(), BondStereo::None),
+                (0, 2, BondType::single(), BondStereo::None),
+                (0, 3, BondType::single(), BondStereo::None),
+            ],
+        )?;
+        assign_stereo_from_coordinates(&mut mol);
+        assert!(mol.stereo().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tetrahedral_from_parity() -> Result<(), MoleculeError> {
+        let mut mol = bromochlorofluoromethane(BondStereo::None)?;
+        mol.atoms_mut()[0].set_property(AtomProperty::StereoParity, 1u32);
+
+        assign_stereo_from_parity(&mut mol);
+
+        assert_eq!(
+            mol.stereo().tetrahedral,
+            vec![TetrahedralStereo {
+                center: 0,
+                neighbors: [1, 2, 3, 0],
+                parity: TetrahedralParity::Clockwise,
+            }]
+        );
+
+        // Parities agree with the wedges they are normally written with
+        let mut mol = bromochlorofluoromethane(BondStereo::Up)?;
+        mol.atoms_mut()[0].set_property(AtomProperty::StereoParity, 2u32);
+        let mut from_parity = mol.clone();
+        assign_stereo_from_coordinates(&mut mol);
+        assign_stereo_from_parity(&mut from_parity);
+        assert_eq!(mol.stereo(), from_parity.stereo());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tetrahedral_to_smiles() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::io::{read_mol, read_smiles, write_smiles, SmilesOptions};
+        use crate::mol::assign_cip_labels;
+
+        for (wedge, smiles) in &[
+            (BondStereo::Up, "[C@@H](F)(Cl)Br"),
+            (BondStereo::Down, "[C@H](F)(Cl)Br"),
+        ] {
+            let mut mol = bromochlorofluoromethane(*wedge)?;
+            assign_stereo_from_coordinates(&mut mol);
+            assert_eq!(&write_smiles(&mol, SmilesOptions::default())?, smiles);
+
+            let expected = &mol.stereo().tetrahedral[0];
+            assert_eq!(
+                read_smiles(smiles)?.stereo().tetrahedral[0].parity_for(&expected.neighbors),
+                Some(expected.parity)
+            );
+        }
+
+        // L-alanine from a Molfile keeps its configuration through SMILES
+        let mut mol = read_mol(std::fs::File::open("./test_files/alanine_v2000.mol")?)?;
+        let mut roundtrip = read_smiles(&write_smiles(&mol, SmilesOptions::default())?)?;
+        assign_cip_labels(&mut mol);
+        assign_cip_labels(&mut roundtrip);
+        let labels = |mol: &Molecule| -> Vec<String> {
+            mol.atoms()
+                .iter()
+                .filter_map(|atom| atom.get_property_as_string(&AtomProperty::CipLabel))
+                .collect()
+        };
+        assert_eq!(labels(&mol), vec!["S".to_string()]);
+        assert_eq!(labels(&roundtrip), labels(&mol));
+
+        Ok(())
+    }
+
+    #[test]
+    fn remap() {
+        let mut stereo = Stereo {
+            tetrahedral: vec![
+                TetrahedralStereo {
+                    center: 1,
+                    neighbors: [0, 2, 3, 1],
+                    parity: TetrahedralParity::Clockwise,
+                },
+                TetrahedralStereo {
+                    center: 3,
+                    neighbors: [1, 4, 5, 6],
+                    parity: TetrahedralParity::Clockwise,
+                },
+            ],
+            double_bonds: vec![DoubleBondStereo {
+                bond: 2,
+                neighbors: [2, 4],
+                configuration: DoubleBondConfiguration::Trans,
+            }],
+            groups: vec![
+                StereoGroup {
+                    kind: StereoGroupKind::Or,
+                    number: 1,
+                    atoms: vec![0],
+                },
+                StereoGroup {
+                    kind: StereoGroupKind::And,
+                    number: 2,
+                    atoms: vec![0, 3],
+                },
+            ],
+            chiral: true,
+        };
+
+        stereo.remap(
+            &[None, Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)],
+            &[None, Some(0), Some(1)],
+        );
+
+        assert!(stereo.tetrahedral_at(1).is_none());
+        assert_eq!(stereo.tetrahedral_at(2).unwrap().neighbors, [0, 3, 4, 5]);
+        assert_eq!(stereo.double_bond_at(1).unwrap().neighbors, [1, 3]);
+        assert_eq!(
+            stereo.groups,
+            vec![StereoGroup {
+                kind: StereoGroupKind::And,
+                number: 2,
+                atoms: vec![2],
+            }]
+        );
+        assert_eq!(stereo.group_of(2).unwrap().kind, StereoGroupKind::And);
+        assert!(stereo.chiral);
+    }
+}
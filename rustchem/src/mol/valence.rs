@@ -0,0 +1,259 @@
+use super::{AtomIndex, AtomProperty, BondType, Element, Molecule};
+
+// Elements that take implicit hydrogens to fill their valence; metals and noble gases do not
+const IMPLICIT_HYDROGEN_SYMBOLS: [&str; 17] = [
+    "H", "B", "C", "N", "O", "F", "Si", "P", "S", "Cl", "Ge", "As", "Se", "Br", "Te", "I", "At",
+];
+
+/// Returns the valences an atom of `element` with the given charge normally takes, in increasing
+/// order.
+///
+/// Charged atoms take the valences of the element they are isoelectronic with (so N+ is like C,
+/// and O- like F). Atoms that do not take implicit hydrogens have no normal valences.
+pub fn allowed_valences(element: &Element, formal_charge: i32) -> &'static [u32] {
+    if !IMPLICIT_HYDROGEN_SYMBOLS.contains(&element.symbol.as_str()) {
+        return &[];
+    }
+
+    let isoelectronic = element.atomic_number as i32 - formal_charge;
+    match Element::from_atomic_number(isoelectronic.max(0) as u32) {
+        Ok(isoelectronic)
+            if IMPLICIT_HYDROGEN_SYMBOLS.contains(&isoelectronic.symbol.as_str())
+                || isoelectronic.group == Some(18) =>
+        {
+            &isoelectronic.default_valences
+        }
+        _ => &[],
+    }
+}
+
+impl Molecule {
+    /// Returns the number of implicit hydrogens on the atom.
+    ///
+    /// An explicit hydrogen count on the atom takes priority, followed by an explicit valence.
+    /// Otherwise the atom takes the lowest of its allowed valences that fits its bonds and radical
+    /// electrons, and hydrogens make up the difference. Query atoms only have the hydrogens given
+    /// explicitly.
+    ///
+    /// Like the neighbour queries, this panics if `atom` is not an atom of the molecule.
+    pub fn implicit_hydrogen_count(&self, atom: AtomIndex) -> u32 {
+        let explicit = &self.atoms()[atom];
+        match (explicit.hydrogen_count, explicit.valence) {
+            (Some(hydrogen_count), _) => hydrogen_count,
+            (None, Some(valence)) => valence.saturating_sub(self.bond_order_sum(atom)),
+            (None, None) => self.default_implicit_hydrogen_count(atom),
+        }
+    }
+
+    /// Returns the number of implicit hydrogens the valence model gives the atom, ignoring any
+    /// explicit hydrogen count or valence.
+    pub fn default_implicit_hydrogen_count(&self, atom: AtomIndex) -> u32 {
+        let explicit = &self.atoms()[atom];
+        if explicit.properties.contains_key(&AtomProperty::AtomList) {
+            return 0;
+        }
+
+        let used = self.bond_order_sum(atom) + explicit.radical.electrons();
+        allowed_valences(explicit.element, explicit.formal_charge)
+            .iter()
+            .find(|valence| **valence >= used)
+            .map_or(0, |valence| valence - used)
+    }
+
+    /// Returns the number of hydrogens on the atom, both implicit and as explicit neighbours.
+    pub fn total_hydrogen_count(&self, atom: AtomIndex) -> u32 {
+        let explicit_hydrogens = self
+            .neighbors(atom)
+            .filter(|neighbor| self.atoms()[*neighbor].element.atomic_number == 1)
+            .count() as u32;
+        self.implicit_hydrogen_count(atom) + explicit_hydrogens
+    }
+
+    /// Returns the sum of the orders of the atom's bonds.
+    ///
+    /// Aromatic bonds count as single bonds, plus one for the double bond an aromatic atom shares
+    /// with its ring if its valence leaves room for one. Query bonds count as single bonds.
+    pub fn bond_order_sum(&self, atom: AtomIndex) -> u32 {
+        let (bond_order_sum, has_aromatic_bond) = self.kekule_bond_order_sum(atom);
+        if has_aromatic_bond && self.has_room_for_double_bond(atom) {
+            bond_order_sum + 1
+        } else {
+            bond_order_sum
+        }
+    }
+
+    /// Returns the valence of the atom: its bond orders and implicit hydrogens.
+    pub fn valence(&self, atom: AtomIndex) -> u32 {
+        self.bond_order_sum(atom) + self.implicit_hydrogen_count(atom)
+    }
+
+    /// Returns whether an atom's valence leaves room for a double bond in place of one of its
+    /// aromatic bonds. Only hydrogens given explicitly count towards its valence.
+    pub(super) fn has_room_for_double_bond(&self, atom: AtomIndex) -> bool {
+        let (bond_order_sum, _) = self.kekule_bond_order_sum(atom);
+        let explicit = &self.atoms()[atom];
+        let used =
+            bond_order_sum + explicit.hydrogen_count.unwrap_or(0) + explicit.radical.electrons();
+
+        match explicit.valence {
+            Some(valence) => valence > used,
+            None => allowed_valences(explicit.element, explicit.formal_charge)
+                .iter()
+                .find(|valence| **valence >= used)
+                .is_some_and(|valence| *valence > used),
+        }
+    }
+
+    // Bond order sum with aromatic bonds counted as single bonds, and whether there were any
+    fn kekule_bond_order_sum(&self, atom: AtomIndex) -> (u32, bool) {
+        let mut has_aromatic_bond = false;
+        let bond_order_sum = self
+            .bonds_of(atom)
+            .map(|bond| match self.bonds()[bond].bond_type {
+                BondType::Covalent(order) if order > 0 => order as u32,
+                BondType::Aromatic => {
+                    has_aromatic_bond = true;
+                    1
+                }
+                _ => 1,
+            })
+            .sum();
+        (bond_order_sum, has_aromatic_bond)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{read_smiles, ParseError};
+    use crate::mol::{Atom, Bond, MoleculeError, Radical};
+
+    fn implicit_hydrogens(molecule: &Molecule) -> Vec<u32> {
+        (0..molecule.atoms().len())
+            .map(|atom| molecule.implicit_hydrogen_count(atom))
+            .collect()
+    }
+
+    #[test]
+    fn allowed_valences_charged() -> Result<(), MoleculeError> {
+        let carbon = Element::from_symbol("C")?;
+        let nitrogen = Element::from_symbol("N")?;
+        let sulfur = Element::from_symbol("S")?;
+        let chlorine = Element::from_symbol("Cl")?;
+
+        assert_eq!(allowed_valences(carbon, 0), &[4]);
+        assert_eq!(allowed_valences(carbon, 1), &[3]);
+        assert_eq!(allowed_valences(carbon, -1), &[3, 5]);
+        assert_eq!(allowed_valences(nitrogen, 0), &[3, 5]);
+        assert_eq!(allowed_valences(nitrogen, 1), &[4]);
+        assert_eq!(allowed_valences(nitrogen, -1), &[2]);
+        assert_eq!(allowed_valences(sulfur, 0), &[2, 4, 6]);
+        assert_eq!(allowed_valences(sulfur, 1), &[3, 5]);
+        assert_eq!(allowed_valences(chlorine, -1), &[0]);
+        assert!(allowed_valences(Element::from_symbol("Fe")?, 0).is_empty());
+        assert!(allowed_valences(Element::from_symbol("B")?, 1).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn implicit_hydrogen_count() -> Result<(), ParseError> {
+        let mol = read_smiles("CC(=O)O.C[N+](C)(C)C.CS(=O)(=O)C.[Na+].[Cl-]")?;
+        let mut mol = Molecule::from_graph(
+            mol.atoms()
+                .iter()
+                .map(|atom| {
+                    let mut atom = atom.clone();
+                    atom.hydrogen_count = None;
+                    atom
+                })
+                .collect(),
+            mol.bonds().to_vec(),
+        );
+
+        assert_eq!(
+            implicit_hydrogens(&mol),
+            vec![3, 0, 0, 1, 3, 0, 3, 3, 3, 3, 0, 0, 0, 3, 0, 0]
+        );
+
+        mol.atoms_mut()[1].radical = Radical::Doublet;
+        mol.atoms_mut()[0].radical = Radical::Triplet;
+
+        assert_eq!(mol.implicit_hydrogen_count(0), 1);
+        assert_eq!(mol.implicit_hydrogen_count(1), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn implicit_hydrogen_count_aromatic() -> Result<(), ParseError> {
+        let mol = read_smiles("c1ccncc1.c1cc[nH]c1.c1ccsc1.c1cc[n+](C)cc1.Cc1ccccc1")?;
+
+        assert_eq!(
+            implicit_hydrogens(&mol),
+            [
+                vec![1, 1, 1, 0, 1, 1],
+                vec![1, 1, 1, 1, 1],
+                vec![1, 1, 1, 0, 1],
+                vec![1, 1, 1, 0, 3, 1, 1],
+                vec![3, 0, 1, 1, 1, 1, 1],
+            ]
+            .concat()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_overrides() -> Result<(), MoleculeError> {
+        let mut mol = Molecule::new();
+        mol.add_atom(Atom::from_symbol("C")?);
+        mol.add_atom(Atom::from_symbol("N")?);
+        mol.add_bond(Bond::new(0, 1, BondType::single()))?;
+
+        assert_eq!(implicit_hydrogens(&mol), vec![3, 2]);
+
+        mol.atoms_mut()[0].valence = Some(2);
+        mol.atoms_mut()[1].valence = Some(0);
+
+        assert_eq!(implicit_hydrogens(&mol), vec![1, 0]);
+
+        mol.atoms_mut()[0].hydrogen_count = Some(3);
+
+        assert_eq!(implicit_hydrogens(&mol), vec![3, 0]);
+        assert_eq!(mol.default_implicit_hydrogen_count(0), 3);
+        assert_eq!(mol.default_implicit_hydrogen_count(1), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn total_hydrogen_count() -> Result<(), ParseError> {
+        let mol = read_smiles("[H]C([H])O")?;
+
+        assert_eq!(mol.implicit_hydrogen_count(1), 1);
+        assert_eq!(mol.total_hydrogen_count(1), 3);
+        assert_eq!(mol.total_hydrogen_count(3), 1);
+        assert_eq!(mol.valence(1), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hypervalent() -> Result<(), ParseError> {
+        // More bonds than any allowed valence leaves no room for hydrogens
+        let mol = read_smiles("FC(F)(F)(F)F.FP(F)(F)(F)F")?;
+
+        assert_eq!(mol.implicit_hydrogen_count(1), 0);
+        assert_eq!(mol.implicit_hydrogen_count(7), 0);
+        assert_eq!(mol.valence(1), 5);
+
+        // Nitrogen takes a valence of 5 after 3, as in the OpenSMILES organic subset
+        let mol = read_smiles("CN(=O)=O.CN(C)(C)C")?;
+
+        assert_eq!(mol.implicit_hydrogen_count(1), 0);
+        assert_eq!(mol.implicit_hydrogen_count(5), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,367 @@
+use super::{allowed_valences, AtomIndex, AtomProperty, BondIndex, Element, Molecule};
+use std::collections::HashMap;
+
+// Atoms closer than this (in the units of the coordinates, normally Ångströms) are taken to be
+// at the same position
+const OVERLAP_DISTANCE: f64 = 0.1;
+
+/// How serious a `ValidationIssue` is. Errors are ordered after warnings.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Severity {
+    /// The structure is usable, but probably not what was intended
+    Warning,
+    /// The structure is not chemically or structurally valid
+    Error,
+}
+
+/// The kind of problem found by `validate`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ValidationCode {
+    /// An atom has more bonds, hydrogens and radical electrons than any of its allowed valences
+    Hypervalent,
+    /// An atom has a positive charge greater than its number of electrons, or a negative charge
+    /// beyond the end of the periodic table
+    ImpossibleCharge,
+    /// An atom's isotope does not exist for its element
+    UnknownIsotope,
+    /// A bond refers to an atom index that is out of range
+    DanglingBond,
+    /// A bond joins an atom to itself
+    SelfBond,
+    /// Two or more bonds join the same pair of atoms
+    DuplicateBond,
+    /// Two atoms that are not bonded to each other share the same position
+    OverlappingAtoms,
+    /// A bond joins two atoms at the same position
+    ZeroLengthBond,
+}
+
+/// A problem found by `validate`, with the atoms and bonds involved.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: ValidationCode,
+    pub atoms: Vec<AtomIndex>,
+    pub bonds: Vec<BondIndex>,
+}
+
+impl ValidationIssue {
+    fn new(code: ValidationCode, atoms: Vec<AtomIndex>, bonds: Vec<BondIndex>) -> ValidationIssue {
+        let severity = match code {
+            ValidationCode::OverlappingAtoms | ValidationCode::ZeroLengthBond => Severity::Warning,
+            _ => Severity::Error,
+        };
+        ValidationIssue {
+            severity,
+            code,
+            atoms,
+            bonds,
+        }
+    }
+}
+
+/// Checks the molecule for problems in its atoms, bonds and coordinates.
+///
+/// Issues are returned grouped by atom checks, then bond checks, then coordinate checks. Bonds
+/// that refer to missing atoms or join an atom to itself are left out of the other checks, and
+/// coordinates are only checked if some atom is away from the origin. Query atoms are not
+/// checked for hypervalence.
+pub fn validate(molecule: &Molecule) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    validate_atoms(molecule, &mut issues);
+    validate_bonds(molecule, &mut issues);
+    validate_coordinates(molecule, &mut issues);
+    issues
+}
+
+fn validate_atoms(molecule: &Molecule, issues: &mut Vec<ValidationIssue>) {
+    for (index, atom) in molecule.atoms().iter().enumerate() {
+        // Dummy atoms stand for no one element, so take any charge
+        let atomic_number = atom.element.atomic_number as i64;
+        let electrons = atomic_number - atom.formal_charge as i64;
+        let is_impossible_charge = atom.formal_charge as i64 > atomic_number
+            || electrons > atomic_number && Element::from_atomic_number(electrons as u32).is_err();
+        if !atom.element.is_dummy() && is_impossible_charge {
+            issues.push(ValidationIssue::new(
+                ValidationCode::ImpossibleCharge,
+                vec![index],
+                Vec::new(),
+            ));
+        }
+
+        if atom.get_isotope().is_err() {
+            issues.push(ValidationIssue::new(
+                ValidationCode::UnknownIsotope,
+                vec![index],
+                Vec::new(),
+            ));
+        }
+
+        if atom.properties.contains_key(&AtomProperty::AtomList) {
+            continue;
+        }
+
+        let used = molecule.bond_order_sum(index)
+            + atom.hydrogen_count.unwrap_or(0)
+            + atom.radical.electrons();
+        if let Some(max_valence) = allowed_valences(atom.element, atom.formal_charge).last() {
+            if used > *max_valence {
+                issues.push(ValidationIssue::new(
+                    ValidationCode::Hypervalent,
+                    vec![index],
+                    Vec::new(),
+                ));
+            }
+        }
+    }
+}
+
+fn validate_bonds(molecule: &Molecule, issues: &mut Vec<ValidationIssue>) {
+    let num_atoms = molecule.atoms().len();
+    let mut bonds_between: HashMap<(AtomIndex, AtomIndex), Vec<BondIndex>> = HashMap::new();
+
+    for (index, bond) in molecule.bonds().iter().enumerate() {
+        let (from, to) = (bond.from_atom_id, bond.to_atom_id);
+        if from >= num_atoms || to >= num_atoms {
+            let atoms = [from, to]
+                .iter()
+                .copied()
+                .filter(|atom| *atom < num_atoms)
+                .collect();
+            issues.push(ValidationIssue::new(
+                ValidationCode::DanglingBond,
+                atoms,
+                vec![index],
+            ));
+        } else if from == to {
+            issues.push(ValidationIssue::new(
+                ValidationCode::SelfBond,
+                vec![from],
+                vec![index],
+            ));
+        } else {
+            bonds_between
+                .entry((from.min(to), from.max(to)))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut duplicates = bonds_between
+        .into_iter()
+        .filter(|(_, bonds)| bonds.len() > 1)
+        .collect::<Vec<((AtomIndex, AtomIndex), Vec<BondIndex>)>>();
+    duplicates.sort_unstable_by_key(|(_, bonds)| bonds[0]);
+    for ((from, to), bonds) in duplicates {
+        issues.push(ValidationIssue::new(
+            ValidationCode::DuplicateBond,
+            vec![from, to],
+            bonds,
+        ));
+    }
+}
+
+fn validate_coordinates(molecule: &Molecule, issues: &mut Vec<ValidationIssue>) {
+    let atoms = molecule.atoms();
+    let has_coordinates = atoms
+        .iter()
+        .any(|atom| atom.position.x != 0.0 || atom.position.y != 0.0 || atom.position.z != 0.0);
+    if !has_coordinates {
+        return;
+    }
+
+    for (index, bond) in molecule.bonds().iter().enumerate() {
+        let (from, to) = (bond.from_atom_id, bond.to_atom_id);
+        if from < atoms.len()
+            && to < atoms.len()
+            && from != to
+            && atoms[from].position.distance(atoms[to].position) < OVERLAP_DISTANCE
+        {
+            issues.push(ValidationIssue::new(
+                ValidationCode::ZeroLengthBond,
+                vec![from, to],
+                vec![index],
+            ));
+        }
+    }
+
+    // Sweep along x so that only atoms with nearby x coordinates need comparing
+    let mut by_x = (0..atoms.len()).collect::<Vec<AtomIndex>>();
+    by_x.sort_by(|a, b| atoms[*a].position.x.total_cmp(&atoms[*b].position.x));
+
+    let mut overlapping = Vec::new();
+    for (start, atom) in by_x.iter().enumerate() {
+        let position = atoms[*atom].position;
+        for other in &by_x[start + 1..] {
+            let other_position = atoms[*other].position;
+            if other_position.x - position.x >= OVERLAP_DISTANCE {
+                break;
+            }
+            if position.distance(other_position) < OVERLAP_DISTANCE
+                && molecule.bond_between(*atom, *other).is_none()
+            {
+                overlapping.push(((*atom).min(*other), (*atom).max(*other)));
+            }
+        }
+    }
+
+    overlapping.sort_unstable();
+    for (atom, other) in overlapping {
+        issues.push(ValidationIssue::new(
+            ValidationCode::OverlappingAtoms,
+            vec![atom, other],
+            Vec::new(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::read_smiles;
+    use crate::mol::{Atom, Bond, BondType, MoleculeError, Point3d, Radical};
+
+    fn codes(issues: &[ValidationIssue]) -> Vec<ValidationCode> {
+        issues.iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn valid_molecule() -> Result<(), Box<dyn std::error::Error>> {
+        let mol = read_smiles("CC(=O)[O-].C[N+](C)(C)C.CS(=O)(=O)C.c1cc[nH]c1.[13CH4].[Na+]")?;
+
+        assert_eq!(validate(&mol), Vec::new());
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_atoms() -> Result<(), Box<dyn std::error::Error>> {
+        let mut mol = read_smiles("FC(F)(F)(F)F.[CH3]C.[H+2].O.[14N].C.C")?;
+        mol.atoms_mut()[11].isotope = Some(40);
+        mol.atoms_mut()[12].radical = Radical::Doublet;
+        mol.atoms_mut()[12].hydrogen_count = Some(4);
+
+        let issues = validate(&mol);
+
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ValidationCode::Hypervalent,
+                ValidationCode::ImpossibleCharge,
+                ValidationCode::UnknownIsotope,
+                ValidationCode::Hypervalent,
+            ]
+        );
+        assert_eq!(issues[0].atoms, vec![1]);
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(issues[1].atoms, vec![8]);
+        assert_eq!(issues[2].atoms, vec![11]);
+        assert_eq!(issues[3].atoms, vec![12]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_charges() -> Result<(), Box<dyn std::error::Error>> {
+        // A bare proton, wildcards and ions with electrons left are all possible
+        let mol = read_smiles("[H+].*.[*+].[Li+].[O-2].[Li+3]")?;
+        assert!(validate(&mol).is_empty());
+
+        let mut mol = read_smiles("[Li+4].[Li-]")?;
+        mol.atoms_mut()[1].formal_charge = -200;
+        let issues = validate(&mol);
+
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ValidationCode::ImpossibleCharge,
+                ValidationCode::ImpossibleCharge
+            ]
+        );
+        assert_eq!(issues[0].atoms, vec![0]);
+        assert_eq!(issues[1].atoms, vec![1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_bonds() -> Result<(), MoleculeError> {
+        let atoms = vec![
+            Atom::from_symbol("C")?,
+            Atom::from_symbol("C")?,
+            Atom::from_symbol("O")?,
+        ];
+        let bonds = vec![
+            Bond::new(0, 1, BondType::single()),
+            Bond::new(1, 5, BondType::single()),
+            Bond::new(2, 2, BondType::single()),
+            Bond::new(1, 0, BondType::single()),
+            Bond::new(1, 2, BondType::single()),
+        ];
+        let mol = Molecule::from_graph(atoms, bonds);
+
+        let issues = validate(&mol);
+
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ValidationCode::DanglingBond,
+                ValidationCode::SelfBond,
+                ValidationCode::DuplicateBond,
+            ]
+        );
+        assert_eq!(issues[0].atoms, vec![1]);
+        assert_eq!(issues[0].bonds, vec![1]);
+        assert_eq!(issues[1].atoms, vec![2]);
+        assert_eq!(issues[1].bonds, vec![2]);
+        assert_eq!(issues[2].atoms, vec![0, 1]);
+        assert_eq!(issues[2].bonds, vec![0, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_coordinates() -> Result<(), MoleculeError> {
+        let mut mol = Molecule::new();
+        for (x, y, z) in &[
+            (0.0, 0.0, 0.0),
+            (1.5, 0.0, 0.0),
+            (1.5, 0.0, 0.05),
+            (3.0, 1.0, 0.0),
+            (3.0, 1.0, 0.0),
+        ] {
+            let mut atom = Atom::from_symbol("C")?;
+            atom.position = Point3d::new(*x, *y, *z);
+            mol.add_atom(atom);
+        }
+        mol.add_bond(Bond::new(0, 1, BondType::single()))?;
+        mol.add_bond(Bond::new(1, 2, BondType::single()))?;
+
+        let issues = validate(&mol);
+
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ValidationCode::ZeroLengthBond,
+                ValidationCode::OverlappingAtoms
+            ]
+        );
+        assert_eq!(issues[0].atoms, vec![1, 2]);
+        assert_eq!(issues[0].bonds, vec![1]);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[1].atoms, vec![3, 4]);
+        assert_eq!(issues[1].bonds, Vec::new());
+
+        // Molecules without coordinates have all their atoms at the origin
+        let mol = Molecule::from_graph(
+            mol.atoms()
+                .iter()
+                .map(|atom| Atom::new(atom.element))
+                .collect(),
+            Vec::new(),
+        );
+        assert_eq!(validate(&mol), Vec::new());
+
+        Ok(())
+    }
+}